@@ -651,6 +651,15 @@ function describe_raire_result(output_div,explanation_div,data) {
         if (data.solution.Ok.warning_trim_timed_out) {
             add(output_div,"p","warning").innerText="Warning : Trimming timed out. Some assertions may be redundant.";
         }
+        if (data.solution.Ok.warning_provisional) {
+            add(output_div,"p","warning").innerText="Warning : Provisional result from a partial count. It may change when counting completes.";
+        }
+        if (data.solution.Ok.truncated) {
+            add(output_div,"p","warning").innerText="Warning : Only the hardest assertions are shown. They do not prove the winner and must not be used for an audit.";
+        }
+        if (data.solution.Ok.conditional_on_tie_break) {
+            add(output_div,"p","warning").innerText="Warning : The winner was determined by a tie resolution. Assertions with a zero margin hold only because of it.";
+        }
         function describe_time(what,time_taken) {
             if (time_taken) {
                 let time_desc = time_taken.seconds>0.1?Number(time_taken.seconds).toFixed(1)+" seconds":Number(time_taken.seconds*1000).toFixed(2)+" milliseconds";
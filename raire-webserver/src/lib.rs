@@ -0,0 +1,102 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::{Deserialize, Serialize};
+
+/// Server-wide defaults for fields missing from the audit in a problem. Fields given in the problem take precedence.
+#[derive(Clone,Copy,Debug,Default)]
+pub struct AuditDefaults {
+    /// Used for the `confidence` of BRAVO and MACRO audits.
+    pub confidence : Option<f64>,
+    /// If true, `total_auditable_ballots` is the total number of votes.
+    pub total_ballots_from_votes : bool,
+}
+
+impl AuditDefaults {
+    /// Fill in missing fields in the audit of a problem in JSON form.
+    fn apply(&self,problem:&mut serde_json::Value) {
+        let total_votes : u64 = problem.get("votes").and_then(|v|v.as_array()).map(|votes|votes.iter().filter_map(|v|v.get("n").and_then(|n|n.as_u64())).sum()).unwrap_or(0);
+        if let Some(audit) = problem.get_mut("audit").and_then(|a|a.as_object_mut()) {
+            if let Some(confidence) = self.confidence {
+                let has_confidence = matches!(audit.get("type").and_then(|t|t.as_str()),Some("BRAVO")|Some("MACRO"));
+                if has_confidence && !audit.contains_key("confidence") { audit.insert("confidence".to_string(),confidence.into()); }
+            }
+            if self.total_ballots_from_votes && !audit.contains_key("total_auditable_ballots") {
+                audit.insert("total_auditable_ballots".to_string(),total_votes.into());
+            }
+        }
+    }
+}
+
+/// Handler for `POST /raire`: solve a problem, after filling in any missing audit fields from `defaults`.
+pub async fn raire(
+    State(defaults): State<AuditDefaults>,
+    // this argument tells axum to parse the request body
+    // as JSON, which is converted to a RaireProblem after filling in defaults.
+    Json(mut problem): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<raire::RaireSolution>),(StatusCode, String)> {
+    defaults.apply(&mut problem);
+    let problem : raire::RaireProblem = serde_json::from_value(problem).map_err(|e|(StatusCode::UNPROCESSABLE_ENTITY,format!("Failed to deserialize the JSON body into the target type: {e}")))?;
+    let solution = solve_cancelling_on_drop(problem,raire::RaireProblem::solve_cancellable).await.map_err(|e|(StatusCode::INTERNAL_SERVER_ERROR,e.to_string()))?;
+    // this will be converted into a JSON response
+    // with a status code of `200 OK`
+    Ok((StatusCode::OK, Json(solution)))
+}
+
+/// Run `solve` on `problem` in a blocking thread, with a flag that is set if the returned future is dropped before it finishes.
+/// If the client disconnects, the request future is dropped, which cancels the solve rather than letting it run to its time limit.
+pub async fn solve_cancelling_on_drop(problem:raire::RaireProblem,solve:impl FnOnce(raire::RaireProblem,Arc<AtomicBool>)->raire::RaireSolution+Send+'static) -> Result<raire::RaireSolution,tokio::task::JoinError> {
+    let cancel = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+    let flag = cancel.0.clone();
+    let solution = tokio::task::spawn_blocking(move||solve(problem,flag)).await;
+    drop(cancel);
+    solution
+}
+
+/// Sets the flag when dropped.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) { self.0.store(true,Ordering::Relaxed); }
+}
+
+/// A claimed solution to a problem, submitted for verification.
+#[derive(Deserialize)]
+pub struct VerificationRequest {
+    pub problem : raire::RaireProblem,
+    pub result : raire::raire_algorithm::RaireResult,
+}
+
+/// Whether a submitted solution is valid, and if not, why not.
+#[derive(Serialize,Debug)]
+pub struct VerificationVerdict {
+    pub valid : bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error : Option<raire::RaireError>,
+}
+
+/// Handler for `POST /raire/verify`: check that a claimed result is a valid solution to a problem.
+pub async fn verify(
+    Json(request): Json<VerificationRequest>,
+) -> (StatusCode, Json<VerificationVerdict>) {
+    let verdict = match request.problem.verify_solution(&request.result) {
+        Ok(()) => VerificationVerdict{valid:true,error:None},
+        Err(e) => VerificationVerdict{valid:false,error:Some(e)},
+    };
+    (StatusCode::OK, Json(verdict))
+}
@@ -11,13 +11,13 @@
 
 use axum::{
     routing::{post},
-    http::StatusCode,
-    Json, Router,
+    Router,
 };
 use std::net::IpAddr;
 use tower_http::services::ServeDir;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use clap::Parser;
+use raire_webserver::{AuditDefaults, raire, verify};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -31,8 +31,16 @@ struct CliOptions {
     #[arg(short, long)]
     ip : Option<IpAddr>,
 
-}
+    /// The confidence to use for BRAVO and MACRO audits in problems that do not specify it.
+    /// A confidence given in the problem takes precedence.
+    #[arg(long, env = "RAIRE_DEFAULT_CONFIDENCE")]
+    default_confidence : Option<f64>,
 
+    /// For audits in problems that do not specify `total_auditable_ballots`, use the total number of votes.
+    /// A value given in the problem takes precedence.
+    #[arg(long, env = "RAIRE_DEFAULT_TOTAL_BALLOTS_FROM_VOTES")]
+    default_total_ballots_from_votes : bool,
+}
 
 #[tokio::main]
 async fn main() {
@@ -47,12 +55,16 @@ async fn main() {
         .init();
 
     let serve_dir = ServeDir::new("WebContent");
+    let defaults = AuditDefaults{confidence:args.default_confidence,total_ballots_from_votes:args.default_total_ballots_from_votes};
 
     // build our application with a route
     let app = Router::new()
         // `POST /raire` goes to `raire`
         .route("/raire", post(raire))
-        .nest_service("/",serve_dir);
+        // `POST /raire/verify` goes to `verify`
+        .route("/raire/verify", post(verify))
+        .nest_service("/",serve_dir)
+        .with_state(defaults);
 
 
     let address = format!("127.0.0.1:{}",args.socket.unwrap_or(3000));
@@ -60,15 +72,3 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(&address).await.unwrap();
     axum::serve(listener,app).await.unwrap();
 }
-
-
-async fn raire(
-    // this argument tells axum to parse the request body
-    // as JSON into a `CreateUser` type
-    Json(problem): Json<raire::RaireProblem>,
-) -> (StatusCode, Json<raire::RaireSolution>) {
-    let solution = problem.solve();
-    // this will be converted into a JSON response
-    // with a status code of `201 Created`
-    (StatusCode::OK, Json(solution))
-}
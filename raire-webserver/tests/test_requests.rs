@@ -0,0 +1,105 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the request handlers of the webserver.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use raire::timeout::TimeoutCause;
+use raire_webserver::{raire, solve_cancelling_on_drop, verify, AuditDefaults, VerificationRequest, VerificationVerdict};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use serde_json::json;
+
+/// The example from the Guide to RAIRE, as JSON.
+fn problem() -> serde_json::Value {
+    json!({
+        "metadata" : { "candidates" : ["Alice","Bob","Chuan","Diego"] },
+        "num_candidates" : 4,
+        "votes" : [
+            { "n" : 5000, "prefs" : [2,1,0] },
+            { "n" : 1000, "prefs" : [1,2,3] },
+            { "n" : 1500, "prefs" : [3,0] },
+            { "n" : 4000, "prefs" : [0,3] },
+            { "n" : 2000, "prefs" : [3] }
+        ],
+        "winner" : 2,
+        "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 13500 }
+    })
+}
+
+async fn submit(result:serde_json::Value) -> VerificationVerdict {
+    let request : VerificationRequest = serde_json::from_value(json!({"problem":problem(),"result":result})).unwrap();
+    let (status,Json(verdict)) = verify(Json(request)).await;
+    assert_eq!(StatusCode::OK,status);
+    verdict
+}
+
+#[tokio::test]
+async fn test_verify() {
+    let (_,Json(solution)) = raire(State(AuditDefaults::default()),Json(problem())).await.unwrap();
+    let result = serde_json::to_value(solution.solution.unwrap()).unwrap();
+    let verdict = submit(result.clone()).await;
+    assert!(verdict.valid,"{:?}",verdict);
+    // Remove the assertion that Chuan NEB Bob, so Bob is no longer ruled out.
+    let mut insufficient = result.clone();
+    insufficient["assertions"].as_array_mut().unwrap().retain(|a|a["assertion"]!=json!({"type":"NEB","winner":2,"loser":1}));
+    let verdict = submit(insufficient).await;
+    assert!(!verdict.valid);
+    assert!(matches!(verdict.error,Some(raire::RaireError::InternalErrorDidntRuleOutLoser)));
+    // Claim that Alice NEB Bob, which is not true.
+    let mut false_assertion = result;
+    false_assertion["assertions"].as_array_mut().unwrap().push(json!({"assertion":{"type":"NEB","winner":0,"loser":1},"difficulty":1.0,"margin":1}));
+    let verdict = submit(false_assertion).await;
+    assert!(!verdict.valid);
+    assert!(matches!(verdict.error,Some(raire::RaireError::AssertionDoesNotHold(_))));
+}
+
+#[tokio::test]
+async fn test_audit_defaults() {
+    let mut bravo = problem();
+    bravo["audit"] = json!({ "type" : "BRAVO" });
+    let (status,_) = raire(State(AuditDefaults::default()),Json(bravo.clone())).await.unwrap_err();
+    assert_eq!(StatusCode::UNPROCESSABLE_ENTITY,status);
+    let defaults = AuditDefaults{confidence:Some(0.05),total_ballots_from_votes:true};
+    let (status,Json(solution)) = raire(State(defaults),Json(bravo.clone())).await.unwrap();
+    assert_eq!(StatusCode::OK,status);
+    let with_default = solution.solution.unwrap().difficulty;
+    // A confidence in the request takes precedence over the default.
+    bravo["audit"]["confidence"] = json!(0.1);
+    let (_,Json(solution)) = raire(State(defaults),Json(bravo.clone())).await.unwrap();
+    let with_request = solution.solution.unwrap().difficulty;
+    assert!(with_request<with_default);
+    bravo["audit"]["total_auditable_ballots"] = json!(13500);
+    let (_,Json(solution)) = raire(State(AuditDefaults::default()),Json(bravo)).await.unwrap();
+    assert_eq!(with_request,solution.solution.unwrap().difficulty);
+}
+
+#[tokio::test]
+async fn test_cancel_on_drop() {
+    let problem : raire::RaireProblem = serde_json::from_value(problem()).unwrap();
+    let (started_sender,started) = tokio::sync::oneshot::channel();
+    let (cause_sender,cause) = std::sync::mpsc::channel();
+    let request = solve_cancelling_on_drop(problem,move|problem,cancel|{
+        started_sender.send(()).unwrap();
+        // Don't start solving until the request has been dropped, so the problem being easy doesn't matter.
+        let give_up = Instant::now()+Duration::from_secs(10);
+        while !cancel.load(Ordering::Relaxed) && Instant::now()<give_up { std::thread::sleep(Duration::from_millis(1)); }
+        let solution = problem.solve_cancellable(cancel);
+        cause_sender.send(solution.timeout_cause).unwrap();
+        solution
+    });
+    tokio::select! {
+        _ = request => panic!("The solve should not finish until the request is dropped"),
+        _ = started => {},
+    }
+    // The request future has now been dropped mid-solve.
+    assert_eq!(TimeoutCause::Cancel,cause.recv_timeout(Duration::from_secs(20)).unwrap());
+}
@@ -11,10 +11,12 @@
 //! The types of assertions about the election, generally relative standings of various candidates
 
 
-use crate::audit_type::{AssertionDifficulty, AuditType};
+use crate::audit_type::{AssertionDifficulty, Audit, AuditType};
 use crate::irv::{BallotPaperCount, CandidateIndex, Votes};
+use crate::raire_algorithm::TieBreak;
 use serde::Deserialize;
 use serde::Serialize;
+use crate::RaireError;
 
 /// Assert that _winner_ beats _loser_ in a winner only audit satisfying the condition
 /// that _winner_ gets more first preference votes than _loser_ gets votes when all
@@ -39,8 +41,16 @@ impl NotEliminatedBefore {
         (difficulty,if tally_winner>=tally_loser {tally_winner-tally_loser} else {BallotPaperCount(0)})
     }
 
+    /// The tallies (winner,loser) used to evaluate this assertion. The winner's tally is first preferences only; the loser's tally is when only the winner and loser are continuing.
+    pub fn tallies(&self, votes:&Votes) -> (BallotPaperCount,BallotPaperCount) {
+        let tally_winner = votes.first_preference_only_tally(self.winner);
+        let tallies = votes.restricted_tallies(&[self.winner,self.loser]);
+        (tally_winner,tallies[1])
+    }
+
     /// Find the NEB assertion that best rules out the given candidate being the next eliminated, with later_in_pi being the other continuing candidates.
-    pub fn find_best_assertion<A:AuditType>(c:CandidateIndex, later_in_pi:&[CandidateIndex], votes:&Votes, audit:&A) -> Option<AssertionAndDifficulty> {
+    /// Equally difficult assertions are chosen between with `tie_break`, and failing that the first found (by candidate index) is used.
+    pub fn find_best_assertion<A:AuditType>(c:CandidateIndex, later_in_pi:&[CandidateIndex], votes:&Votes, audit:&A,tie_break:TieBreak) -> Option<AssertionAndDifficulty> {
         let mut best_difficulty = f64::MAX;
         let mut best_assertion : Option<NotEliminatedBefore> = None;
         let mut best_margin : BallotPaperCount = BallotPaperCount(0);
@@ -55,7 +65,7 @@ impl NotEliminatedBefore {
                     NotEliminatedBefore {winner:alt_c,loser:c}
                 };
                 let (difficulty,margin) = contest.difficulty(votes, audit);
-                if difficulty<best_difficulty {
+                if difficulty<best_difficulty || (difficulty==best_difficulty && difficulty.is_finite() && best_assertion.is_some_and(|best|tie_break.prefers(&Assertion::NEB(contest),&Assertion::NEB(best)))) {
                     best_difficulty=difficulty;
                     best_assertion=Some(contest);
                     best_margin=margin;
@@ -63,12 +73,13 @@ impl NotEliminatedBefore {
             }
         }
         if let Some(assertion) = best_assertion {
-            Some(AssertionAndDifficulty { assertion:Assertion::NEB(assertion), margin: best_margin, difficulty:best_difficulty })
+            Some(AssertionAndDifficulty { assertion:Assertion::NEB(assertion), margin: best_margin, difficulty:best_difficulty, tallies: None })
         } else {None}
     }
 
     /// Find the NEB assertion that best rules out the given candidate being the next eliminated, with later_in_pi being the other continuing candidates.
-    pub fn find_best_assertion_using_cache(c:CandidateIndex, later_in_pi:&[CandidateIndex],votes:&Votes,cache:&NotEliminatedBeforeCache) -> Option<AssertionAndDifficulty> {
+    /// Equally difficult assertions are chosen between with `tie_break`, and failing that the first found (by candidate index) is used.
+    pub fn find_best_assertion_using_cache(c:CandidateIndex, later_in_pi:&[CandidateIndex],votes:&Votes,cache:&NotEliminatedBeforeCache,tie_break:TieBreak) -> Option<AssertionAndDifficulty> {
         let mut best_difficulty = f64::MAX;
         let mut best_assertion : Option<NotEliminatedBefore> = None;
         let mut best_margin : BallotPaperCount = BallotPaperCount(0);
@@ -83,15 +94,15 @@ impl NotEliminatedBefore {
                     NotEliminatedBefore {winner:alt_c,loser:c}
                 };
                 let (difficulty,margin) = cache.difficulty(contest);
-                if difficulty< best_difficulty {
-                    best_difficulty =difficulty;
+                if difficulty<best_difficulty || (difficulty==best_difficulty && difficulty.is_finite() && best_assertion.is_some_and(|best|tie_break.prefers(&Assertion::NEB(contest),&Assertion::NEB(best)))) {
+                    best_difficulty=difficulty;
                     best_assertion=Some(contest);
                     best_margin=margin;
                 }
             }
         }
         if let Some(assertion) = best_assertion {
-            Some(AssertionAndDifficulty { assertion:Assertion::NEB(assertion), margin: best_margin, difficulty: best_difficulty })
+            Some(AssertionAndDifficulty { assertion:Assertion::NEB(assertion), margin: best_margin, difficulty: best_difficulty, tallies: None })
         } else {None}
     }
 
@@ -124,6 +135,48 @@ impl NotEliminatedBeforeCache {
         }
         NotEliminatedBeforeCache{cache}
     }
+
+    /// Recompute the cache in place for a different audit type (or the same votes with different audit parameters).
+    /// If the number of candidates is different, the cache is rebuilt.
+    pub fn rescore<A:AuditType>(&mut self,votes:&Votes,audit:&A) {
+        if self.cache.len()!=votes.num_candidates() as usize { *self=Self::new(votes,audit); return; }
+        for (winner,row) in self.cache.iter_mut().enumerate() {
+            for (loser,entry) in row.iter_mut().enumerate() {
+                if winner!=loser {
+                    *entry=NotEliminatedBefore{winner:CandidateIndex(winner as u32),loser:CandidateIndex(loser as u32)}.difficulty(votes,audit);
+                }
+            }
+        }
+    }
+}
+
+/// Check a set of assertions (e.g. imported from a hand-edited file) for obvious inconsistencies:
+/// * An assertion with the same winner and loser,
+/// * A NEN assertion whose winner or loser is not in its continuing candidates, or whose continuing candidates are not sorted and distinct,
+/// * Two NEB assertions each saying the other's winner is not eliminated before it (A NEB B and B NEB A),
+/// * Two NEN assertions with the same continuing candidates and opposite winner and loser.
+///
+/// Returns `RaireError::InconsistentAssertions` with the indices of the first problem found.
+/// This does not detect all inconsistencies, just those visible without considering elimination orders.
+pub fn check_assertion_consistency(assertions:&[AssertionAndDifficulty]) -> Result<(),RaireError> {
+    for (i,a) in assertions.iter().enumerate() {
+        let impossible = match &a.assertion {
+            Assertion::NEB(neb) => neb.winner==neb.loser,
+            Assertion::NEN(nen) => nen.winner==nen.loser || !nen.continuing.contains(&nen.winner) || !nen.continuing.contains(&nen.loser) || !nen.continuing.windows(2).all(|w|w[0].0<w[1].0),
+        };
+        if impossible { return Err(RaireError::InconsistentAssertions(i,i)); }
+    }
+    for (i,a) in assertions.iter().enumerate() {
+        for (j,b) in assertions.iter().enumerate().skip(i+1) {
+            let contradictory = match (&a.assertion,&b.assertion) {
+                (Assertion::NEB(a),Assertion::NEB(b)) => a.winner==b.loser && a.loser==b.winner,
+                (Assertion::NEN(a),Assertion::NEN(b)) => a.winner==b.loser && a.loser==b.winner && a.continuing==b.continuing,
+                _ => false,
+            };
+            if contradictory { return Err(RaireError::InconsistentAssertions(i,j)); }
+        }
+    }
+    Ok(())
 }
 
 /// An elimination order will be either compatible with a suffix or not.
@@ -177,6 +230,9 @@ impl SpecificLoserAmongstContinuing {
 /// In particular, this means that _winner_ can not be the next candidate eliminated.
 ///
 /// This was called IRV in the original paper.
+///
+/// The winner and loser must both be in _continuing_, which must be sorted, or the assertion will
+/// silently produce wrong results. Prefer [NotEliminatedNext::new], which checks this, to a struct literal.
 #[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
 pub struct NotEliminatedNext {
     pub winner : CandidateIndex,
@@ -186,6 +242,15 @@ pub struct NotEliminatedNext {
 }
 
 impl NotEliminatedNext {
+    /// Make a new assertion, sorting (and removing duplicates from) _continuing_.
+    /// Returns `RaireError::InvalidAssertion` if the winner and loser are the same, or either is not in _continuing_.
+    pub fn new(winner:CandidateIndex,loser:CandidateIndex,mut continuing:Vec<CandidateIndex>) -> Result<Self,RaireError> {
+        continuing.sort_unstable_by_key(|c|c.0);
+        continuing.dedup();
+        if winner==loser || !continuing.contains(&winner) || !continuing.contains(&loser) { return Err(RaireError::InvalidAssertion); }
+        Ok(NotEliminatedNext{winner,loser,continuing})
+    }
+
     pub fn difficulty<A:AuditType>(&self, votes:&Votes, audit:&A) -> AssertionDifficulty {
         let tallies = votes.restricted_tallies(&self.continuing);
         let mut tally_winner = BallotPaperCount(usize::MAX);
@@ -194,19 +259,42 @@ impl NotEliminatedNext {
             if self.loser==self.continuing[i] { tally_loser=tallies[i]; }
             else if self.winner==self.continuing[i] { tally_winner=tallies[i]; }
         }
+        if tally_winner==BallotPaperCount(usize::MAX) { return f64::INFINITY; } // winner not continuing, so the assertion makes no sense.
         audit.difficulty(tally_winner, tally_loser) // active paper count = tallies.iter().cloned().sum() for historical reenactment
     }
 
+    /// The tallies (winner,loser) used to evaluate this assertion, when only the continuing candidates are left.
+    pub fn tallies(&self, votes:&Votes) -> (BallotPaperCount,BallotPaperCount) {
+        let tallies = votes.restricted_tallies(&self.continuing);
+        let mut tally_winner = BallotPaperCount(0);
+        let mut tally_loser = BallotPaperCount(0);
+        for (&c,&tally) in self.continuing.iter().zip(tallies.iter()) {
+            if self.loser==c { tally_loser=tally; }
+            else if self.winner==c { tally_winner=tally; }
+        }
+        (tally_winner,tally_loser)
+    }
+
     /// Find the best NEN cote to rule out winner from being the next eliminated when only the given candidates are continuing
+    ///
+    /// Returns None if there is no other continuing candidate, or if the winner is not continuing (which makes no sense).
     pub fn find_best_difficulty<A:AuditType>(votes:&Votes, audit:&A, continuing:&[CandidateIndex], winner:CandidateIndex) -> Option<AssertionAndDifficulty> {
-        let tallies = votes.restricted_tallies(&continuing);
+        Self::find_best_difficulty_with_tie_break(votes,audit,continuing,winner,TieBreak::default())
+    }
+
+    /// Like [Self::find_best_difficulty], but if several continuing candidates have the equal lowest tally, the loser is chosen with `tie_break`.
+    /// [TieBreak::PreferLowerCandidateIndex] chooses the lowest numbered, so the result does not depend on the order of `continuing`.
+    /// Otherwise, as in the original implementation, the last of them in `continuing` is the loser.
+    pub fn find_best_difficulty_with_tie_break<A:AuditType>(votes:&Votes, audit:&A, continuing:&[CandidateIndex], winner:CandidateIndex,tie_break:TieBreak) -> Option<AssertionAndDifficulty> {
+        if !continuing.contains(&winner) { return None; }
+        let tallies = votes.restricted_tallies(continuing);
         let mut tally_winner = BallotPaperCount(usize::MAX);
         let mut tally_loser = BallotPaperCount(usize::MAX);
         let mut best_loser  : Option<CandidateIndex> = None;
         //println!("continuing = {:?} tallies={:?}",continuing,tallies);
         for i in 0..continuing.len() {
             if winner==continuing[i] { tally_winner=tallies[i]; }
-            else if tallies[i]<=tally_loser { best_loser=Some(continuing[i]);  tally_loser=tallies[i]; }
+            else if tallies[i]<tally_loser || (tallies[i]==tally_loser && (tie_break!=TieBreak::PreferLowerCandidateIndex || best_loser.is_some_and(|best|continuing[i].0<best.0))) { best_loser=Some(continuing[i]);  tally_loser=tallies[i]; }
         }
         if let Some(loser) = best_loser {
             let difficulty = audit.difficulty(tally_winner, tally_loser);  // active paper count = tallies.iter().cloned().sum() for historical reenactment
@@ -214,7 +302,7 @@ impl NotEliminatedNext {
             let mut continuing = continuing.to_vec();
             continuing.sort_unstable_by_key(|c|c.0); // important to make it canonical so that equality checks of assertions work, and so is_continuing can use a binary search. Also sorted is easier to read.
             let assertion = NotEliminatedNext { winner, loser, continuing };
-            Some(AssertionAndDifficulty { assertion:Assertion::NEN(assertion), margin, difficulty })
+            Some(AssertionAndDifficulty { assertion:Assertion::NEN(assertion), margin, difficulty, tallies: None })
         } else {None}
     }
 
@@ -254,6 +342,86 @@ impl Assertion {
             Assertion::NEN(_) => false,
         }
     }
+
+    /// The winner of the comparison in this assertion.
+    pub fn winner(&self) -> CandidateIndex {
+        match self {
+            Assertion::NEB(neb) => neb.winner,
+            Assertion::NEN(nen) => nen.winner,
+        }
+    }
+
+    /// The loser of the comparison in this assertion.
+    pub fn loser(&self) -> CandidateIndex {
+        match self {
+            Assertion::NEB(neb) => neb.loser,
+            Assertion::NEN(nen) => nen.loser,
+        }
+    }
+
+    /// The tallies (winner,loser) used to evaluate this assertion.
+    pub fn tallies(&self, votes:&Votes) -> (BallotPaperCount,BallotPaperCount) {
+        match self {
+            Assertion::NEB(neb) => neb.tallies(votes),
+            Assertion::NEN(nen) => nen.tallies(votes),
+        }
+    }
+
+    /// The difficulty and margin of this assertion for the given votes and audit type, computed from [Self::tallies].
+    pub fn difficulty<A:AuditType>(&self, votes:&Votes, audit:&A) -> (AssertionDifficulty,BallotPaperCount) {
+        let (tally_winner,tally_loser) = self.tallies(votes);
+        (audit.difficulty(tally_winner,tally_loser),if tally_winner>=tally_loser {tally_winner-tally_loser} else {BallotPaperCount(0)})
+    }
+
+    /// For an audit in progress, the proportion of the statistical evidence needed to confirm this assertion that has
+    /// been collected so far, given the number of ballots sampled and the number of one vote overstatements found.
+    /// The audit of this assertion can stop once this reaches 1. Clean samples increase it, and overstatements decrease it
+    /// (possibly below zero). It is negative infinity if the assertion is not true for the votes.
+    ///
+    /// This uses the Kaplan-Markov model for ballot comparison audits, in which the risk measure after `n` samples with
+    /// `o` one vote overstatements is `(1-1/U)^n/(1-1/(2γ))^o` where `U=2γN/V` for margin `V` out of `N` auditable ballots,
+    /// and error inflation factor `γ`. The result is the log of the risk measure divided by the log of the risk limit.
+    /// This is consistent with the MACRO difficulty, in that sampling that many clean ballots gives approximately 1.
+    /// Currently only [Audit::MACRO] is supported; other audit types give NaN.
+    pub fn risk_headroom(&self,votes:&Votes,audit:&Audit,ballots_sampled:BallotPaperCount,overstatements:u32) -> f64 {
+        let Audit::MACRO(audit) = audit else { return f64::NAN; };
+        let (winner,loser) = self.tallies(votes);
+        if winner<=loser { return f64::NEG_INFINITY; }
+        let u = 2.0*audit.error_inflation_factor*audit.total_auditable_ballots.0 as f64/(winner-loser).0 as f64;
+        let log_risk = ballots_sampled.0 as f64*(1.0-1.0/u).ln()-overstatements as f64*(1.0-0.5/audit.error_inflation_factor).ln();
+        log_risk/audit.confidence.ln()
+    }
+
+    /// A sentence in plain English describing what this assertion means, and why it is supported by the `votes`, for
+    /// automatically generated audit narratives. `names` are the names of the candidates.
+    pub fn plain_english(&self, names:&[String], votes:&Votes) -> String {
+        let name = |c:CandidateIndex| names.get(c.0 as usize).cloned().unwrap_or_else(||format!("Candidate {}",c.0));
+        let (winner,loser) = (name(self.winner()),name(self.loser()));
+        let (tally_winner,tally_loser) = self.tallies(votes);
+        let claim = match self {
+            Assertion::NEB(_) => format!("{winner} is never eliminated before {loser}"),
+            Assertion::NEN(nen) => format!("{winner} is not eliminated next when only {} remain",nen.continuing.iter().map(|&c|name(c)).collect::<Vec<_>>().join(", ")),
+        };
+        if tally_winner<=tally_loser {
+            return format!("{claim}. However this is not supported by the votes, as {winner} has {tally_winner} votes and {loser} has {tally_loser}.");
+        }
+        let margin = tally_winner-tally_loser;
+        let percentage = 100.0*margin.0 as f64/votes.total_votes().0 as f64;
+        match self {
+            Assertion::NEB(_) => format!("{claim}, because {winner} has {tally_winner} first preference votes, more than the {tally_loser} votes {loser} could have while {winner} is continuing, a margin of {margin} votes ({percentage:.1}% of all ballots)."),
+            Assertion::NEN(_) => format!("{claim}, because {winner} has {tally_winner} votes at that point, more than {loser} with {tally_loser}, a margin of {margin} votes ({percentage:.1}% of all ballots)."),
+        }
+    }
+
+    /// The minimum number of ballots that would need to be changed to make this assertion false.
+    ///
+    /// Changing a ballot from the winner to the loser reduces the margin by 2, and the assertion
+    /// is false once the winner's tally is no longer strictly greater than the loser's. So this
+    /// is the margin divided by 2, rounded up. Note that this is different from the margin itself.
+    pub fn flipping_ballots(&self, votes:&Votes) -> BallotPaperCount {
+        let (tally_winner,tally_loser) = self.tallies(votes);
+        if tally_winner<=tally_loser { BallotPaperCount(0) } else { BallotPaperCount((tally_winner-tally_loser).0.div_ceil(2)) }
+    }
 }
 
 #[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
@@ -262,6 +430,55 @@ pub struct AssertionAndDifficulty {
     /// The number of votes between the winner and loser.
     pub margin : BallotPaperCount,
     pub difficulty: f64,
+    /// If requested, the tallies compared by the assertion, from which the margin and difficulty were computed,
+    /// for downstream audit tools. See [crate::raire_algorithm::RaireResult::add_tallies].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tallies : Option<AssertionTallies>,
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+/// The tallies compared by an assertion. See [Assertion::tallies].
+pub struct AssertionTallies {
+    /// The tally of the winner of the assertion.
+    pub winner : BallotPaperCount,
+    /// The tally of the loser of the assertion.
+    pub loser : BallotPaperCount,
+}
+
+impl AssertionAndDifficulty {
+    /// The tallies compared by the assertion, see [Assertion::tallies].
+    pub fn compute_tallies(&self,votes:&Votes) -> AssertionTallies {
+        let (winner,loser) = self.assertion.tallies(votes);
+        AssertionTallies{winner,loser}
+    }
+
+    /// Recompute the difficulty and margin (and tallies, if present) for the given votes and audit type, for instance
+    /// if the risk limit or number of auditable ballots changed after the assertions were generated.
+    pub fn rescore<A:AuditType>(&mut self,votes:&Votes,audit:&A) {
+        (self.difficulty,self.margin) = self.assertion.difficulty(votes,audit);
+        if self.tallies.is_some() { self.tallies=Some(self.compute_tallies(votes)); }
+    }
+
+    /// The margin as a percentage of the denominator appropriate to the audit type, for display. For `BRAVO` this is
+    /// the continuing ballots, those ranking one of the continuing candidates of a NEN assertion, or the winner or loser of a
+    /// NEB assertion. For other audit types it is the total auditable ballots, giving the diluted margin. A `TwoPhase` audit uses the initial audit.
+    pub fn margin_percent(&self,votes:&Votes,audit:&Audit) -> f64 {
+        let denominator : BallotPaperCount = match audit {
+            Audit::BRAVO(_) => match &self.assertion {
+                Assertion::NEN(nen) => votes.restricted_tallies(&nen.continuing).into_iter().sum(),
+                Assertion::NEB(neb) => votes.restricted_tallies(&[neb.winner,neb.loser]).into_iter().sum(),
+            },
+            Audit::TwoPhase(two_phase) => return self.margin_percent(votes,&two_phase.initial),
+            _ => audit.total_auditable_ballots(),
+        };
+        100.0*self.margin.0 as f64/denominator.0 as f64
+    }
+
+    /// The expected number of ballots to be sampled for this assertion as a percentage of `total` ballots, for display,
+    /// if the audit type gives sample sizes (see [Audit::expected_sample_size]). The `audit` should be the one used to compute the difficulty.
+    pub fn sample_size_percent(&self,audit:&Audit,total:BallotPaperCount) -> Option<f64> {
+        Some(100.0*audit.expected_sample_size(self.difficulty)?/total.0 as f64)
+    }
 }
 
 
@@ -11,7 +11,9 @@
 //! The types of assertions about the election, generally relative standings of various candidates
 
 
-use crate::irv::{BallotPaperCount};
+use crate::assertions::AssertionAndDifficulty;
+use crate::irv::{BallotPaperCount, Votes};
+use crate::RaireError;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -46,6 +48,12 @@ impl BallotPollingBRAVO {
         self.bravo_function(lowest_tally_winner,highest_tally_loser,self.total_auditable_ballots)
     }
 
+    /// The difficulty that would be obtained with a different `confidence`, otherwise the same as [AuditType::difficulty].
+    /// Useful for seeing how the difficulty changes with the risk limit without re-solving.
+    pub fn difficulty_at_confidence(&self,lowest_tally_winner:BallotPaperCount,highest_tally_loser:BallotPaperCount,confidence:f64) -> AssertionDifficulty {
+        BallotPollingBRAVO{confidence,..*self}.difficulty(lowest_tally_winner,highest_tally_loser)
+    }
+
     /// This function is only public for testing some historical data. You probably don't want to use this directly.
     pub fn bravo_function(&self,winner_tally:BallotPaperCount,loser_tally:BallotPaperCount,paper_count:BallotPaperCount) -> AssertionDifficulty {
         if winner_tally.0<=loser_tally.0 { f64::INFINITY } else {
@@ -89,6 +97,12 @@ impl BallotComparisonMACRO {
             -self.confidence.ln()*u
         }
     }
+
+    /// The difficulty that would be obtained with a different `confidence`, otherwise the same as [AuditType::difficulty].
+    /// Useful for seeing how the difficulty changes with the risk limit without re-solving.
+    pub fn difficulty_at_confidence(&self,lowest_tally_winner:BallotPaperCount,highest_tally_loser:BallotPaperCount,confidence:f64) -> AssertionDifficulty {
+        BallotComparisonMACRO{confidence,..*self}.difficulty(lowest_tally_winner,highest_tally_loser)
+    }
 }
 
 impl AuditType for BallotComparisonMACRO {
@@ -97,16 +111,36 @@ impl AuditType for BallotComparisonMACRO {
     }
 }
 
+/// Whether the margin (winner-loser, the winner having a higher tally) is less than the optional `min_auditable_margin`.
+fn below_min_auditable_margin(min_auditable_margin:Option<BallotPaperCount>,lowest_tally_winner:BallotPaperCount,highest_tally_loser:BallotPaperCount) -> bool {
+    min_auditable_margin.is_some_and(|min|lowest_tally_winner-highest_tally_loser<min)
+}
+
 /// A comparison where the difficulty = 1/diluted margin.
 /// Useful for BallotComparison audits
 #[derive(Clone,Copy,Debug,Serialize,Deserialize)]
 pub struct BallotComparisonOneOnDilutedMargin {
     pub total_auditable_ballots : BallotPaperCount,
+    /// If present, any margin below this is considered infeasible to audit (e.g. a full recount would be done instead), and has infinite difficulty.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub min_auditable_margin : Option<BallotPaperCount>,
+}
+
+impl BallotComparisonOneOnDilutedMargin {
+    /// An audit of `total_auditable_ballots` ballots with no minimum auditable margin.
+    pub const fn new(total_auditable_ballots:BallotPaperCount) -> Self {
+        BallotComparisonOneOnDilutedMargin{total_auditable_ballots,min_auditable_margin:None}
+    }
+
+    /// The same audit, with any margin below `min_auditable_margin` considered infeasible to audit.
+    pub const fn with_min_auditable_margin(self,min_auditable_margin:BallotPaperCount) -> Self {
+        BallotComparisonOneOnDilutedMargin{min_auditable_margin:Some(min_auditable_margin),..self}
+    }
 }
 
 impl AuditType for BallotComparisonOneOnDilutedMargin {
     fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
-        if lowest_tally_winner<=highest_tally_loser { f64::INFINITY } else {
+        if lowest_tally_winner<=highest_tally_loser || below_min_auditable_margin(self.min_auditable_margin,lowest_tally_winner,highest_tally_loser) { f64::INFINITY } else {
             let reciprocal_diluted_margin = self.total_auditable_ballots.0 as f64/(lowest_tally_winner-highest_tally_loser).0 as f64;
             reciprocal_diluted_margin
         }
@@ -119,17 +153,130 @@ impl AuditType for BallotComparisonOneOnDilutedMargin {
 #[derive(Clone,Copy,Debug,Serialize,Deserialize)]
 pub struct BallotPollingOneOnDilutedMarginSquared {
     pub total_auditable_ballots : BallotPaperCount,
+    /// If present, any margin below this is considered infeasible to audit (e.g. a full recount would be done instead), and has infinite difficulty.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub min_auditable_margin : Option<BallotPaperCount>,
+}
+
+impl BallotPollingOneOnDilutedMarginSquared {
+    /// An audit of `total_auditable_ballots` ballots with no minimum auditable margin.
+    pub const fn new(total_auditable_ballots:BallotPaperCount) -> Self {
+        BallotPollingOneOnDilutedMarginSquared{total_auditable_ballots,min_auditable_margin:None}
+    }
+
+    /// The same audit, with any margin below `min_auditable_margin` considered infeasible to audit.
+    pub const fn with_min_auditable_margin(self,min_auditable_margin:BallotPaperCount) -> Self {
+        BallotPollingOneOnDilutedMarginSquared{min_auditable_margin:Some(min_auditable_margin),..self}
+    }
 }
 
 impl AuditType for BallotPollingOneOnDilutedMarginSquared {
     fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
-        if lowest_tally_winner<=highest_tally_loser { f64::INFINITY } else {
+        if lowest_tally_winner<=highest_tally_loser || below_min_auditable_margin(self.min_auditable_margin,lowest_tally_winner,highest_tally_loser) { f64::INFINITY } else {
             let reciprocal_diluted_margin = self.total_auditable_ballots.0 as f64/(lowest_tally_winner-highest_tally_loser).0 as f64;
             reciprocal_diluted_margin*reciprocal_diluted_margin
         }
     }
 }
 
+/// A Bayesian ballot polling audit, which continues until the posterior probability of an upset (the reported
+/// winner of the comparison actually losing) is below `upset_prior`, using a uniform prior on the winner's share.
+///
+/// The difficulty is a proxy for the number of ballots that need to be sampled, using a normal approximation
+/// to the posterior assuming the sample matches the reported tallies. If s = w/(w+l) is the winner's share of the
+/// ballots relevant to the comparison, and z is the (1-`upset_prior`) quantile of the standard normal distribution,
+/// then z²s(1-s)/(s-½)² relevant ballots are needed, which is scaled up by the proportion of ballots that are relevant.
+#[derive(Clone,Copy,Debug,Serialize,Deserialize)]
+#[serde(try_from = "UncheckedBayesianAudit")]
+pub struct BayesianAudit {
+    /// The acceptable posterior probability of an upset. A number strictly between 0 and 0.5, analogous to the risk limit. See [Self::new].
+    pub upset_prior : f64,
+    pub total_auditable_ballots : BallotPaperCount,
+}
+
+impl BayesianAudit {
+    /// An audit of `total_auditable_ballots` ballots. Returns [RaireError::InvalidUpsetPrior] unless `upset_prior` is
+    /// strictly between 0 and 0.5, as an upset can never be ruled out completely, and a prior of 0.5 or more is met before sampling.
+    pub fn new(upset_prior:f64,total_auditable_ballots:BallotPaperCount) -> Result<Self,RaireError> {
+        if upset_prior>0.0 && upset_prior<0.5 { Ok(BayesianAudit{upset_prior,total_auditable_ballots}) } else { Err(RaireError::InvalidUpsetPrior(upset_prior)) }
+    }
+}
+
+#[derive(Deserialize)]
+/// The JSON form of a [BayesianAudit], checked by [BayesianAudit::new] when read.
+struct UncheckedBayesianAudit {
+    upset_prior : f64,
+    total_auditable_ballots : BallotPaperCount,
+}
+
+impl TryFrom<UncheckedBayesianAudit> for BayesianAudit {
+    type Error = RaireError;
+    fn try_from(audit: UncheckedBayesianAudit) -> Result<Self,RaireError> { BayesianAudit::new(audit.upset_prior,audit.total_auditable_ballots) }
+}
+
+impl AuditType for BayesianAudit {
+    fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
+        if lowest_tally_winner<=highest_tally_loser { f64::INFINITY } else {
+            let w = lowest_tally_winner.0 as f64;
+            let l = highest_tally_loser.0 as f64;
+            let s = w/(w+l);
+            let z = inverse_standard_normal_cdf(1.0-self.upset_prior);
+            let relevant_ballots_needed = z*z*s*(1.0-s)/((s-0.5)*(s-0.5));
+            relevant_ballots_needed*self.total_auditable_ballots.0 as f64/(w+l)
+        }
+    }
+}
+
+/// An audit that starts with one method (typically ballot polling) and switches to another (typically ballot comparison)
+/// if the audit escalates, as done in some jurisdictions. The difficulty used for finding assertions is that of the
+/// `initial` audit, but the `escalation` difficulty can be obtained with [Self::phase_difficulties] to plan for the switch.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct TwoPhaseAudit {
+    pub initial : Box<Audit>,
+    pub escalation : Box<Audit>,
+}
+
+impl TwoPhaseAudit {
+    /// The difficulty of a comparison under the initial and escalation audits respectively.
+    pub fn phase_difficulties(&self, lowest_tally_winner:BallotPaperCount, highest_tally_loser:BallotPaperCount) -> (AssertionDifficulty,AssertionDifficulty) {
+        (self.initial.difficulty(lowest_tally_winner,highest_tally_loser),self.escalation.difficulty(lowest_tally_winner,highest_tally_loser))
+    }
+
+    /// The difficulty of each assertion under the initial and escalation audits respectively, recomputed from `votes`.
+    pub fn assertion_difficulties(&self,assertions:&[AssertionAndDifficulty],votes:&Votes) -> Vec<(AssertionDifficulty,AssertionDifficulty)> {
+        assertions.iter().map(|a|{
+            let (winner,loser) = a.assertion.tallies(votes);
+            self.phase_difficulties(winner,loser)
+        }).collect()
+    }
+}
+
+impl AuditType for TwoPhaseAudit {
+    fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
+        self.initial.difficulty(lowest_tally_winner,highest_tally_loser)
+    }
+}
+
+/// The inverse of the cumulative distribution function of the standard normal distribution, for 0<p<1,
+/// using Acklam's rational approximation (relative error less than 1.15e-9). NaN if `p` is not finite.
+fn inverse_standard_normal_cdf(p:f64) -> f64 {
+    const A : [f64;6] = [-3.969683028665376e+01,2.209460984245205e+02,-2.759285104469687e+02,1.38357751867269e+02,-3.066479806614716e+01,2.506628277459239e+00];
+    const B : [f64;5] = [-5.447609879822406e+01,1.615858368580409e+02,-1.556989798598866e+02,6.680131188771972e+01,-1.328068155288572e+01];
+    const C : [f64;6] = [-7.784894002430293e-03,-3.223964580411365e-01,-2.400758277161838e+00,-2.549732539343734e+00,4.374664141464968e+00,2.938163982698783e+00];
+    const D : [f64;4] = [7.784695709041462e-03,3.224671290700398e-01,2.445134137142996e+00,3.754408661907416e+00];
+    const P_LOW : f64 = 0.02425;
+    if !p.is_finite() { f64::NAN } else if p<=0.0 { f64::NEG_INFINITY } else if p>=1.0 { f64::INFINITY } else if p<P_LOW {
+        let q = (-2.0*p.ln()).sqrt();
+        (((((C[0]*q+C[1])*q+C[2])*q+C[3])*q+C[4])*q+C[5])/((((D[0]*q+D[1])*q+D[2])*q+D[3])*q+1.0)
+    } else if p<=1.0-P_LOW {
+        let q = p-0.5;
+        let r = q*q;
+        (((((A[0]*r+A[1])*r+A[2])*r+A[3])*r+A[4])*r+A[5])*q/(((((B[0]*r+B[1])*r+B[2])*r+B[3])*r+B[4])*r+1.0)
+    } else {
+        -inverse_standard_normal_cdf(1.0-p)
+    }
+}
+
 #[derive(Clone,Debug,Serialize,Deserialize)]
 #[serde(tag = "type")]
 pub enum Audit {
@@ -139,6 +286,58 @@ pub enum Audit {
     OneOnMargin(BallotComparisonOneOnDilutedMargin),
     #[serde(alias = "MarginSq")] // for backwards compatibility
     OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared),
+    Bayesian(BayesianAudit),
+    TwoPhase(TwoPhaseAudit),
+}
+
+impl Audit {
+    /// The expected number of ballots that need to be sampled for an assertion with the given difficulty,
+    /// if the difficulty for this audit type is an estimate of sample size (`BRAVO`, `MACRO` and `Bayesian`). Otherwise `None`.
+    pub fn expected_sample_size(&self,difficulty:AssertionDifficulty) -> Option<f64> {
+        match self {
+            Audit::BRAVO(_) | Audit::MACRO(_) | Audit::Bayesian(_) => Some(difficulty),
+            Audit::OneOnMargin(_) | Audit::OneOnMarginSq(_) => None,
+            Audit::TwoPhase(audit) => audit.initial.expected_sample_size(difficulty),
+        }
+    }
+
+    /// The number of ballots that may be sampled. For a `TwoPhase` audit, that of the initial audit.
+    pub fn total_auditable_ballots(&self) -> BallotPaperCount {
+        match self {
+            Audit::BRAVO(audit) => audit.total_auditable_ballots,
+            Audit::MACRO(audit) => audit.total_auditable_ballots,
+            Audit::OneOnMargin(audit) => audit.total_auditable_ballots,
+            Audit::OneOnMarginSq(audit) => audit.total_auditable_ballots,
+            Audit::Bayesian(audit) => audit.total_auditable_ballots,
+            Audit::TwoPhase(audit) => audit.initial.total_auditable_ballots(),
+        }
+    }
+
+    /// The same audit with a different confidence α, for audit types that have one (`BRAVO` and `MACRO`).
+    /// Both phases of a `TwoPhase` audit are changed. Other audit types are returned unchanged.
+    pub fn with_confidence(&self,confidence:f64) -> Audit {
+        match self {
+            Audit::BRAVO(audit) => Audit::BRAVO(BallotPollingBRAVO{confidence,..*audit}),
+            Audit::MACRO(audit) => Audit::MACRO(BallotComparisonMACRO{confidence,..*audit}),
+            Audit::TwoPhase(audit) => Audit::TwoPhase(TwoPhaseAudit{initial:Box::new(audit.initial.with_confidence(confidence)),escalation:Box::new(audit.escalation.with_confidence(confidence))}),
+            _ => self.clone(),
+        }
+    }
+
+    /// The same audit for votes with every ballot multiplied by `factor`. The total auditable ballots, and any minimum
+    /// auditable margin, are multiplied by `factor`, so comparing tallies multiplied by `factor` has the same difficulty
+    /// as comparing the original tallies. Both phases of a `TwoPhase` audit are changed.
+    pub fn scaled(&self,factor:usize) -> Audit {
+        let scale = |count:BallotPaperCount| BallotPaperCount(count.0*factor);
+        match self {
+            Audit::BRAVO(audit) => Audit::BRAVO(BallotPollingBRAVO{total_auditable_ballots:scale(audit.total_auditable_ballots),..*audit}),
+            Audit::MACRO(audit) => Audit::MACRO(BallotComparisonMACRO{total_auditable_ballots:scale(audit.total_auditable_ballots),..*audit}),
+            Audit::OneOnMargin(audit) => Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{total_auditable_ballots:scale(audit.total_auditable_ballots),min_auditable_margin:audit.min_auditable_margin.map(scale)}),
+            Audit::OneOnMarginSq(audit) => Audit::OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared{total_auditable_ballots:scale(audit.total_auditable_ballots),min_auditable_margin:audit.min_auditable_margin.map(scale)}),
+            Audit::Bayesian(audit) => Audit::Bayesian(BayesianAudit{total_auditable_ballots:scale(audit.total_auditable_ballots),..*audit}),
+            Audit::TwoPhase(audit) => Audit::TwoPhase(TwoPhaseAudit{initial:Box::new(audit.initial.scaled(factor)),escalation:Box::new(audit.escalation.scaled(factor))}),
+        }
+    }
 }
 
 impl AuditType for Audit {
@@ -148,6 +347,8 @@ impl AuditType for Audit {
             Audit::MACRO(audit) => audit.difficulty(lowest_tally_winner,highest_tally_loser),
             Audit::OneOnMargin(audit) => audit.difficulty(lowest_tally_winner, highest_tally_loser),
             Audit::OneOnMarginSq(audit) => audit.difficulty(lowest_tally_winner, highest_tally_loser),
+            Audit::Bayesian(audit) => audit.difficulty(lowest_tally_winner, highest_tally_loser),
+            Audit::TwoPhase(audit) => audit.difficulty(lowest_tally_winner, highest_tally_loser),
         }
     }
 }
\ No newline at end of file
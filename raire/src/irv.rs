@@ -21,6 +21,7 @@ use std::ops::{Add, AddAssign, Sub, SubAssign};
 use std::str::FromStr;
 use serde::Deserialize;
 use serde::Serialize;
+use crate::audit_type::{AssertionDifficulty, AuditType};
 use crate::RaireError;
 use crate::timeout::TimeOut;
 
@@ -141,6 +142,34 @@ impl Vote {
     }
 }
 
+/// What to do with a ballot that skips a rank, e.g. marks a first and third preference but no second.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+pub enum SkippedRankRule {
+    /// Ignore the skipped rank, moving later preferences up. The example above would be treated as first and second preferences.
+    #[default]
+    SkipOver,
+    /// The ballot exhausts at the skipped rank, ignoring later preferences. The example above would only have a first preference.
+    Exhaust,
+}
+
+/// A rough classification of how expensive the full RAIRE computation is likely to be. See [Votes::estimate_solve_cost].
+/// Ordered from cheapest to most expensive.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord,Hash,Serialize,Deserialize)]
+pub enum DifficultyClass {
+    Easy,
+    Moderate,
+    Hard,
+}
+
+/// A cheap heuristic estimate of how expensive the full RAIRE computation will be. See [Votes::estimate_solve_cost].
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct SolveCostEstimate {
+    pub candidates : u32,
+    /// The number of distinct preference lists.
+    pub distinct_votes : usize,
+    pub rough_difficulty_class : DifficultyClass,
+}
+
 pub struct Votes {
     pub votes : Vec<Vote>,
     first_preference_votes : Vec<BallotPaperCount>,
@@ -149,6 +178,16 @@ pub struct Votes {
 
 
 impl Votes {
+    /// Convert the marks on a ballot, in rank order with None for a rank with no candidate marked, into a preference list
+    /// for [Vote::prefs], applying the given rule for skipped ranks. Importers should use this when ballots may have gaps.
+    /// Unmarked ranks at the end of the ballot are not skipped ranks, and are ignored by either rule.
+    pub fn normalize_skipped_ranks(ranks:&[Option<CandidateIndex>],rule:SkippedRankRule) -> Vec<CandidateIndex> {
+        match rule {
+            SkippedRankRule::SkipOver => ranks.iter().flatten().copied().collect(),
+            SkippedRankRule::Exhaust => ranks.iter().map_while(|&c|c).collect(),
+        }
+    }
+
     pub fn new(votes:Vec<Vote>,num_candidates:usize) -> Result<Votes,RaireError> {
         let mut first_preference_votes = vec![BallotPaperCount(0);num_candidates];
         for v in &votes {
@@ -161,6 +200,13 @@ impl Votes {
     }
     pub fn first_preference_only_tally(&self,candidate:CandidateIndex) -> BallotPaperCount { self.first_preference_votes[candidate.0 as usize] }
 
+    /// Make a copy of these votes with the `excluded` candidates removed from all preference lists, as if they were not in the contest,
+    /// e.g. because they were disqualified. Candidate indices are unchanged, so the excluded candidates are still present but get no votes.
+    pub fn project(&self,excluded:&[CandidateIndex]) -> Votes {
+        let votes : Vec<Vote> = self.votes.iter().map(|v|Vote{n:v.n,prefs:v.prefs.iter().filter(|c|!excluded.contains(c)).cloned().collect()}).collect();
+        Votes::new(votes,self.num_candidates() as usize).expect("Candidates were already checked")
+    }
+
     /// Get the tallies for continuing candidates, returning a vector of the same length and order as the continuing structure
     pub fn restricted_tallies(&self,continuing:&[CandidateIndex]) -> Vec<BallotPaperCount> {
         let mut res = vec![BallotPaperCount(0);continuing.len()];
@@ -190,17 +236,206 @@ impl Votes {
 
     pub fn num_candidates(&self) -> u32 { self.first_preference_votes.len() as u32 }
 
+    /// The pairwise comparison matrix: entry `[i][j]` is the number of ballots ranking candidate i above candidate j,
+    /// where both are present on the ballot. This is the basis of Condorcet and Smith set analysis.
+    pub fn pairwise_matrix(&self) -> Vec<Vec<BallotPaperCount>> {
+        let n = self.num_candidates() as usize;
+        let mut res = vec![vec![BallotPaperCount(0);n];n];
+        for v in &self.votes {
+            for (position,above) in v.prefs.iter().enumerate() {
+                for below in &v.prefs[position+1..] {
+                    res[above.0 as usize][below.0 as usize]+=v.n;
+                }
+            }
+        }
+        res
+    }
+
+    /// The hypothetical top-two runoff sometimes reported alongside IRV: the two candidates with the most first preferences
+    /// (ties resolved in favour of the lower candidate index), compared on all ballots preferring one to the other.
+    /// Returns the runoff winner, the runoff loser, and the margin between them. If the runoff is tied, the candidate
+    /// with more first preferences is listed first.
+    ///
+    /// Returns None if there are fewer than two candidates.
+    pub fn top_two_runoff(&self) -> Option<(CandidateIndex,CandidateIndex,BallotPaperCount)> {
+        let mut by_first_preferences : Vec<CandidateIndex> = (0..self.num_candidates()).map(CandidateIndex).collect();
+        by_first_preferences.sort_by_key(|&c|std::cmp::Reverse(self.first_preference_only_tally(c))); // stable, so ties keep index order.
+        let (&first,&second) = (by_first_preferences.first()?,by_first_preferences.get(1)?);
+        let tallies = self.restricted_tallies(&[first,second]);
+        if tallies[1]>tallies[0] { Some((second,first,BallotPaperCount(tallies[1].0-tallies[0].0))) }
+        else { Some((first,second,BallotPaperCount(tallies[0].0-tallies[1].0))) }
+    }
+
+    /// For each candidate, how much their first preferences alone contribute to the feasibility of an audit, for explaining
+    /// the connection between votes and audit difficulty to voters. This is the diluted margin (margin divided by total ballots)
+    /// of the easiest NEB assertion with that candidate as the winner, that is, the reciprocal of its difficulty for
+    /// [crate::audit_type::BallotComparisonOneOnDilutedMargin]. Zero if the candidate cannot be shown to beat anyone by NEB.
+    ///
+    /// The margin of "c NEB d" is c's first preference tally less the number of ballots preferring d to c.
+    pub fn first_preference_decisiveness(&self) -> Vec<f64> {
+        let total = self.total_votes().0 as f64;
+        (0..self.num_candidates()).map(CandidateIndex).map(|winner|{
+            let tally_winner = self.first_preference_only_tally(winner);
+            (0..self.num_candidates()).map(CandidateIndex).filter(|&loser|loser!=winner).map(|loser|{
+                let tally_loser = self.restricted_tallies(&[winner,loser])[1];
+                if tally_winner>tally_loser && total>0.0 { (tally_winner.0-tally_loser.0) as f64/total } else { 0.0 }
+            }).fold(0.0,f64::max)
+        }).collect()
+    }
+
+    /// A heuristic check for multi-winner STV data (such as an Australian Senate contest) passed in by mistake,
+    /// as importers sometimes do. RAIRE only handles single winner IRV, so a true result deserves a warning.
+    ///
+    /// STV contests typically have many candidates, and a majority of ballots following one of a few party tickets
+    /// ranking every candidate. So this returns true if there are at least 20 candidates, and more than half the
+    /// ballots are complete rankings identical to at least 1% of all ballots. Single winner contests rarely
+    /// have enough candidates for this to happen, even with how-to-vote cards.
+    pub fn looks_like_multiwinner(&self) -> bool {
+        let num_candidates = self.num_candidates() as usize;
+        if num_candidates<20 { return false; }
+        let mut complete_rankings : HashMap<&[CandidateIndex],BallotPaperCount> = HashMap::new();
+        for v in &self.votes {
+            if v.prefs.len()==num_candidates { *complete_rankings.entry(&v.prefs).or_insert(BallotPaperCount(0))+=v.n; }
+        }
+        let total = self.total_votes();
+        let on_tickets : BallotPaperCount = complete_rankings.into_values().filter(|&n|n.0*100>=total.0).sum();
+        on_tickets.0*2>total.0
+    }
+
+    /// Candidates whose presence provably does not affect the winner, so reports on contests with many candidates can
+    /// collapse them. These are the largest group of candidates with the fewest first preferences whose combined first
+    /// preferences are fewer than the first preferences of every other candidate. Their tallies can only increase by
+    /// transfers from each other, so they are all eliminated before anyone else (regardless of the order they are
+    /// eliminated), and the count then proceeds as if they had never been candidates. This is the usual bulk exclusion rule.
+    ///
+    /// At least two candidates are always left, so the leading candidates are never flagged. Sorted by candidate index.
+    pub fn irrelevant_candidates(&self) -> Vec<CandidateIndex> {
+        let mut by_tally : Vec<CandidateIndex> = (0..self.num_candidates()).map(CandidateIndex).collect();
+        by_tally.sort_by_key(|&c|self.first_preference_only_tally(c));
+        let mut excluded = 0;
+        let mut sum = BallotPaperCount(0);
+        for k in 0..by_tally.len().saturating_sub(2) {
+            sum+=self.first_preference_only_tally(by_tally[k]);
+            if sum<self.first_preference_only_tally(by_tally[k+1]) { excluded=k+1; }
+        }
+        let mut res = by_tally[..excluded].to_vec();
+        res.sort_unstable_by_key(|c|c.0);
+        res
+    }
+
+    /// A cheap estimate of how expensive running RAIRE on these votes will be, so batch schedulers can order work.
+    /// This is only a heuristic, based on the number of candidates and how close the top two candidates are on first
+    /// preferences, and may be wrong for particular contests. The search space grows exponentially with the number of
+    /// candidates, and close contests need more of it to be searched.
+    ///
+    /// A contest is [DifficultyClass::Easy] if it has at most 4 candidates, or the leading candidate has a majority of first preferences
+    /// or leads by at least 10% of ballots. Otherwise it is [DifficultyClass::Hard] if it has at least 10 candidates and the lead
+    /// is less than 1% of ballots, else [DifficultyClass::Moderate].
+    pub fn estimate_solve_cost(&self) -> SolveCostEstimate {
+        let candidates = self.num_candidates();
+        let distinct_votes = self.votes.iter().map(|v|&v.prefs).collect::<HashSet<_>>().len();
+        let mut tallies = self.first_preference_votes.clone();
+        tallies.sort_unstable_by(|a,b|b.cmp(a));
+        let total = self.total_votes().0 as f64;
+        let leader = tallies.first().map(|t|t.0 as f64).unwrap_or(0.0);
+        let lead = (leader-tallies.get(1).map(|t|t.0 as f64).unwrap_or(0.0))/total.max(1.0);
+        let rough_difficulty_class = if candidates<=4 || leader*2.0>total || lead>=0.1 { DifficultyClass::Easy }
+            else if candidates>=10 && lead<0.01 { DifficultyClass::Hard }
+            else { DifficultyClass::Moderate };
+        SolveCostEstimate{candidates,distinct_votes,rough_difficulty_class}
+    }
+
+    /// A data integrity check that every ballot is accounted for: when all candidates are continuing, the sum
+    /// of the tallies plus the exhausted (blank) ballots should equal the total number of ballots, and the tallies
+    /// should match the first preference tallies computed when this structure was made. A failure indicates
+    /// ballots have been lost or changed, for instance by a bug in an importer, or ballots with invalid candidates
+    /// added to `votes` directly. Lost ballots give [RaireError::TallyNotConserved], and ballots moved between candidates
+    /// give [RaireError::FirstPreferenceTallyChanged] for the first candidate whose tally differs.
+    pub fn verify_tally_conservation(&self) -> Result<(),RaireError> {
+        let all_candidates : Vec<CandidateIndex> = (0..self.num_candidates()).map(CandidateIndex).collect();
+        let tallies = self.restricted_tallies(&all_candidates);
+        let counted : BallotPaperCount = tallies.iter().cloned().sum();
+        let exhausted : BallotPaperCount = self.votes.iter().filter(|v|v.prefs.is_empty()).map(|v|v.n).sum();
+        let total = self.total_votes();
+        if counted+exhausted!=total { return Err(RaireError::TallyNotConserved(counted,exhausted,total)); }
+        if let Some((candidate,(&tally,&original))) = tallies.iter().zip(self.first_preference_votes.iter()).enumerate().find(|(_,(tally,original))|tally!=original) {
+            return Err(RaireError::FirstPreferenceTallyChanged(CandidateIndex(candidate as u32),tally,original));
+        }
+        Ok(())
+    }
+
+    /// A fast lower bound on the difficulty of auditing the contest, useful for screening a large number of contests
+    /// before running the full RAIRE algorithm. This is not the actual difficulty, which may be much higher.
+    ///
+    /// This is the difficulty of the final round of the IRV count (ties being resolved arbitrarily), the winner
+    /// against the runner up when only they are continuing. This is a lower bound as the elimination order
+    /// with the same candidates eliminated first, but the runner up winning, needs to be ruled out, and all other
+    /// assertions that could rule it out are at least this difficult. Earlier rounds are not considered, as a
+    /// different elimination order early on need not change the winner.
+    pub fn margin_based_difficulty_bound<A:AuditType>(&self,audit:&A) -> AssertionDifficulty {
+        let mut continuing : Vec<CandidateIndex> = (0..self.num_candidates()).map(CandidateIndex).collect();
+        while continuing.len()>2 {
+            let tallies = self.restricted_tallies(&continuing);
+            let lowest = (0..continuing.len()).min_by_key(|&i|tallies[i]).unwrap();
+            continuing.remove(lowest);
+        }
+        if continuing.len()<2 { return 0.0; }
+        let tallies = self.restricted_tallies(&continuing);
+        if tallies[0]>=tallies[1] { audit.difficulty(tallies[0],tallies[1]) } else { audit.difficulty(tallies[1],tallies[0]) }
+    }
+
+    /// Find the candidate(s) who could be eliminated first, that is, those with the lowest first preference tally.
+    /// There will be more than one if there is a tie.
+    ///
+    /// only possible error is RaireError::TimeoutCheckingWinner
+    pub fn first_eliminated_candidates(&self,timeout:&mut TimeOut) -> Result<Vec<CandidateIndex>,RaireError> {
+        if timeout.quick_check_timeout() { return Err(RaireError::TimeoutCheckingWinner); }
+        let all_candidates : Vec<CandidateIndex> = (0..self.num_candidates()).map(CandidateIndex).collect();
+        let tallies = self.restricted_tallies(&all_candidates);
+        Ok(match tallies.iter().min() {
+            Some(&min_tally) => all_candidates.into_iter().zip(tallies).filter(|(_,tally)|*tally==min_tally).map(|(c,_)|c).collect(),
+            None => vec![],
+        })
+    }
+
     /// only possible error is RaireError::TimeoutCheckingWinner
     pub fn run_election(&self,timeout:&mut TimeOut) -> Result<IRVResult,RaireError> {
         let mut work = IRVElectionWork{ winner_given_continuing_candidates: Default::default(), elimination_order: vec![] };
         let all_candidates : Vec<CandidateIndex> = (0..self.num_candidates()).into_iter().map(|c|CandidateIndex(c)).collect();
-        let possible_winners = work.find_all_possible_winners(all_candidates,&self,timeout)?;
+        let possible_winners = timeout.within_winner_computation_limit(|timeout|work.find_all_possible_winners(all_candidates,&self,timeout))?;
         Ok(IRVResult{ possible_winners, elimination_order: work.elimination_order })
     }
 
+
+    /// Run the election with the Coombs method instead of IRV, for comparison by researchers. RAIRE cannot audit this.
+    ///
+    /// If a candidate has a majority of the first preferences of the non-exhausted ballots, they win.
+    /// Otherwise the candidate ranked last amongst the continuing candidates on the most ballots is eliminated,
+    /// and the process repeats. A ballot that does not rank all candidates counts as a last place for the lowest ranked continuing
+    /// candidate on it. As with [Self::run_election], all possible tie resolutions are tried. In the elimination order, the
+    /// candidates left when there is a majority winner are listed in increasing order of tally.
+    ///
+    /// only possible error is RaireError::TimeoutCheckingWinner
+    pub fn run_coombs(&self,timeout:&mut TimeOut) -> Result<IRVResult,RaireError> {
+        let mut work = CoombsElectionWork{ winner_given_continuing_candidates: Default::default(), elimination_order: vec![] };
+        let all_candidates : Vec<CandidateIndex> = (0..self.num_candidates()).map(CandidateIndex).collect();
+        let possible_winners = work.find_all_possible_winners(all_candidates,self,timeout)?;
+        Ok(IRVResult{ possible_winners, elimination_order: work.elimination_order })
+    }
+
+    /// The number of votes for which each of the continuing candidates is the lowest ranked continuing candidate.
+    pub fn restricted_last_place_tallies(&self,continuing:&[CandidateIndex]) -> Vec<BallotPaperCount> {
+        let mut res = vec![BallotPaperCount(0);continuing.len()];
+        let mut position_in_continuing : Vec<Option<usize>> = vec![None;self.num_candidates() as usize];
+        for (i,c) in continuing.iter().enumerate() { position_in_continuing[c.0 as usize]=Some(i); }
+        for v in &self.votes {
+            if let Some(i) = v.prefs.iter().rev().find_map(|c|position_in_continuing[c.0 as usize]) { res[i]+=v.n; }
+        }
+        res
+    }
 }
 
-/// The result of an IRV election.
+/// The result of an IRV election (or other method such as [Votes::run_coombs]).
 pub struct IRVResult {
     /// Possible winners under IRV with no tie resolution. There may be tie resolution rules, but such a close election is not auditable stochastically.
     pub possible_winners : Vec<CandidateIndex>,
@@ -314,3 +549,48 @@ impl IRVElectionWork {
         None
     }
 }
+
+struct CoombsElectionWork {
+    /// Key is a list of continuing candidates, in canonical sorted order.
+    /// Value is a list of possible candidates who could win from that point.
+    winner_given_continuing_candidates : HashMap<Vec<CandidateIndex>,Vec<CandidateIndex>>,
+    /// One order in which candidates are eliminated.
+    elimination_order : Vec<CandidateIndex>,
+}
+
+impl CoombsElectionWork {
+    /// Find all possible winners, trying all options with ties, with a set of given continuing votes.
+    /// Like [IRVElectionWork::find_all_possible_winners], this uses dynamic programming on the continuing candidates.
+    fn find_all_possible_winners(&mut self,continuing:Vec<CandidateIndex>,votes:&Votes,timeout:&mut TimeOut) -> Result<Vec<CandidateIndex>,RaireError> {
+        if timeout.quick_check_timeout() { return Err(RaireError::TimeoutCheckingWinner); }
+        // Whether we are in the path of the first depth first traversal of the tree of elimination orders.
+        let on_first_path = self.elimination_order.len()+continuing.len()==votes.num_candidates() as usize;
+        if continuing.is_empty() { return Ok(vec![]); }
+        if let Some(already_computed) = self.winner_given_continuing_candidates.get(&continuing) { return Ok(already_computed.clone()); }
+        let tallies = votes.restricted_tallies(&continuing);
+        let total : BallotPaperCount = tallies.iter().copied().sum();
+        if let Some(majority) = (0..continuing.len()).find(|&i|continuing.len()==1 || tallies[i].0*2>total.0) {
+            if on_first_path {
+                let mut order : Vec<usize> = (0..continuing.len()).filter(|&i|i!=majority).collect();
+                order.sort_by_key(|&i|tallies[i]);
+                self.elimination_order.extend(order.into_iter().map(|i|continuing[i]));
+                self.elimination_order.push(continuing[majority]);
+            }
+            return Ok(vec![continuing[majority]]);
+        }
+        let last_place = votes.restricted_last_place_tallies(&continuing);
+        let max_last_place = *last_place.iter().max().unwrap();
+        let mut winners = HashSet::new();
+        for i in 0..continuing.len() {
+            if max_last_place==last_place[i] { // this is a plausible candidate to exclude. There may be a tie in which case there are multiple options. Try them all.
+                if self.elimination_order.len()+continuing.len()==votes.num_candidates() as usize { self.elimination_order.push(continuing[i]); }
+                let mut new_continuing = continuing[0..i].to_vec();
+                new_continuing.extend_from_slice(&continuing[i+1..]);
+                for c in self.find_all_possible_winners(new_continuing,votes,timeout)? { winners.insert(c); }
+            }
+        }
+        let winners : Vec<CandidateIndex> = winners.into_iter().collect();
+        self.winner_given_continuing_candidates.insert(continuing,winners.clone());
+        Ok(winners)
+    }
+}
@@ -8,11 +8,19 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
-use std::time::Duration;
-use crate::audit_type::Audit;
-use crate::irv::{CandidateIndex, Vote, Votes};
-use crate::raire_algorithm::{raire, RaireResult, TrimAlgorithm};
-use serde::Deserialize;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature="sha2")]
+use sha2::{Digest, Sha256};
+use crate::assertions::{Assertion, AssertionAndDifficulty};
+use crate::audit_type::{Audit, AuditType, BallotComparisonOneOnDilutedMargin};
+use crate::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
+use crate::raire_algorithm::{include_required_assertions, raire, raire_multi, raire_resume, raire_with_checkpoints, raire_with_options, raire_with_partial, score_required_assertions, FrontierOrdering, PartialRaireResult, RaireOptions, RaireResult, SearchCheckpoint, TieBreak, TrimAlgorithm};
+use crate::metadata::ContestMetadata;
+use crate::timeout::{TimeOut, TimeoutCause};
+use crate::tree_showing_what_assertions_pruned_leaves::{human_order, order_assertions_and_remove_unnecessary};
+use serde::{Deserialize, Deserializer, Serializer};
 use serde::Serialize;
 
 pub mod assertions;
@@ -21,12 +29,13 @@ pub mod audit_type;
 pub mod raire_algorithm;
 pub mod tree_showing_what_assertions_pruned_leaves;
 pub mod timeout;
+pub mod metadata;
 
 #[derive(thiserror::Error, Debug,Serialize,Deserialize,Clone)]
 pub enum RaireError {
     #[error("there must be at least one candidate")]
     InvalidNumberOfCandidates,
-    #[error("time out limit should be greater than zero")]
+    #[error("time and work limits should be greater than zero")]
     InvalidTimeout,
     #[error("candidate numbers in the preferences lists should be integers 0 to num_candidates-1")]
     InvalidCandidateNumber,
@@ -53,17 +62,91 @@ pub enum RaireError {
     InternalErrorDidntRuleOutLoser,
     #[error("internal error - trimming couldn't work")]
     InternalErrorTrimming,
+    #[error("the assertion, together with the existing assertions, rules out the winner")]
+    AssertionRulesOutWinner,
+    /// The assertions with the given indices contradict each other (or if both indices are the same, that assertion is impossible).
+    #[error("assertions {0} and {1} are inconsistent")]
+    InconsistentAssertions(usize,usize),
+    /// An assertion was constructed that does not make sense, such as a NotEliminatedNext assertion whose winner or loser is not continuing.
+    #[error("invalid assertion - the winner and loser must be different, and both continuing")]
+    InvalidAssertion,
+    /// The ballots counted for candidates (first) plus the exhausted ballots (second) do not add up to the total number of ballots (third).
+    /// This indicates ballots have been lost, for instance by a bug in an importer. See [Votes::verify_tally_conservation].
+    #[error("{0} ballots counted for candidates plus {1} exhausted ballots does not equal the total {2} ballots")]
+    TallyNotConserved(BallotPaperCount,BallotPaperCount,BallotPaperCount),
+    /// The assertion with the given index is not true for the votes.
+    #[error("assertion {0} does not hold for the votes")]
+    AssertionDoesNotHold(usize),
+    /// The claimed result is for a different number of candidates (the second) than the problem (the first).
+    #[error("the problem has {0} candidates but the result has {1}")]
+    WrongNumberOfCandidates(u32,u32),
+    /// A patch could not be applied to a problem, see [RaireProblem::apply_patch].
+    #[error("invalid patch - {0}")]
+    InvalidPatch(String),
+    /// The winner was given by a name that is not one of the candidates in the metadata.
+    #[error("no candidate is called {0}")]
+    UnknownCandidateName(String),
+    /// The winner was given by a name shared by more than one candidate in the metadata.
+    #[error("more than one candidate is called {0}")]
+    AmbiguousCandidateName(String),
+    /// A contest in a [RaireBatchProblem], combined with the shared fields, is not a valid [RaireProblem].
+    #[error("invalid contest - {0}")]
+    InvalidContest(String),
+    /// A checkpoint passed to [raire_algorithm::raire_resume] is not a valid state of the search.
+    #[error("invalid checkpoint - {0}")]
+    InvalidCheckpoint(String),
+    /// There is no assertion with the given index in the result.
+    #[error("there is no assertion with index {0}")]
+    InvalidAssertionIndex(usize),
+    /// The tally for the given candidate, when all candidates are continuing, (second) differs from their first preference
+    /// tally computed when the votes were made (third). This indicates ballots have been changed. See [Votes::verify_tally_conservation].
+    #[error("candidate {0} has {1} first preferences but had {2} when the votes were made")]
+    FirstPreferenceTallyChanged(CandidateIndex,BallotPaperCount,BallotPaperCount),
+    /// The `upset_prior` of a [audit_type::BayesianAudit] is not strictly between 0 and 0.5.
+    #[error("the upset prior {0} should be greater than 0 and less than 0.5")]
+    InvalidUpsetPrior(f64),
 }
 /// This file contains an API suitable for a web service.
 
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+#[serde(untagged)]
+/// The announced winner of a contest as given in the JSON for a [RaireProblem], either a candidate index (a number) or the
+/// name of a candidate (a string) in the `candidates` of the metadata. A name is less error prone if the order of the
+/// candidates may have been changed. A name is resolved to an index when the problem is read.
+pub enum WinnerSpec {
+    Index(CandidateIndex),
+    Name(String),
+}
+
+impl WinnerSpec {
+    /// The index of the winner, looking up a name in `metadata`.
+    pub fn resolve(&self,metadata:&ContestMetadata) -> Result<CandidateIndex,RaireError> {
+        match self {
+            WinnerSpec::Index(c) => Ok(*c),
+            WinnerSpec::Name(name) => {
+                let mut matching = metadata.candidates.iter().enumerate().filter(|(_,n)|*n==name).map(|(i,_)|CandidateIndex(i as u32));
+                match (matching.next(),matching.next()) {
+                    (Some(c),None) => Ok(c),
+                    (None,_) => Err(RaireError::UnknownCandidateName(name.clone())),
+                    (Some(_),Some(_)) => Err(RaireError::AmbiguousCandidateName(name.clone())),
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone,Debug,Serialize,Deserialize)]
+#[serde(remote="Self")]
 pub struct RaireProblem {
+    /// Not used for the computation, but copied to the solution. See [Self::contest_metadata] for the fields used by tools.
+    #[serde(default)]
     pub metadata : serde_json::Value,
     pub num_candidates : usize,
     pub votes : Vec<Vote>,
     /// Not strictly necessary, only used for consistency checking with the announced winner.
     /// But I recommend it.
     /// We don't want to announce the wrong winner, and then for the audit to prove the winner is the correct person, and no one notice that that was not the person announced.
+    /// May be given by name in JSON, see [WinnerSpec].
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub winner : Option<CandidateIndex>,
     pub audit : Audit,
@@ -75,27 +158,741 @@ pub struct RaireProblem {
     pub difficulty_estimate : Option<f64>,
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub time_limit_seconds : Option<f64>,
+    /// Whether to exclude candidates provably behind the winner by a NEB assertion from the main search unless needed. Default false.
+    /// This is an optimization that does not change the difficulty of the result, but may change which assertions are produced.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub use_neb_prepass : Option<bool>,
+    /// Whether to record every assertion considered during the search, including rejected ones, in the result. Default false.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub record_considered_assertions : Option<bool>,
+    /// Whether to include statistics about how effectively the search was pruned in the result. Default false.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub record_search_stats : Option<bool>,
+    /// How to choose between equally difficult assertions. Default [TieBreak::PreferNEB].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tie_break : Option<TieBreak>,
+    /// The order in which elimination order suffixes are expanded in the search. Default [FrontierOrdering::BestFirst].
+    /// This does not change the difficulty of the result, but may change how long the search takes.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub frontier_ordering : Option<FrontierOrdering>,
+    /// If present, only the hardest this many assertions are returned, and the `truncated` flag is set in the result if any were removed.
+    /// This is for user interfaces with limited display space. The truncated assertions do not prove the winner, so must NOT be used for an audit.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_assertions_returned : Option<usize>,
+    /// If present, during the search any elimination order suffix that can be ruled out by an assertion with at most this
+    /// difficulty is immediately ruled out by it, without looking for a better assertion. This makes the search faster, at
+    /// the cost of possibly more or harder (but no harder than this) assertions. Unlike `difficulty_estimate`, it does not set a lower bound.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub auto_accept_difficulty : Option<f64>,
+    /// If present, a limit on the units of work done (see [TimeTaken::work]). This is useful as, unlike `time_limit_seconds`,
+    /// it does not depend on the speed of the computer. If both are present, whichever is exceeded first causes a timeout.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub work_limit : Option<u64>,
+    /// If present, and `winner` is one of several tied winners, the statutory tie resolution: in a tie for elimination,
+    /// the candidate listed earliest is eliminated. The result is then conditional on this tie resolution. See [tie_break_order_solve].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tie_break_order : Option<Vec<CandidateIndex>>,
+    /// If present, a separate limit on the seconds spent determining the winner, in addition to `time_limit_seconds`.
+    /// This can dominate on pathological contests with many ties. If exceeded, the error is [RaireError::TimeoutCheckingWinner]
+    /// with a `timeout_cause` of [TimeoutCause::WinnerComputationTime].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub winner_computation_time_limit : Option<f64>,
+    /// If present, a separate limit on the seconds spent finding assertions, in addition to `time_limit_seconds`.
+    /// If exceeded, the error is [RaireError::TimeoutFindingAssertions] with a `timeout_cause` of [TimeoutCause::FindingAssertionsTime].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub time_limit_finding_assertions : Option<f64>,
+    /// If present, a separate limit on the seconds spent trimming assertions, in addition to `time_limit_seconds`, so a slow trim
+    /// can be capped without risking the search for assertions. If exceeded, the untrimmed assertions are returned with
+    /// `warning_trim_timed_out` and a `timeout_cause` of [TimeoutCause::TrimmingTime].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub time_limit_trimming : Option<f64>,
+    /// If true, and the search for assertions times out, the state of the search is returned in [RaireSolution::partial].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub return_partial_on_timeout : Option<bool>,
+    /// If true, the tallies compared by each assertion are included in the output. See [RaireResult::add_tallies].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub include_tallies : Option<bool>,
+    /// If true, the solution includes its [Provenance]. This is off by default as the timestamp in it makes the output
+    /// differ from run to run, and with the `sha2` feature it costs a hash of the problem.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub include_provenance : Option<bool>,
+    /// Assertions that must be included in the output, for instance ones already committed to in an earlier round
+    /// of an audit. See [RaireOptions::required_assertions].
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub required_assertions : Vec<Assertion>,
+    /// If present, NEN assertions with more than this many continuing candidates are not used, as they can be hard to explain
+    /// to audit boards. Zero means only NEB assertions are used. This may increase the difficulty. See [RaireOptions::max_nen_continuing].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_nen_continuing : Option<usize>,
+}
+
+impl Default for RaireProblem {
+    /// A problem with no candidates or votes, and every option absent. Use as `RaireProblem{num_candidates,votes,audit,..Default::default()}`.
+    /// The default audit is a placeholder ([Audit::OneOnMargin] with no auditable ballots) that should always be replaced.
+    fn default() -> Self {
+        RaireProblem{
+            metadata: serde_json::Value::Null,
+            num_candidates: 0,
+            votes: vec![],
+            winner: None,
+            audit: Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(0))),
+            trim_algorithm: None,
+            difficulty_estimate: None,
+            time_limit_seconds: None,
+            use_neb_prepass: None,
+            record_considered_assertions: None,
+            record_search_stats: None,
+            tie_break: None,
+            frontier_ordering: None,
+            max_assertions_returned: None,
+            auto_accept_difficulty: None,
+            work_limit: None,
+            tie_break_order: None,
+            winner_computation_time_limit: None,
+            time_limit_finding_assertions: None,
+            time_limit_trimming: None,
+            return_partial_on_timeout: None,
+            include_tallies: None,
+            include_provenance: None,
+            required_assertions: vec![],
+            max_nen_continuing: None,
+        }
+    }
 }
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct RaireSolution {
+    /// A copy of the metadata in the problem.
+    #[serde(default)]
     pub metadata : serde_json::Value,
     pub solution : Result<RaireResult,RaireError>,
+    /// Which limit, if any, caused a timeout, either giving a timeout error or a partial result.
+    #[serde(default,skip_serializing_if = "TimeoutCause::is_none")]
+    pub timeout_cause : TimeoutCause,
+    /// If requested with [RaireProblem::return_partial_on_timeout], the state of the search when it timed out looking for assertions.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub partial : Option<PartialRaireResult>,
+    /// Where the solution came from. Not present in solutions from older versions.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub provenance : Option<Provenance>,
 }
 
-impl RaireProblem {
-    pub fn solve(self) -> RaireSolution {
-        let solution = {
-            if self.time_limit_seconds.is_some_and(|v|v<=0.0||v.is_nan()) {
-                Err(RaireError::InvalidTimeout)
-            } else {
-                let mut timeout = timeout::TimeOut::new(None,self.time_limit_seconds.map(|seconds|Duration::from_secs_f64(seconds)));
-                match Votes::new(self.votes,self.num_candidates) {
-                    Ok(votes) => raire(&votes,self.winner,&self.audit,self.trim_algorithm.unwrap_or(TrimAlgorithm::MinimizeTree),&mut timeout),
-                    Err(e) => Err(e)
+impl RaireSolution {
+    /// The metadata as a typed [ContestMetadata].
+    pub fn contest_metadata(&self) -> ContestMetadata { self.metadata.clone().into() }
+
+    /// A canonical JSON serialization, so that two runs on the same input give byte-identical output, suitable for
+    /// hashing and signing. The assertions are in the order of [human_order], object keys are sorted, there is no
+    /// whitespace, and numbers are written in the shortest form that reads back exactly. Things that vary from run
+    /// to run are left out: the `seconds` in the timing information, and the `timestamp` in the provenance.
+    /// The output can still be read as a [RaireSolution].
+    pub fn to_canonical_json(&self) -> String {
+        let mut solution = self.clone();
+        if let Ok(result) = &mut solution.solution {
+            result.assertions.sort_by(|a,b|human_order(&a.assertion,&b.assertion));
+        }
+        let mut value = serde_json::to_value(&solution).expect("RaireSolution should always be serializable");
+        if let Some(result) = value.pointer_mut("/solution/Ok").and_then(|r|r.as_object_mut()) {
+            for (key,time_taken) in result.iter_mut() {
+                if key.starts_with("time_to_") {
+                    if let Some(time_taken) = time_taken.as_object_mut() { time_taken.remove("seconds"); }
                 }
             }
+        }
+        if let Some(provenance) = value.get_mut("provenance").and_then(|p|p.as_object_mut()) { provenance.remove("timestamp"); }
+        let mut res = String::new();
+        write_canonical_json(&value,&mut res);
+        res
+    }
+}
+
+/// Write `value` as JSON with object keys sorted and no whitespace.
+fn write_canonical_json(value:&serde_json::Value,out:&mut String) {
+    match value {
+        serde_json::Value::Array(values) => {
+            out.push('[');
+            for (i,v) in values.iter().enumerate() {
+                if i>0 { out.push(','); }
+                write_canonical_json(v,out);
+            }
+            out.push(']');
+        }
+        serde_json::Value::Object(map) => {
+            let mut keys : Vec<&String> = map.keys().collect();
+            keys.sort_unstable();
+            out.push('{');
+            for (i,key) in keys.into_iter().enumerate() {
+                if i>0 { out.push(','); }
+                out.push_str(&serde_json::Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical_json(&map[key],out);
+            }
+            out.push('}');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// The version of the format of [RaireSolution], incremented when the format changes incompatibly.
+pub const SOLUTION_FORMAT_VERSION : u32 = 1;
+
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+/// Enough information to trace an archived solution back to the exact software and data that produced it.
+pub struct Provenance {
+    /// See [SOLUTION_FORMAT_VERSION].
+    pub format_version : u32,
+    /// The version of the raire crate that produced the solution.
+    pub solver_version : String,
+    /// When the solution was produced, in seconds since 1970-01-01 UTC. Zero if not known, for instance if read
+    /// from canonical JSON (see [RaireSolution::to_canonical_json]).
+    #[serde(default)]
+    pub timestamp : u64,
+    /// The SHA-256 hash (as lower case hex) of the problem, serialized as JSON. Only present if the `sha2` feature is enabled.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub problem_sha256 : Option<String>,
+}
+
+impl Provenance {
+    /// The provenance of a solution to `problem` produced now.
+    #[cfg_attr(not(feature="sha2"),allow(unused_variables))]
+    pub fn new(problem:&RaireProblem) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d|d.as_secs()).unwrap_or(0);
+        #[cfg(feature="sha2")]
+        let problem_sha256 = Some(problem.sha256());
+        #[cfg(not(feature="sha2"))]
+        let problem_sha256 = None;
+        Provenance{format_version:SOLUTION_FORMAT_VERSION,solver_version:env!("CARGO_PKG_VERSION").to_string(),timestamp,problem_sha256}
+    }
+}
+
+impl Serialize for RaireProblem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RaireProblem::serialize(self,serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RaireProblem {
+    /// As derived, except that a winner given by name is resolved against the candidates in the metadata, see [WinnerSpec].
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut json = serde_json::Value::deserialize(deserializer)?;
+        if let Some(Ok(WinnerSpec::Name(name))) = json.get("winner").map(WinnerSpec::deserialize) {
+            let winner = WinnerSpec::Name(name).resolve(&json.get("metadata").cloned().unwrap_or_default().into()).map_err(serde::de::Error::custom)?;
+            json["winner"] = serde_json::to_value(winner).map_err(serde::de::Error::custom)?;
+        }
+        RaireProblem::deserialize(json).map_err(serde::de::Error::custom)
+    }
+}
+
+impl RaireProblem {
+    /// The SHA-256 hash (as lower case hex) of the problem serialized as JSON, see [Provenance]. Requires the `sha2` feature.
+    #[cfg(feature="sha2")]
+    pub fn sha256(&self) -> String {
+        let json = serde_json::to_string(self).expect("RaireProblem should always be serializable");
+        Sha256::digest(json.as_bytes()).iter().map(|b|format!("{:02x}",b)).collect()
+    }
+
+    /// The provenance of a solution to this problem produced now, if requested with [Self::include_provenance].
+    fn provenance(&self) -> Option<Provenance> {
+        if self.include_provenance.unwrap_or(false) { Some(Provenance::new(self)) } else { None }
+    }
+
+    /// The metadata as a typed [ContestMetadata].
+    pub fn contest_metadata(&self) -> ContestMetadata { self.metadata.clone().into() }
+
+    /// The options for the search for assertions given in the problem, with defaults for those not given.
+    pub fn options(&self) -> RaireOptions {
+        RaireOptions{
+            use_neb_prepass: self.use_neb_prepass.unwrap_or(false),
+            frontier_ordering: self.frontier_ordering.unwrap_or_default(),
+            tie_break: self.tie_break.unwrap_or_default(),
+            record_considered_assertions: self.record_considered_assertions.unwrap_or(false),
+            record_search_stats: self.record_search_stats.unwrap_or(false),
+            auto_accept_difficulty: self.auto_accept_difficulty,
+            max_nen_continuing: self.max_nen_continuing,
+            required_assertions: self.required_assertions.clone(),
+        }
+    }
+
+    pub fn solve(self) -> RaireSolution { self.solve_with_optional_cancel(None) }
+
+    /// Solve the problem as [Self::solve] does, but give up if `cancel` is set to true by another thread, for instance
+    /// when the client requesting the solution has disconnected. The solution is then an error with a `timeout_cause`
+    /// of [TimeoutCause::Cancel].
+    pub fn solve_cancellable(self,cancel:Arc<AtomicBool>) -> RaireSolution { self.solve_with_optional_cancel(Some(cancel)) }
+
+    fn solve_with_optional_cancel(self,cancel:Option<Arc<AtomicBool>>) -> RaireSolution {
+        let provenance = self.provenance();
+        let mut timeout_cause = TimeoutCause::None;
+        let mut partial = None;
+        let timeout = problem_timeout(&self).map(|timeout| match cancel {
+            Some(cancel) => timeout.with_cancel_flag(cancel),
+            None => timeout,
+        });
+        let (winner,options) = (self.winner,self.options());
+        let solution = timeout.and_then(|mut timeout|{
+            let votes = Votes::new(self.votes,self.num_candidates)?;
+            let trim_algorithm = self.trim_algorithm.unwrap_or(TrimAlgorithm::MinimizeTree);
+            let result = if self.return_partial_on_timeout.unwrap_or(false) {
+                let (result,found) = raire_with_partial(&votes,winner,&self.audit,trim_algorithm,&options,&mut timeout);
+                partial = found;
+                result
+            } else { raire_with_options(&votes,winner,&self.audit,trim_algorithm,&options,&mut timeout) };
+            let result = match (result,winner,&self.tie_break_order) {
+                (Err(RaireError::TiedWinners(tied_winners)),Some(winner),Some(tie_break_order)) if tied_winners.contains(&winner) => tie_break_order_solve(&votes,winner,&self.audit,tie_break_order,trim_algorithm,&options,&mut timeout),
+                (result,_,_) => result,
+            };
+            timeout_cause = timeout.cause();
+            result.map(|mut result|{
+                if self.include_tallies.unwrap_or(false) { result.add_tallies(&votes); }
+                truncate_if_requested(result,self.max_assertions_returned)
+            })
+        });
+        RaireSolution{metadata:self.metadata,solution,timeout_cause,partial,provenance}
+    }
+
+    /// Solve the problem as [Self::solve] does, also passing the state of the search for assertions to `on_checkpoint` every
+    /// `checkpoint_interval`, and continuing the search from `resume_from` if given, so a long run can survive the process
+    /// being restarted. See [raire_with_checkpoints] and [raire_resume]. `tie_break_order` and `return_partial_on_timeout`
+    /// are not supported.
+    pub fn solve_with_checkpoints(self,resume_from:Option<&SearchCheckpoint>,checkpoint_interval:Duration,on_checkpoint:&mut dyn FnMut(&SearchCheckpoint)) -> RaireSolution {
+        let provenance = self.provenance();
+        let mut timeout_cause = TimeoutCause::None;
+        let winner = self.winner;
+        let solution = problem_timeout(&self).and_then(|mut timeout|{
+            let votes = Votes::new(self.votes.clone(),self.num_candidates)?;
+            let (trim_algorithm,options) = (self.trim_algorithm.unwrap_or(TrimAlgorithm::MinimizeTree),self.options());
+            let result = match resume_from {
+                Some(checkpoint) if winner.is_some_and(|winner|winner!=checkpoint.winner) => Err(RaireError::WrongWinner(vec![checkpoint.winner])),
+                Some(checkpoint) => raire_resume(&votes,&self.audit,checkpoint,trim_algorithm,&options,checkpoint_interval,on_checkpoint,&mut timeout),
+                None => raire_with_checkpoints(&votes,winner,&self.audit,trim_algorithm,&options,checkpoint_interval,on_checkpoint,&mut timeout),
+            };
+            timeout_cause = timeout.cause();
+            result.map(|mut result|{
+                if self.include_tallies.unwrap_or(false) { result.add_tallies(&votes); }
+                truncate_if_requested(result,self.max_assertions_returned)
+            })
+        });
+        RaireSolution{metadata:self.metadata,solution,timeout_cause,partial:None,provenance}
+    }
+
+    /// Apply a JSON Merge Patch (RFC 7386) to the problem, e.g. `{"time_limit_seconds":10,"trim_algorithm":null}`, so an interactive
+    /// tool can change some fields without resending the votes. Objects are merged recursively, and a null removes a field
+    /// (resetting it to its default). The votes cannot be patched, as they are usually large.
+    ///
+    /// The patched problem is checked for valid time limits and candidate numbers. If anything is wrong, `self` is unchanged
+    /// and an error is returned, usually [RaireError::InvalidPatch].
+    pub fn apply_patch(&mut self,patch:serde_json::Value) -> Result<(),RaireError> {
+        if patch.get("votes").is_some() { return Err(RaireError::InvalidPatch("votes cannot be patched".to_string())); }
+        let votes = std::mem::take(&mut self.votes); // avoid copying the votes.
+        let patched = serde_json::to_value(&*self).and_then(|mut problem|{
+            merge_patch(&mut problem,&patch);
+            serde_json::from_value::<RaireProblem>(problem)
+        }).map_err(|e|RaireError::InvalidPatch(e.to_string()));
+        self.votes = votes;
+        let mut patched = patched?;
+        problem_timeout(&patched)?;
+        if patched.num_candidates==0 { return Err(RaireError::InvalidNumberOfCandidates); }
+        let valid = |c:&CandidateIndex|(c.0 as usize)<patched.num_candidates;
+        if !(patched.winner.iter().all(valid) && self.votes.iter().all(|v|v.prefs.iter().all(valid))) { return Err(RaireError::InvalidCandidateNumber); }
+        patched.votes = std::mem::take(&mut self.votes);
+        *self = patched;
+        Ok(())
+    }
+
+    /// Check that a claimed result, for instance produced by a third party, is a valid solution to this problem:
+    /// the assertions all hold for the votes (see [RaireResult::verify_assertions_hold_for_votes]), and together
+    /// prove the claimed winner (see [RaireResult::verify_result_does_prove_winner]), who must be the winner
+    /// given in the problem, if any. The difficulties claimed in the result are not checked.
+    ///
+    /// Note that this can be slow for a large number of candidates.
+    pub fn verify_solution(&self,result:&RaireResult) -> Result<(),RaireError> {
+        let votes = Votes::new(self.votes.clone(),self.num_candidates)?;
+        if votes.num_candidates()!=result.num_candidates { return Err(RaireError::WrongNumberOfCandidates(votes.num_candidates(),result.num_candidates)); }
+        if result.winner.0>=result.num_candidates { return Err(RaireError::InvalidCandidateNumber); }
+        if let Some(winner) = self.winner {
+            if winner!=result.winner { return Err(RaireError::WrongWinner(vec![winner])); }
+        }
+        result.verify_assertions_hold_for_votes(&votes)?;
+        result.verify_result_does_prove_winner()
+    }
+
+    /// Recompute the difficulties of the assertions in a previously computed `result` for the audit type in this problem,
+    /// which may be different from the one the result was computed for, using the votes in this problem.
+    /// The assertions are not searched for again. See [RaireResult::rescore].
+    pub fn rescore(&self,result:&mut RaireResult) -> Result<(),RaireError> {
+        let votes = Votes::new(self.votes.clone(),self.num_candidates)?;
+        if votes.num_candidates()!=result.num_candidates { return Err(RaireError::WrongNumberOfCandidates(votes.num_candidates(),result.num_candidates)); }
+        result.rescore(&votes,&self.audit);
+        Ok(())
+    }
+
+    /// Solve the problem with both [TrimAlgorithm::MinimizeTree] (first) and [TrimAlgorithm::MinimizeAssertions] (second),
+    /// which is about twice as fast as calling [Self::solve] twice as the search for assertions is only done once, with
+    /// each trim applied to a copy of the untrimmed assertions. The `trim_algorithm` in `self` is ignored.
+    pub fn solve_both_trims(&self) -> (RaireSolution,RaireSolution) {
+        let mut timeout_cause = TimeoutCause::None;
+        let solutions = problem_timeout(self).and_then(|mut timeout|{
+            let votes = Votes::new(self.votes.clone(),self.num_candidates)?;
+            let untrimmed = raire_with_options(&votes,self.winner,&self.audit,TrimAlgorithm::None,&self.options(),&mut timeout);
+            timeout_cause = timeout.cause();
+            let untrimmed = untrimmed?;
+            let required = score_required_assertions(&self.required_assertions,&votes,&self.audit,&untrimmed.example_elimination_order)?;
+            let mut trim = |trim_algorithm:TrimAlgorithm| match trim_copy(&untrimmed,&required,trim_algorithm,&mut timeout) {
+                Err(RaireError::TimeoutTrimmingAssertions) => Ok(RaireResult{warning_trim_timed_out:true,..untrimmed.clone()}),
+                result => result,
+            }.map(|mut result|{
+                if self.include_tallies.unwrap_or(false) { result.add_tallies(&votes); }
+                truncate_if_requested(result,self.max_assertions_returned)
+            });
+            let solutions = (trim(TrimAlgorithm::MinimizeTree),trim(TrimAlgorithm::MinimizeAssertions));
+            timeout_cause = timeout.cause();
+            Ok(solutions)
+        });
+        let (tree,assertions) = match solutions {
+            Ok(solutions) => solutions,
+            Err(e) => (Err(e.clone()),Err(e)),
         };
-        RaireSolution{metadata:self.metadata,solution}
+        let provenance = self.provenance();
+        (RaireSolution{metadata:self.metadata.clone(),solution:tree,timeout_cause,partial:None,provenance:provenance.clone()},RaireSolution{metadata:self.metadata.clone(),solution:assertions,timeout_cause,partial:None,provenance})
+    }
+
+    /// Solve the problem with each of several audit types instead of the audit in `self`, for instance to get both the
+    /// ballot comparison and the ballot polling difficulty for planning. This is faster than solving for each audit type
+    /// separately as the winner is only determined once. The time and work limits apply to the whole computation, and
+    /// `tie_break_order` and `return_partial_on_timeout` are not supported. There is a solution for each audit type, in order.
+    pub fn solve_multi(&self,audits:&[Audit]) -> Vec<RaireSolution> {
+        let mut timeout_cause = TimeoutCause::None;
+        let results = problem_timeout(self).and_then(|mut timeout|{
+            let votes = Votes::new(self.votes.clone(),self.num_candidates)?;
+            let results = raire_multi(&votes,self.winner,audits,self.trim_algorithm.unwrap_or(TrimAlgorithm::MinimizeTree),&self.options(),&mut timeout);
+            timeout_cause = timeout.cause();
+            Ok(results?.into_iter().map(|result|result.map(|mut result|{
+                if self.include_tallies.unwrap_or(false) { result.add_tallies(&votes); }
+                truncate_if_requested(result,self.max_assertions_returned)
+            })).collect::<Vec<_>>())
+        });
+        audits.iter().enumerate().map(|(i,audit)|{
+            let solution = match &results {
+                Ok(results) => results[i].clone(),
+                Err(e) => Err(e.clone()),
+            };
+            let timeout_cause = match &solution {
+                Ok(result) if !result.warning_trim_timed_out => TimeoutCause::None,
+                _ => timeout_cause,
+            };
+            let provenance = RaireProblem{audit:audit.clone(),..self.clone()}.provenance();
+            RaireSolution{metadata:self.metadata.clone(),solution,timeout_cause,partial:None,provenance}
+        }).collect()
+    }
+
+    /// Solve the problem as if the `disqualified` candidates were not in the contest, for instance if they were
+    /// disqualified after counting. The votes are projected (see [Votes::project]) to exclude the disqualified
+    /// candidates, who are left in the problem with no votes so that candidate indices are unchanged.
+    /// The provenance of the solution is for `self`, the problem before projection.
+    pub fn solve_with_disqualified(&self,disqualified:&[CandidateIndex]) -> RaireSolution {
+        match Votes::new(self.votes.clone(),self.num_candidates) {
+            Ok(votes) => RaireSolution{provenance:self.provenance(),..RaireProblem{votes:votes.project(disqualified).votes,..self.clone()}.solve()},
+            Err(e) => RaireSolution{metadata:self.metadata.clone(),solution:Err(e),timeout_cause:TimeoutCause::None,partial:None,provenance:self.provenance()},
+        }
+    }
+}
+
+/// Solve the same contest (as specified in `base`) with each of several alternative sets of votes, for instance from
+/// different rules for interpreting ambiguous marks on ballots. This lets one see whether the winner and difficulty
+/// are stable across interpretations. The votes in `base` are ignored.
+pub fn solve_under_interpretations(base:&RaireProblem,interpretations:&[Vec<Vote>]) -> Vec<RaireSolution> {
+    interpretations.iter().map(|votes|RaireProblem{votes:votes.clone(),..base.clone()}.solve()).collect()
+}
+
+/// Solve the same contest (as specified in `base`) at each of several confidence levels α, for instance to show
+/// how the difficulty of the audit depends upon the chosen risk limit. See [Audit::with_confidence].
+pub fn solve_across_confidence(base:&RaireProblem,confidences:&[f64]) -> Vec<RaireSolution> {
+    confidences.iter().map(|&confidence|RaireProblem{audit:base.audit.with_confidence(confidence),..base.clone()}.solve()).collect()
+}
+
+/// Check that the same winner is proven at each of several confidence levels α. This should always be the case,
+/// as the confidence affects the difficulty rather than the winner, but this guards against bugs.
+/// Returns false if any of the solutions is an error. Use [solve_across_confidence] to get the difficulty at each level.
+pub fn winner_stable_across_confidence(problem:&RaireProblem,confidences:&[f64]) -> bool {
+    let winners : Result<Vec<CandidateIndex>,RaireError> = solve_across_confidence(problem,confidences).into_iter().map(|s|s.solution.map(|r|r.winner)).collect();
+    winners.is_ok_and(|winners|winners.windows(2).all(|w|w[0]==w[1]))
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+/// One contest in a batch for [solve_batch_with_overrides].
+pub struct BatchEntry {
+    pub problem : RaireProblem,
+    /// If present, used instead of the audit in `problem`, for jurisdictions where different contests use different audit methods.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub audit_override : Option<Audit>,
+}
+
+/// Solve each contest in a batch, first replacing its audit by the `audit_override` if present. See [solve_batch].
+pub fn solve_batch_with_overrides(batch:Vec<BatchEntry>) -> Vec<RaireSolution> {
+    solve_batch(batch.into_iter().map(|entry| Ok(match entry.audit_override {
+        Some(audit) => RaireProblem{audit,..entry.problem},
+        None => entry.problem,
+    }))).collect()
+}
+
+/// Solve each contest in a batch in turn, each with its own time limit. This underlies all the ways of solving several
+/// contests: [RaireBatchProblem::solve_all], [solve_batch_with_overrides], and the `solve_folder` utility.
+///
+/// A contest that is not a valid problem is given as the error, with any metadata known for it, and gives a solution
+/// with that error and metadata, so that the other contests can still be solved. The solutions are computed lazily,
+/// so each can be used (e.g. written out) as soon as it is available.
+pub fn solve_batch(contests:impl IntoIterator<Item=Result<RaireProblem,(RaireError,serde_json::Value)>>) -> impl Iterator<Item=RaireSolution> {
+    contests.into_iter().map(|contest| match contest {
+        Ok(problem) => problem.solve(),
+        Err((error,metadata)) => RaireSolution{metadata,solution:Err(error),timeout_cause:TimeoutCause::None,partial:None,provenance:None},
+    })
+}
+
+/// Parse a contest in the JSON form of a [RaireProblem], merged over the `shared` fields as in [RaireBatchProblem].
+/// If it is not valid, the [RaireError::InvalidContest] error is returned with the metadata given in the contest (if any), as used by [solve_batch].
+pub fn parse_batch_contest(shared:&serde_json::Value,contest:&serde_json::Value) -> Result<RaireProblem,(RaireError,serde_json::Value)> {
+    let mut problem = shared.clone();
+    merge_patch(&mut problem,contest);
+    serde_json::from_value(problem).map_err(|e|(RaireError::InvalidContest(e.to_string()),contest.get("metadata").cloned().unwrap_or_default()))
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+/// Several contests with shared settings, for instance all the contests in an election. See [RaireBatchProblem::solve_all].
+pub struct RaireBatchProblem {
+    /// Fields shared by all contests, such as `audit`, `trim_algorithm` or `time_limit_seconds`, in the JSON form of a [RaireProblem].
+    #[serde(default)]
+    pub shared : serde_json::Value,
+    /// Each contest, in the JSON form of a [RaireProblem]. Each contest is merged over `shared` as a JSON Merge Patch
+    /// (RFC 7386), so fields given in a contest take precedence, and objects such as `audit` are merged field by field.
+    pub contests : Vec<serde_json::Value>,
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+/// The solutions to a [RaireBatchProblem], one per contest in the same order, with a summary.
+pub struct RaireBatchSolution {
+    pub solutions : Vec<RaireSolution>,
+    pub summary : RaireBatchSummary,
+}
+
+#[derive(Clone,Debug,Default,PartialEq,Serialize,Deserialize)]
+/// An overview of the solutions to a [RaireBatchProblem].
+pub struct RaireBatchSummary {
+    /// The number of contests successfully solved.
+    pub solved : usize,
+    /// The number of contests for which no assertions were found, for instance because of a timeout or a tie.
+    pub failed : usize,
+    /// The total number of assertions in all solved contests.
+    pub total_assertions : usize,
+    /// The index of the most difficult solved contest, which will dominate the audit effort.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub hardest_contest : Option<usize>,
+    /// The difficulty of the most difficult solved contest.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_difficulty : Option<f64>,
+}
+
+impl RaireBatchProblem {
+    /// The problem for each contest, after merging over the shared fields.
+    pub fn problems(&self) -> Vec<Result<RaireProblem,RaireError>> {
+        self.contests.iter().map(|contest|parse_batch_contest(&self.shared,contest).map_err(|(e,_)|e)).collect()
+    }
+
+    /// Solve each contest in turn, as [solve_batch] does. Each contest has its own time limit. A contest that is not valid gives a solution
+    /// with a [RaireError::InvalidContest] error, and the metadata given in the contest (if any), so that the other contests can still be solved.
+    pub fn solve_all(&self) -> RaireBatchSolution {
+        let solutions : Vec<RaireSolution> = solve_batch(self.contests.iter().map(|contest|parse_batch_contest(&self.shared,contest))).collect();
+        let mut summary = RaireBatchSummary::default();
+        for (index,solution) in solutions.iter().enumerate() {
+            match &solution.solution {
+                Ok(result) => {
+                    summary.solved+=1;
+                    summary.total_assertions+=result.assertions.len();
+                    if summary.max_difficulty.is_none_or(|max|result.difficulty>max) {
+                        summary.max_difficulty=Some(result.difficulty);
+                        summary.hardest_contest=Some(index);
+                    }
+                }
+                Err(_) => summary.failed+=1,
+            }
+        }
+        RaireBatchSolution{solutions,summary}
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+/// The tradeoff between the trim algorithms for a particular contest. See [trim_comparison].
+pub struct TrimComparison {
+    /// The number of assertions left by [TrimAlgorithm::MinimizeTree].
+    pub tree_count : usize,
+    /// The number of assertions left by [TrimAlgorithm::MinimizeAssertions].
+    pub assertions_count : usize,
+    /// The total number of nodes in the trees a human needs to check to verify the assertions left by [TrimAlgorithm::MinimizeTree].
+    pub tree_node_count : usize,
+}
+
+/// Solve the problem once, and trim the assertions with both [TrimAlgorithm::MinimizeTree] and [TrimAlgorithm::MinimizeAssertions],
+/// reporting the tradeoff between the number of assertions and the size of the tree needed to verify them.
+/// This lets election officials make an informed choice of trim algorithm for a particular contest.
+/// The `trim_algorithm` in `problem` is ignored.
+pub fn trim_comparison(problem:&RaireProblem) -> Result<TrimComparison,RaireError> {
+    let mut timeout = problem_timeout(problem)?;
+    let results = solve_with_each_trim(problem,&[TrimAlgorithm::MinimizeTree,TrimAlgorithm::MinimizeAssertions],&mut timeout)?;
+    let tree_effort = results[0].human_verification_effort(&mut timeout)?;
+    Ok(TrimComparison{tree_count:results[0].assertions.len(),assertions_count:results[1].assertions.len(),tree_node_count:tree_effort.tree_node_count})
+}
+
+/// Solve the problem once, and trim the assertions with each trim algorithm (None, MinimizeTree, MinimizeAssertions in that order),
+/// returning the number of assertions and the maximum difficulty of the assertions for each, so the tradeoff can be plotted.
+/// The `trim_algorithm` in `problem` is ignored.
+pub fn trim_pareto(problem:&RaireProblem) -> Result<Vec<(usize,f64)>,RaireError> {
+    let mut timeout = problem_timeout(problem)?;
+    let results = solve_with_each_trim(problem,&[TrimAlgorithm::None,TrimAlgorithm::MinimizeTree,TrimAlgorithm::MinimizeAssertions],&mut timeout)?;
+    Ok(results.iter().map(|r|(r.assertions.len(),r.assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max))).collect())
+}
+
+/// Apply a JSON Merge Patch (RFC 7386) to `target`.
+fn merge_patch(target:&mut serde_json::Value,patch:&serde_json::Value) {
+    if let serde_json::Value::Object(patch) = patch {
+        if !target.is_object() { *target = serde_json::Value::Object(serde_json::Map::new()); }
+        let target = target.as_object_mut().unwrap();
+        for (key,value) in patch {
+            if value.is_null() { target.remove(key); }
+            else { merge_patch(target.entry(key.clone()).or_insert(serde_json::Value::Null),value); }
+        }
+    } else { *target = patch.clone(); }
+}
+
+/// The time and work limits specified in a problem.
+fn problem_timeout(problem:&RaireProblem) -> Result<TimeOut,RaireError> {
+    let invalid_seconds = |seconds:Option<f64>|seconds.is_some_and(|v|v<=0.0||v.is_nan());
+    if [problem.time_limit_seconds,problem.winner_computation_time_limit,problem.time_limit_finding_assertions,problem.time_limit_trimming].into_iter().any(invalid_seconds) || problem.work_limit==Some(0) { return Err(RaireError::InvalidTimeout); }
+    let mut timeout = TimeOut::new(problem.work_limit,problem.time_limit_seconds.map(Duration::from_secs_f64));
+    if let Some(seconds) = problem.winner_computation_time_limit { timeout = timeout.with_winner_computation_limit(Duration::from_secs_f64(seconds)); }
+    if let Some(seconds) = problem.time_limit_finding_assertions { timeout = timeout.with_finding_assertions_limit(Duration::from_secs_f64(seconds)); }
+    if let Some(seconds) = problem.time_limit_trimming { timeout = timeout.with_trimming_limit(Duration::from_secs_f64(seconds)); }
+    Ok(timeout)
+}
+
+/// Solve the problem once without trimming, and then trim the assertions with each of the given trim algorithms.
+fn solve_with_each_trim(problem:&RaireProblem,trim_algorithms:&[TrimAlgorithm],timeout:&mut TimeOut) -> Result<Vec<RaireResult>,RaireError> {
+    let votes = Votes::new(problem.votes.clone(),problem.num_candidates)?;
+    let untrimmed = raire_with_options(&votes,problem.winner,&problem.audit,TrimAlgorithm::None,&problem.options(),timeout)?;
+    let required = score_required_assertions(&problem.required_assertions,&votes,&problem.audit,&untrimmed.example_elimination_order)?;
+    trim_algorithms.iter().map(|&trim_algorithm|trim_copy(&untrimmed,&required,trim_algorithm,timeout)).collect()
+}
+
+/// Truncate the assertions in the result if requested by [RaireProblem::max_assertions_returned].
+fn truncate_if_requested(mut result:RaireResult,max_assertions_returned:Option<usize>) -> RaireResult {
+    if let Some(max_assertions) = max_assertions_returned { result.truncate_for_display(max_assertions); }
+    result
+}
+
+/// Trim a copy of the assertions in an untrimmed result, updating the margin and the time taken to trim.
+/// The `required` assertions are kept even if redundant.
+fn trim_copy(untrimmed:&RaireResult,required:&[AssertionAndDifficulty],trim_algorithm:TrimAlgorithm,timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    let start = timeout.time_taken();
+    let mut assertions = untrimmed.assertions.clone();
+    timeout.within_trimming_limit(|timeout|order_assertions_and_remove_unnecessary(&mut assertions,untrimmed.winner,untrimmed.num_candidates,trim_algorithm,timeout))?;
+    include_required_assertions(&mut assertions,required);
+    let margin = assertions.iter().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0));
+    Ok(RaireResult{assertions,margin,time_to_trim_assertions:timeout.time_taken()-start,..untrimmed.clone()})
+}
+
+/// Find the provable winner and the assertions proving it for a partial count, for instance on election night
+/// while ballots are still arriving. The winner is whoever wins on the ballots counted so far.
+///
+/// The result is provisional: it may change (including the winner) as more ballots are counted, and should not be
+/// used for an audit until counting completes. The `warning_provisional` flag is set in the result to indicate this.
+pub fn provisional_solve(partial_votes:&Votes,audit:&Audit,timeout:&mut TimeOut) -> RaireSolution {
+    provisional_solve_with_options(partial_votes,audit,&RaireOptions::default(),timeout)
+}
+
+/// Like [provisional_solve], with the given options for the search for assertions. Any `required_assertions` in `options`
+/// must hold for the partial count.
+pub fn provisional_solve_with_options(partial_votes:&Votes,audit:&Audit,options:&RaireOptions,timeout:&mut TimeOut) -> RaireSolution {
+    let solution = raire_with_options(partial_votes,None,audit,TrimAlgorithm::MinimizeTree,options,timeout).map(|result|RaireResult{warning_provisional:true,..result});
+    RaireSolution{metadata:Default::default(),solution,timeout_cause:timeout.cause(),partial:None,provenance:None}
+}
+
+/// A conservative estimate of the audit effort when there is a tie for the winner (see [RaireError::TiedWinners]),
+/// for instance to bound effort while the tie is being resolved.
+///
+/// For each tied winner, the tie is resolved in their favour by [tie_break_order_solve], with the elimination order of
+/// one resolution of the ties that makes them win, and the result with the highest difficulty is returned. As in
+/// [tie_break_order_solve], the result is conditional on the tie resolution, and assertions with a zero margin (which
+/// hold only because of it) are excluded from the difficulty. If there is no tie, this is the same as running RAIRE normally.
+pub fn worst_case_tied_solve(votes:&Votes,audit:&Audit,trim_algorithm:TrimAlgorithm,timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    let tied_winners = match raire(votes,None,audit,trim_algorithm,timeout) {
+        Err(RaireError::TiedWinners(tied_winners)) => tied_winners,
+        result => { return result; }
+    };
+    let all_candidates : Vec<CandidateIndex> = (0..votes.num_candidates()).map(CandidateIndex).collect();
+    // Only returned if there are no tied winners, which does not happen as a tie needs at least two.
+    let mut worst : Result<RaireResult,RaireError> = Err(RaireError::TiedWinners(tied_winners.clone()));
+    for winner in tied_winners {
+        let tie_break_order = elimination_order_won_by(votes,winner,&all_candidates,timeout)?.ok_or(RaireError::WrongWinner(vec![winner]))?;
+        let result = tie_break_order_solve(votes,winner,audit,&tie_break_order,trim_algorithm,&RaireOptions::default(),timeout)?;
+        if worst.as_ref().map(|w|result.difficulty>w.difficulty).unwrap_or(true) { worst=Ok(result); }
+    }
+    worst
+}
+
+/// An elimination order of the `continuing` candidates, ending with `winner`, in which each candidate is eliminated
+/// with the lowest tally (possibly tied), or None if no resolution of ties makes `winner` win. As a `tie_break_order`
+/// for [tie_break_order_solve], it resolves each tie as it is resolved in the order.
+fn elimination_order_won_by(votes:&Votes,winner:CandidateIndex,continuing:&[CandidateIndex],timeout:&mut TimeOut) -> Result<Option<Vec<CandidateIndex>>,RaireError> {
+    if timeout.quick_check_timeout() { return Err(RaireError::TimeoutCheckingWinner); }
+    if continuing.len()==1 { return Ok(if continuing[0]==winner { Some(vec![winner]) } else { None }); }
+    let tallies = votes.restricted_tallies(continuing);
+    let lowest = *tallies.iter().min().unwrap();
+    for (i,&eliminated) in continuing.iter().enumerate() {
+        if tallies[i]==lowest && eliminated!=winner {
+            let remaining : Vec<CandidateIndex> = continuing.iter().copied().filter(|&c|c!=eliminated).collect();
+            if let Some(mut order) = elimination_order_won_by(votes,winner,&remaining,timeout)? {
+                order.insert(0,eliminated);
+                return Ok(Some(order));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Find assertions proving `winner` won when the winner is only determined by a statutory tie resolution, given by
+/// `tie_break_order`: in a tie for elimination, the candidate listed earliest is eliminated. Candidates not listed are
+/// eliminated before listed candidates they are tied with, and ties between unlisted candidates are not resolved.
+///
+/// Such a contest cannot really be audited, as a one vote difference could change the outcome. So the result is
+/// conditional on the tie resolution, and `conditional_on_tie_break` is set in it. The assertions are found by running
+/// RAIRE on a copy of the votes with each ballot multiplied by one more than the length of `tie_break_order`, and each
+/// listed candidate given as many extra ballots as one more than their position in it. This resolves ties in the desired
+/// manner without changing any other comparison. The search uses the audit scaled by the same multiple (see [Audit::scaled]), so that
+/// difficulties are comparable to those for the actual votes, and the difficulties and margins are then recomputed from the actual votes.
+/// Assertions with a zero margin hold only because of the tie resolution, and are excluded from the overall difficulty and margin.
+///
+/// The search uses the `options`, except that the `required_assertions` are not used to restrict the search, as they
+/// need not hold for the copy of the votes. Instead they are checked against the actual votes and the elimination order
+/// given by the tie resolution, as in [RaireOptions::required_assertions], and included in the result.
+///
+/// Returns [RaireError::WrongWinner] if the tie resolution does not make `winner` the winner, and
+/// [RaireError::InvalidCandidateNumber] if `tie_break_order` contains an invalid candidate.
+pub fn tie_break_order_solve(votes:&Votes,winner:CandidateIndex,audit:&Audit,tie_break_order:&[CandidateIndex],trim_algorithm:TrimAlgorithm,options:&RaireOptions,timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    if tie_break_order.iter().any(|c|c.0>=votes.num_candidates()) { return Err(RaireError::InvalidCandidateNumber); }
+    let multiplier = tie_break_order.len()+1;
+    let mut ballots : Vec<Vote> = votes.votes.iter().map(|v|Vote{n:BallotPaperCount(v.n.0*multiplier),prefs:v.prefs.clone()}).collect();
+    for (position,&c) in tie_break_order.iter().enumerate() {
+        ballots.push(Vote{n:BallotPaperCount(position+1),prefs:vec![c]});
     }
+    let tie_broken = Votes::new(ballots,votes.num_candidates() as usize)?;
+    let result = raire_with_options(&tie_broken,Some(winner),&audit.scaled(multiplier),trim_algorithm,&RaireOptions{required_assertions:vec![],..options.clone()},timeout)?;
+    let required = score_required_assertions(&options.required_assertions,votes,audit,&result.example_elimination_order)?;
+    let mut assertions : Vec<AssertionAndDifficulty> = result.assertions.into_iter().map(|a|{
+        let (tally_winner,tally_loser) = a.assertion.tallies(votes);
+        let margin = if tally_winner>=tally_loser { tally_winner-tally_loser } else { BallotPaperCount(0) };
+        AssertionAndDifficulty{difficulty:audit.difficulty(tally_winner,tally_loser),margin,assertion:a.assertion,tallies:None}
+    }).collect();
+    include_required_assertions(&mut assertions,&required);
+    let audited = || assertions.iter().filter(|a|a.margin.0>0);
+    let difficulty = audited().map(|a|a.difficulty).fold(0.0,f64::max);
+    let margin = audited().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0));
+    Ok(RaireResult{difficulty,margin,conditional_on_tie_break:true,considered:vec![],stats:None,assertions,..result})
 }
\ No newline at end of file
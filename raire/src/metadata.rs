@@ -0,0 +1,159 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Typed metadata describing a contest, which is not used for the computation but is useful for presenting results.
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+use crate::irv::CandidateIndex;
+
+/// Metadata about a contest, as read from the untyped `metadata` of a [crate::RaireProblem] or [crate::RaireSolution].
+/// In JSON this is an object with optional fields `contest` (the name of the contest) and `candidates` (the names of
+/// the candidates, in order), and any other fields, which are kept in `extra`.
+///
+/// Any JSON is accepted. Fields of the wrong type or empty, and JSON that is not an object, are kept in `extra` unchanged.
+/// The name of the contest may also be given as `contest_name`, in which case it is written back the same way,
+/// so metadata read and written again is unchanged.
+///
+/// Writing fails if `extra` is neither an object nor null and there is a contest name or candidate names, as there
+/// is then no way to write all of them as JSON that reads back the same.
+#[derive(Clone,Debug,Default,PartialEq,Deserialize)]
+#[serde(from="Value")]
+pub struct ContestMetadata {
+    pub contest_name : Option<String>,
+    /// The JSON key the name of the contest was read from, and is written to.
+    pub contest_name_key : ContestNameKey,
+    /// The names of the candidates, in order. May be empty if not known.
+    pub candidates : Vec<String>,
+    /// Any other fields of the metadata, for use by other tools, or the whole metadata if it is not an object. Null if there was no metadata.
+    pub extra : Value,
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+/// The JSON key used for the name of a contest in [ContestMetadata].
+pub enum ContestNameKey {
+    /// `contest`, as used by the visualizer.
+    #[default]
+    Contest,
+    /// `contest_name`.
+    ContestName,
+}
+
+impl ContestNameKey {
+    fn key(self) -> &'static str {
+        match self {
+            ContestNameKey::Contest => "contest",
+            ContestNameKey::ContestName => "contest_name",
+        }
+    }
+}
+
+impl ContestMetadata {
+    /// The name of candidate `c`, or `#c` if not known.
+    pub fn candidate_name(&self,c:CandidateIndex) -> String {
+        match self.candidates.get(c.0 as usize) {
+            Some(name) => name.clone(),
+            None => format!("#{}",c.0),
+        }
+    }
+
+    /// The names of the first `num_candidates` candidates, as used in [crate::raire_algorithm::RaireResult::explainer_rows].
+    pub fn candidate_names(&self,num_candidates:u32) -> Vec<String> {
+        (0..num_candidates).map(|c|self.candidate_name(CandidateIndex(c))).collect()
+    }
+}
+
+impl From<Value> for ContestMetadata {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Object(mut map) => {
+                let mut take_string = |key:&str| match map.get(key) {
+                    Some(Value::String(_)) => map.remove(key).and_then(|v|v.as_str().map(|s|s.to_string())),
+                    _ => None,
+                };
+                let (contest_name,contest_name_key) = match take_string("contest") {
+                    Some(name) => (Some(name),ContestNameKey::Contest),
+                    None => (take_string("contest_name"),ContestNameKey::ContestName),
+                };
+                let candidates = match map.get("candidates") {
+                    Some(Value::Array(names)) if !names.is_empty() && names.iter().all(|n|n.is_string()) => {
+                        let names = names.iter().filter_map(|n|n.as_str()).map(|n|n.to_string()).collect();
+                        map.remove("candidates");
+                        names
+                    }
+                    _ => vec![],
+                };
+                let contest_name_key = if contest_name.is_some() { contest_name_key } else { ContestNameKey::default() };
+                ContestMetadata{contest_name,contest_name_key,candidates,extra:Value::Object(map)}
+            }
+            extra => ContestMetadata{extra,..Default::default()},
+        }
+    }
+}
+
+impl TryFrom<ContestMetadata> for Value {
+    type Error = String;
+
+    fn try_from(metadata: ContestMetadata) -> Result<Self,String> {
+        let mut map = match metadata.extra {
+            Value::Object(map) => map,
+            extra if metadata.contest_name.is_none() && metadata.candidates.is_empty() => return Ok(extra),
+            Value::Null => Map::new(),
+            _ => return Err("metadata with a contest name or candidates must have an object or null for extra".to_string()),
+        };
+        if let Some(contest_name) = metadata.contest_name { map.insert(metadata.contest_name_key.key().to_string(),contest_name.into()); }
+        if !metadata.candidates.is_empty() { map.insert("candidates".to_string(),metadata.candidates.into()); }
+        Ok(Value::Object(map))
+    }
+}
+
+impl Serialize for ContestMetadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Value::try_from(self.clone()).map_err(serde::ser::Error::custom)?.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn test_legacy_metadata() {
+        let metadata : ContestMetadata = serde_json::from_value(json!({"candidates":["Alice","Bob"],"contest":"Mayor","riskLimit":0.05})).unwrap();
+        assert_eq!(Some("Mayor".to_string()),metadata.contest_name);
+        assert_eq!(vec!["Alice".to_string(),"Bob".to_string()],metadata.candidates);
+        assert_eq!(json!({"riskLimit":0.05}),metadata.extra);
+        assert_eq!("Bob",metadata.candidate_name(CandidateIndex(1)));
+        assert_eq!("#2",metadata.candidate_name(CandidateIndex(2)));
+        assert_eq!(json!({"candidates":["Alice","Bob"],"contest":"Mayor","riskLimit":0.05}),serde_json::to_value(&metadata).unwrap());
+        // anything else is kept unchanged.
+        for legacy in [json!(null),json!("a note"),json!({"candidates":[1,2]}),json!({"contest":3})] {
+            let metadata : ContestMetadata = serde_json::from_value(legacy.clone()).unwrap();
+            assert_eq!(ContestMetadata{extra:legacy.clone(),..Default::default()},metadata);
+            assert_eq!(legacy,serde_json::to_value(&metadata).unwrap());
+        }
+        let metadata : ContestMetadata = serde_json::from_value(json!({"contest_name":"Mayor"})).unwrap();
+        assert_eq!(Some("Mayor".to_string()),metadata.contest_name);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for json in [json!({"contest":"Mayor"}),json!({"contest_name":"Mayor","candidates":["Alice","Bob"]}),json!({"contest":"Mayor","contest_name":"Deputy Mayor"}),json!({"contest_name":3,"riskLimit":0.05}),json!([1,2]),json!({}),json!({"candidates":[]}),json!(null)] {
+            let metadata : ContestMetadata = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(json,serde_json::to_value(&metadata).unwrap());
+        }
+        let metadata : ContestMetadata = serde_json::from_value(json!({"contest_name":"Mayor"})).unwrap();
+        assert_eq!(ContestNameKey::ContestName,metadata.contest_name_key);
+        // a non-object extra cannot be written along with names.
+        let metadata = ContestMetadata{contest_name:Some("Mayor".to_string()),extra:json!("a note"),..Default::default()};
+        assert!(serde_json::to_value(&metadata).is_err());
+    }
+}
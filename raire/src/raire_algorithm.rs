@@ -12,12 +12,15 @@
 
 
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-use crate::assertions::{all_elimination_orders, Assertion, AssertionAndDifficulty, NotEliminatedNext, NotEliminatedBefore, EliminationOrder, EliminationOrderSuffix, EffectOfAssertionOnEliminationOrderSuffix, NotEliminatedBeforeCache};
-use crate::audit_type::{AssertionDifficulty, AuditType};
-use crate::irv::{BallotPaperCount, CandidateIndex, Votes};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::time::Duration;
+use crate::assertions::{all_elimination_orders, check_assertion_consistency, Assertion, AssertionAndDifficulty, NotEliminatedNext, NotEliminatedBefore, EliminationOrder, EliminationOrderSuffix, EffectOfAssertionOnEliminationOrderSuffix, NotEliminatedBeforeCache};
+use crate::audit_type::{AssertionDifficulty, Audit, AuditType};
+use crate::irv::{BallotPaperCount, CandidateIndex, IRVResult, Votes};
 use serde::Deserialize;
 use serde::Serialize;
+#[cfg(feature="sha2")]
+use sha2::{Digest, Sha256};
 use crate::RaireError;
 use crate::timeout::{TimeOut, TimeTaken};
 use crate::tree_showing_what_assertions_pruned_leaves::{HowFarToContinueSearchTreeWhenPruningAssertionFound, TreeNodeShowingWhatAssertionsPrunedIt};
@@ -33,11 +36,43 @@ pub struct RaireResult {
     pub margin : BallotPaperCount,
     pub winner : CandidateIndex,
     pub num_candidates : u32,
+    /// An elimination order (first eliminated first) produced by running the IRV count on the votes, ending with the winner.
+    /// If there are ties for elimination, this is just one of the possible orders. If bulk elimination was needed to
+    /// resolve ties efficiently, the order of the candidates eliminated in bulk may be approximate.
+    #[serde(default)]
+    pub example_elimination_order : Vec<CandidateIndex>,
     pub time_to_determine_winners : TimeTaken,
     pub time_to_find_assertions : TimeTaken,
     pub time_to_trim_assertions : TimeTaken,
     #[serde(default,skip_serializing_if = "is_false")]
     pub warning_trim_timed_out : bool,
+    /// True if the result was computed from a partial count (see [crate::provisional_solve]), and so may change when counting completes.
+    #[serde(default,skip_serializing_if = "is_false")]
+    pub warning_provisional : bool,
+    /// True if some assertions were removed for display purposes (see [Self::truncate_for_display]). The remaining
+    /// assertions do not prove the winner, and must NOT be used for an audit.
+    #[serde(default,skip_serializing_if = "is_false")]
+    pub truncated : bool,
+    /// True if the winner is only the winner because of a statutory tie resolution (see [crate::tie_break_order_solve]).
+    /// Assertions with a zero margin hold only because of the tie resolution, and are not audited.
+    #[serde(default,skip_serializing_if = "is_false")]
+    pub conditional_on_tie_break : bool,
+    /// If requested, every assertion evaluated as the best of its type for some elimination order suffix during the search,
+    /// including those rejected. This may be very large, and may contain duplicates. Empty unless requested.
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub considered : Vec<AssertionAndDifficulty>,
+    /// Diagnostic statistics about the search. Only present if requested, and there are fewer than 64 candidates.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub stats : Option<SearchStats>,
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+/// Diagnostic statistics about the search, useful for measuring how effective the pruning of the search is.
+pub struct SearchStats {
+    /// The number of distinct sets of continuing candidates for which the best assertion was evaluated.
+    pub visited_continuing_sets : usize,
+    /// The number of possible sets of continuing candidates, 2 to the power of the number of candidates.
+    pub total_continuing_sets : u64,
 }
 
 impl RaireResult {
@@ -50,6 +85,21 @@ impl RaireResult {
         elimination_orders
     }
 
+    /// The number of (complete) elimination orders allowed by the assertions. This is computed without enumerating
+    /// all the elimination orders, by recursively considering suffixes and stopping when the assertions no longer
+    /// depend on the rest of the order. It is returned as a floating point number as it may be very large.
+    pub fn count_elimination_orders_allowed_by_assertions(&self,num_candidates:u32) -> f64 {
+        let assertions : Vec<&Assertion> = self.assertions.iter().map(|a|&a.assertion).collect();
+        count_elimination_orders(&mut vec![],&assertions,num_candidates)
+    }
+
+    /// A measure of how ambiguous the outcome is given the assertions, being the natural log of the number of
+    /// elimination orders allowed by the assertions (see [Self::count_elimination_orders_allowed_by_assertions]).
+    /// Lower means the assertions pin down the elimination order more tightly. Zero means only one elimination order is possible.
+    pub fn outcome_entropy(&self,num_candidates:u32) -> f64 {
+        self.count_elimination_orders_allowed_by_assertions(num_candidates).ln()
+    }
+
     /// Note this is not very efficient; you would only want to use this for tests.
     pub fn possible_elimination_order_suffixes_allowed_by_assertions(&self,num_candidates:u32) -> Vec<EliminationOrder> {
         let mut elimination_orders : Vec<EliminationOrderSuffix> = vec![vec![]]; // start off with the minimal set.
@@ -63,8 +113,459 @@ impl RaireResult {
         elimination_orders
     }
 
+    /// Fill in the `tallies` of each assertion, for downstream audit tools that need the tallies as well as the margin.
+    pub fn add_tallies(&mut self,votes:&Votes) {
+        for a in &mut self.assertions { a.tallies=Some(a.compute_tallies(votes)); }
+    }
+
+    /// Recompute the difficulty and margin of every assertion, and the overall difficulty and margin, for a different
+    /// audit type (or the same audit type with different parameters such as the risk limit or the number of auditable
+    /// ballots) without searching for assertions again. `votes` should be the votes the assertions were generated from.
+    /// The assertions are unchanged, so they may no longer be optimal for the new audit type, but they still prove the winner.
+    ///
+    /// If the result is `conditional_on_tie_break`, assertions with a zero margin are excluded from the overall difficulty and margin, as when generated.
+    pub fn rescore<A:AuditType>(&mut self,votes:&Votes,audit:&A) {
+        for a in &mut self.assertions { a.rescore(votes,audit); }
+        let audited = || self.assertions.iter().filter(|a|!self.conditional_on_tie_break || a.margin.0>0);
+        self.difficulty = audited().map(|a|a.difficulty).fold(0.0,f64::max);
+        self.margin = audited().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0));
+    }
+
+    /// Just the assertions, with their difficulties and margins, as a JSON array, without
+    /// the other information (overall difficulty, timing, etc.) in this structure.
+    ///
+    /// This deserializes to a `Vec<AssertionAndDifficulty>`.
+    pub fn assertions_only_json(&self) -> serde_json::Value {
+        serde_json::to_value(&self.assertions).expect("AssertionAndDifficulty should always be serializable")
+    }
+
+    /// A SHA-256 hash (as lower case hex) of this result, including the winner, difficulty and set of assertions, suitable
+    /// for publishing as a tamper-evident commitment to an audit. The hash is of the canonical JSON serialization of a
+    /// solution containing just this result (see [crate::RaireSolution::to_canonical_json]), so it does not depend on the order
+    /// of the assertions or on timing information. The tallies of the assertions are left out, so it does not depend
+    /// on whether they were requested either. Requires the `sha2` feature.
+    #[cfg(feature="sha2")]
+    pub fn certificate_hash(&self) -> String {
+        let mut result = self.clone();
+        for a in &mut result.assertions { a.tallies=None; }
+        let solution = crate::RaireSolution{metadata:serde_json::Value::Null,solution:Ok(result),timeout_cause:crate::timeout::TimeoutCause::None,partial:None,provenance:None};
+        Sha256::digest(solution.to_canonical_json().as_bytes()).iter().map(|b|format!("{:02x}",b)).collect()
+    }
+
+    /// The indices of the assertions, grouped by the winner of each assertion, for per-candidate reports.
+    /// Candidates who are not the winner of any assertion are not present.
+    pub fn assertions_by_winner(&self) -> HashMap<CandidateIndex,Vec<usize>> {
+        let mut res : HashMap<CandidateIndex,Vec<usize>> = HashMap::new();
+        for (index,a) in self.assertions.iter().enumerate() {
+            res.entry(a.assertion.winner()).or_default().push(index);
+        }
+        res
+    }
+
+    /// For each candidate, the margin (computed from `votes`) of the least difficult assertion in which the winner directly beats
+    /// that candidate, giving a per-opponent measure of robustness. The result is indexed by candidate.
+    /// It is None for the winner, and for candidates only indirectly eliminated, that is, not the loser of any assertion won by the winner.
+    pub fn candidate_safety_margins(&self,votes:&Votes) -> Vec<Option<BallotPaperCount>> {
+        let mut best : Vec<Option<&AssertionAndDifficulty>> = vec![None;votes.num_candidates() as usize];
+        for a in &self.assertions {
+            if a.assertion.winner()==self.winner {
+                let entry = &mut best[a.assertion.loser().0 as usize];
+                if entry.map(|e|a.difficulty<e.difficulty).unwrap_or(true) { *entry=Some(a); }
+            }
+        }
+        best.into_iter().map(|a|a.map(|a|{
+            let (winner,loser) = a.assertion.tallies(votes);
+            BallotPaperCount(winner.0.saturating_sub(loser.0))
+        })).collect()
+    }
+
+    /// Keep only the `max_assertions` most difficult assertions (in their existing order), for display in user interfaces with
+    /// limited space, setting the `truncated` flag if any were removed. The overall difficulty and margin are unchanged.
+    ///
+    /// The remaining assertions generally do not prove the winner, so a truncated result must NOT be used for an audit.
+    pub fn truncate_for_display(&mut self,max_assertions:usize) {
+        if self.assertions.len()<=max_assertions { return; }
+        let mut by_difficulty : Vec<usize> = (0..self.assertions.len()).collect();
+        by_difficulty.sort_by(|&a,&b|self.assertions[b].difficulty.total_cmp(&self.assertions[a].difficulty));
+        let mut keep = vec![false;self.assertions.len()];
+        for &i in &by_difficulty[..max_assertions] { keep[i]=true; }
+        let mut keep = keep.into_iter();
+        self.assertions.retain(|_|keep.next().unwrap());
+        self.truncated=true;
+    }
+
+    /// Encode the claim "some candidate other than the winner is last in an elimination order consistent with all the assertions"
+    /// as a DIMACS CNF formula, for independent verification by an external SAT solver. If the solver reports the formula
+    /// is unsatisfiable, then the assertions prove the winner.
+    ///
+    /// There are `num_candidates` squared variables. Variable `c*num_candidates+k+1` is true iff candidate `c` is
+    /// in position `k` of the elimination order, position 0 being the first eliminated and the last position being the winner.
+    /// Clauses say that the variables form a permutation, that the reported winner is not last, and that each assertion holds.
+    ///
+    /// Returns `RaireError::InvalidCandidateNumber` if the winner or an assertion refers to a candidate not less than `num_candidates`,
+    /// or an assertion has more continuing candidates than that.
+    pub fn to_cnf(&self) -> Result<String,RaireError> {
+        let num_candidates = self.num_candidates;
+        let valid = |c:&CandidateIndex|c.0<num_candidates;
+        let assertion_valid = |a:&Assertion| valid(&a.winner()) && valid(&a.loser()) && match a { Assertion::NEN(nen) => nen.continuing.iter().all(valid) && nen.continuing.len()<=num_candidates as usize, Assertion::NEB(_) => true };
+        if !(valid(&self.winner) && self.assertions.iter().all(|a|assertion_valid(&a.assertion))) { return Err(RaireError::InvalidCandidateNumber); }
+        let n = num_candidates as usize;
+        let var = |c:CandidateIndex,k:usize| -> i64 { (c.0 as usize*n+k+1) as i64 };
+        let candidates = || (0..num_candidates).map(CandidateIndex);
+        let mut clauses : Vec<Vec<i64>> = vec![];
+        for c in candidates() {
+            clauses.push((0..n).map(|k|var(c,k)).collect()); // each candidate is in some position
+            for k1 in 0..n { for k2 in k1+1..n { clauses.push(vec![-var(c,k1),-var(c,k2)]); } } // ... and only one position
+        }
+        for k in 0..n {
+            clauses.push(candidates().map(|c|var(c,k)).collect()); // each position has some candidate
+            for c1 in candidates() { for c2 in candidates().filter(|c2|c2.0>c1.0) { clauses.push(vec![-var(c1,k),-var(c2,k)]); } } // ... and only one candidate
+        }
+        if n>0 { clauses.push(vec![-var(self.winner,n-1)]); } // the winner is not last
+        for a in &self.assertions {
+            match &a.assertion {
+                Assertion::NEB(neb) => { // the winner is not eliminated before the loser.
+                    for k1 in 0..n { for k2 in k1+1..n { clauses.push(vec![-var(neb.winner,k1),-var(neb.loser,k2)]); } }
+                }
+                Assertion::NEN(nen) => { // when exactly the continuing candidates are left, the winner is not eliminated next.
+                    // That is, if the winner is in position n-m, some other continuing candidate must be in an earlier position.
+                    let first = n-nen.continuing.len();
+                    let mut clause = vec![-var(nen.winner,first)];
+                    for &c in nen.continuing.iter().filter(|&&c|c!=nen.winner) { clause.extend((0..first).map(|k|var(c,k))); }
+                    clauses.push(clause);
+                }
+            }
+        }
+        let mut res = format!("c RAIRE assertions for winner {}\np cnf {} {}\n",self.winner,n*n,clauses.len());
+        for clause in clauses {
+            for literal in clause { res.push_str(&literal.to_string()); res.push(' '); }
+            res.push_str("0\n");
+        }
+        Ok(res)
+    }
+
+    /// A score from 0 to 100 indicating how easy the contest is to audit, higher meaning easier.
+    /// Useful for ranking many contests.
+    ///
+    /// The transform is `100/difficulty`, clamped to the range 0 to 100. An impossible (infinite)
+    /// or undefined (NaN) difficulty gives 0, as it cannot be shown to be easy, and a zero difficulty (e.g. only one candidate) gives 100. For the `OneOnMargin` audit type this is the diluted margin expressed
+    /// as a percentage, and for `OneOnMarginSq` it is 100 times the square of the diluted margin.
+    /// For `BRAVO` and `MACRO` difficulty is an expected number of ballots to sample, so the score
+    /// is not normalized by the number of ballots, and should only be compared between contests
+    /// of similar size.
+    pub fn auditability_score(&self) -> f64 {
+        if self.difficulty.is_nan() { 0.0 } else if self.difficulty<=0.0 { 100.0 } else { (100.0/self.difficulty).clamp(0.0,100.0) }
+    }
+
+    /// The assertions, sorted so that the most difficult comes first. Ties are left in the stored order.
+    pub fn assertions_by_difficulty(&self) -> Vec<&AssertionAndDifficulty> {
+        let mut res : Vec<&AssertionAndDifficulty> = self.assertions.iter().collect();
+        res.sort_by(|a,b|b.difficulty.partial_cmp(&a.difficulty).unwrap_or(Ordering::Equal));
+        res
+    }
+
+    /// Whether the expected number of ballots needed to be sampled for the most difficult assertion is within `budget`.
+    /// The `audit` should be the one used to compute the difficulties.
+    ///
+    /// Returns false if the audit type does not give sample sizes, see [Audit::expected_sample_size].
+    pub fn feasible_within(&self,audit:&Audit,budget:BallotPaperCount) -> bool {
+        match audit.expected_sample_size(self.difficulty) {
+            Some(sample_size) => sample_size<=budget.0 as f64,
+            None => false,
+        }
+    }
+
+    /// The expected number of ballots needed to be sampled for the binding assertion, divided by `total` ballots,
+    /// that is, the fraction of ballots expected to be examined. This is capped at 1, as no more than all the ballots
+    /// can be examined. The `audit` should be the one used to compute the difficulties.
+    ///
+    /// None if the audit type does not give sample sizes (see [Audit::expected_sample_size]), there are no assertions, or `total` is zero.
+    pub fn expected_sample_fraction(&self,audit:&Audit,total:BallotPaperCount) -> Option<f64> {
+        if total.0==0 { return None; }
+        let sample_size = audit.expected_sample_size(self.binding_assertion()?.difficulty)?;
+        Some((sample_size/total.0 as f64).min(1.0))
+    }
+
+    /// One row per assertion for the assertion explainer's table, with fixed columns, so the frontend does not need to
+    /// build them itself. Rows are sorted with the most difficult assertion first, ties in the stored order, which is stable
+    /// for a given result. `names` are the names of the candidates, see [crate::metadata::ContestMetadata::candidate_names].
+    pub fn explainer_rows(&self,names:&[String],votes:&Votes) -> Vec<ExplainerRow> {
+        let mut indices : Vec<usize> = (0..self.assertions.len()).collect();
+        indices.sort_by(|&a,&b|self.assertions[b].difficulty.partial_cmp(&self.assertions[a].difficulty).unwrap_or(Ordering::Equal)); // stable
+        indices.into_iter().map(|index|{
+            let a = &self.assertions[index];
+            ExplainerRow{
+                index,
+                assertion_type : if a.assertion.is_neb() {"NEB"} else {"NEN"}.to_string(),
+                text : a.assertion.plain_english(names,votes),
+                difficulty : a.difficulty,
+                margin : a.margin,
+                risk : None,
+            }
+        }).collect()
+    }
+
+    /// The margin of each assertion divided by `total` ballots, that is, the diluted margin of each assertion,
+    /// in the same order as the assertions. This is the usual x axis for sample size plots.
+    pub fn margin_as_fraction_of_ballots(&self,total:BallotPaperCount) -> Vec<f64> {
+        self.assertions.iter().map(|a|a.margin.0 as f64/total.0 as f64).collect()
+    }
+
+    /// The candidate eliminated last before the winner, that is, the runner up, from the example elimination order.
+    /// If the example elimination order is not present (e.g. a result deserialized from an older version), the election is rerun using `votes`.
+    /// None if there is only one candidate.
+    pub fn runner_up(&self,votes:&Votes) -> Option<CandidateIndex> {
+        let rerun;
+        let elimination_order = if self.example_elimination_order.len()==self.num_candidates as usize { &self.example_elimination_order } else {
+            rerun = votes.run_election(&mut TimeOut::never()).ok()?.elimination_order;
+            &rerun
+        };
+        elimination_order.iter().rev().nth(1).copied()
+    }
+
+    /// The binding assertion, that is, the most difficult one, which determines the difficulty of the audit.
+    /// If several are equally difficult, the first is returned. None if there are no assertions.
+    pub fn binding_assertion(&self) -> Option<&AssertionAndDifficulty> {
+        self.assertions.iter().reduce(|best,a|if a.difficulty>best.difficulty { a } else { best })
+    }
+
+    /// The difficulty of the binding assertion (see [Self::binding_assertion]) if `k` ballots were shifted from its winner to its loser,
+    /// for `steps` values of `k` evenly spaced from 0 to `max_perturbation` inclusive, as (k,difficulty) pairs. The tallies are recomputed
+    /// from `votes`, and the difficulty from `audit`. This can be plotted to show how robust the audit is to errors; the difficulty
+    /// is infinite once the margin has been erased.
+    ///
+    /// Empty if there are no assertions.
+    pub fn difficulty_curve(&self,votes:&Votes,audit:&Audit,steps:usize,max_perturbation:BallotPaperCount) -> Vec<(BallotPaperCount,f64)> {
+        let Some(binding) = self.binding_assertion() else { return vec![]; };
+        let (winner,loser) = binding.assertion.tallies(votes);
+        (0..steps).map(|i|{
+            let k = if steps==1 { 0 } else { max_perturbation.0*i/(steps-1) };
+            let shifted_winner = BallotPaperCount(winner.0.saturating_sub(k));
+            (BallotPaperCount(k),audit.difficulty(shifted_winner,loser+BallotPaperCount(k)))
+        }).collect()
+    }
+
+    /// The (winner,loser) of the comparison in the binding assertion, that is, the pair of candidates whose comparison
+    /// drives the difficulty of the audit. See [Self::binding_assertion].
+    pub fn binding_pair(&self) -> Option<(CandidateIndex,CandidateIndex)> {
+        self.binding_assertion().map(|a|(a.assertion.winner(),a.assertion.loser()))
+    }
+
+    /// The number of ballots that, if altered, could make the binding assertion false. See [Self::binding_assertion]
+    /// and [Assertion::flipping_ballots]. This is the overall "how many ballots matter" figure for the contest.
+    /// Zero if there are no assertions.
+    pub fn critical_ballot_count(&self,votes:&Votes) -> BallotPaperCount {
+        self.binding_assertion().map(|a|a.assertion.flipping_ballots(votes)).unwrap_or(BallotPaperCount(0))
+    }
+
+    /// The distinct sets of continuing candidates in the NEN assertions, showing which rounds of the count the assertions
+    /// reason about. Each set is sorted by candidate index, and the sets are sorted lexicographically.
+    pub fn distinct_continuing_sets(&self) -> Vec<Vec<CandidateIndex>> {
+        let mut res : Vec<Vec<CandidateIndex>> = self.assertions.iter().filter_map(|a|match &a.assertion {
+            Assertion::NEN(nen) => {
+                let mut continuing = nen.continuing.clone();
+                continuing.sort_unstable_by_key(|c|c.0);
+                Some(continuing)
+            }
+            Assertion::NEB(_) => None,
+        }).collect();
+        res.sort_unstable_by(|a,b|a.iter().map(|c|c.0).cmp(b.iter().map(|c|c.0)));
+        res.dedup();
+        res
+    }
+
+    /// The candidates for which there is an assertion that the winner is not eliminated before them.
+    /// These candidates are directly proven to be behind the winner, regardless of anything else.
+    /// Sorted by candidate index.
+    pub fn candidates_proven_behind_winner(&self) -> Vec<CandidateIndex> {
+        let mut res : Vec<CandidateIndex> = self.assertions.iter().filter_map(|a|match &a.assertion {
+            Assertion::NEB(neb) if neb.winner==self.winner => Some(neb.loser),
+            _ => None,
+        }).collect();
+        res.sort_unstable_by_key(|c|c.0);
+        res.dedup();
+        res
+    }
+
+    /// For each candidate other than the winner, classify the kind of argument the assertions use to show that candidate
+    /// did not win. This is the tree of elimination orders with that candidate last standing, stopping
+    /// at the first assertion to rule out each path (as used for `MinimizeTree`).
+    ///
+    /// Where a node in the tree is ruled out by both NEB and NEN assertions, it is considered to be ruled out by the NEB.
+    pub fn elimination_arguments(&self) -> Vec<(CandidateIndex,ArgumentKind)> {
+        let all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
+        let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
+        let mut res = vec![];
+        for candidate in 0..self.num_candidates {
+            let candidate = CandidateIndex(candidate);
+            if candidate!=self.winner {
+                let tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&all_assertion_indices,&all_assertions,self.num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut TimeOut::never()).expect("Can't time out");
+                let kind = if tree.pruning_assertions.iter().any(|&a|all_assertions[a].is_neb()) { ArgumentKind::SingleNEB } else {
+                    let (mut uses_neb,mut uses_nen) = (false,false);
+                    find_assertion_kinds_used(&tree,&all_assertions,&mut uses_neb,&mut uses_nen);
+                    match (uses_neb,uses_nen) {
+                        (true,false) => ArgumentKind::OnlyNEBs,
+                        (false,true) => ArgumentKind::OnlyNENs,
+                        _ => ArgumentKind::Mixed,
+                    }
+                };
+                res.push((candidate,kind));
+            }
+        }
+        res
+    }
+
+    /// For each candidate, the length of the shortest chain of eliminations ending in an assertion that rules out that candidate
+    /// winning, being the shortest path to a leaf in the tree of elimination orders with that candidate last standing, stopping
+    /// at the first assertion to rule out each path (as for [Self::elimination_arguments]). This is 1 if an assertion such as
+    /// a NEB rules the candidate out directly, and more when NEN assertions are needed, showing how deep the argument for each candidate is.
+    ///
+    /// Indexed by candidate. None for the winner, or a candidate not ruled out by the assertions.
+    pub fn elimination_chain_length(&self) -> Vec<Option<usize>> {
+        let all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
+        let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
+        (0..self.num_candidates).map(CandidateIndex).map(|candidate|{
+            if candidate==self.winner { return None; }
+            let tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&all_assertion_indices,&all_assertions,self.num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut TimeOut::never()).expect("Can't time out");
+            if tree.valid { None } else { Some(tree.shortest_path_to_leaf()) }
+        }).collect()
+    }
+
+    /// Estimate how much effort it would take a human to verify that the assertions prove the winner,
+    /// by considering the trees of elimination order suffixes (one per non-winning candidate)
+    /// that are needed to show every alternative winner is ruled out.
+    ///
+    /// This quantifies the tradeoff between [TrimAlgorithm::MinimizeTree] and [TrimAlgorithm::MinimizeAssertions].
+    pub fn human_verification_effort(&self,timeout:&mut TimeOut) -> Result<HumanEffort,RaireError> {
+        let all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
+        let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
+        let mut effort = HumanEffort{num_assertions:all_assertions.len(),tree_node_count:0,max_tree_depth:0};
+        for candidate in 0..self.num_candidates {
+            let candidate = CandidateIndex(candidate);
+            if candidate!=self.winner {
+                let tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&all_assertion_indices,&all_assertions,self.num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,timeout)?;
+                effort.tree_node_count+=tree.node_count();
+                effort.max_tree_depth=effort.max_tree_depth.max(tree.depth());
+            }
+        }
+        Ok(effort)
+    }
+
+    /// Add an assertion to the list of assertions, for instance from a user interactively constructing a set of assertions.
+    ///
+    /// The overall difficulty and margin are updated, and the effect of the new assertion on which assertions
+    /// are necessary is reported. An assertion is redundant if all candidates other than the winner
+    /// are still ruled out when it is removed.
+    ///
+    /// If the new assertion, together with the existing ones, rules out the winner, then it is not
+    /// added and `RaireError::AssertionRulesOutWinner` is returned. Note that this check can be slow.
+    pub fn add_assertion(&mut self,assertion:AssertionAndDifficulty,timeout:&mut TimeOut) -> Result<AdditionEffect,RaireError> {
+        let mut all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
+        let redundant_before = self.redundant_assertions(&all_assertions,timeout)?;
+        all_assertions.push(assertion.assertion.clone());
+        let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
+        let winner_tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],self.winner,&all_assertion_indices,&all_assertions,self.num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,timeout)?;
+        if !winner_tree.valid { return Err(RaireError::AssertionRulesOutWinner); }
+        let redundant_after = self.redundant_assertions(&all_assertions,timeout)?;
+        let previous_difficulty = self.difficulty;
+        let new_index = self.assertions.len();
+        if assertion.difficulty>self.difficulty { self.difficulty=assertion.difficulty; }
+        if assertion.margin<self.margin { self.margin=assertion.margin; }
+        self.assertions.push(assertion);
+        Ok(AdditionEffect{
+            previous_difficulty,
+            new_assertion_redundant: redundant_after.contains(&new_index),
+            now_redundant: redundant_after.into_iter().filter(|i|*i!=new_index && !redundant_before.contains(i)).collect(),
+        })
+    }
+
+    /// Find the indices of the given assertions that could each (individually) be removed while still ruling out every candidate other than the winner.
+    fn redundant_assertions(&self,all_assertions:&[Assertion],timeout:&mut TimeOut) -> Result<Vec<usize>,RaireError> {
+        let mut res = vec![];
+        for removed in 0..all_assertions.len() {
+            let remaining_indices : Vec<usize> = (0..all_assertions.len()).filter(|&i|i!=removed).collect();
+            if self.all_losers_ruled_out(&remaining_indices,all_assertions,timeout)? { res.push(removed); }
+        }
+        Ok(res)
+    }
+
+    /// Whether the assertions with the given indices rule out every candidate other than the winner.
+    fn all_losers_ruled_out(&self,indices:&[usize],all_assertions:&[Assertion],timeout:&mut TimeOut) -> Result<bool,RaireError> {
+        for candidate in 0..self.num_candidates {
+            let candidate = CandidateIndex(candidate);
+            if candidate!=self.winner {
+                let tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,indices,all_assertions,self.num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,timeout)?;
+                if tree.valid { return Ok(false); }
+            }
+        }
+        Ok(true)
+    }
+
+    /// The overall difficulty of the same assertions under a different audit type, being the maximum of each assertion's
+    /// difficulty recomputed from `votes` using `alt_audit`, without searching again. This helps choose an audit method.
+    ///
+    /// The best set of assertions for `alt_audit` may be different, so this is an upper bound on the difficulty
+    /// that RAIRE would find for `alt_audit`.
+    pub fn difficulty_under(&self,votes:&Votes,alt_audit:&Audit) -> f64 {
+        self.assertions.iter().map(|a|{
+            let (winner,loser) = a.assertion.tallies(votes);
+            alt_audit.difficulty(winner,loser)
+        }).fold(0.0,f64::max)
+    }
+
+    /// The assertions that [TrimAlgorithm::MinimizeTree] keeps but [TrimAlgorithm::MinimizeAssertions] drops, such as A1 in
+    /// the [TrimAlgorithm] documentation. These are not needed to prove the winner, but may be useful if the audit escalates,
+    /// as they reduce the risk of needing a full hand count. The difficulties and margins are recomputed from `votes` using `audit`.
+    ///
+    /// The assertions in `self` should not already have been trimmed by [TrimAlgorithm::MinimizeAssertions], else there will be
+    /// nothing extra to find. Returns an empty list if trimming fails (e.g. the assertions do not prove the winner).
+    pub fn escalation_assertions(&self,votes:&Votes,audit:&Audit) -> Vec<AssertionAndDifficulty> {
+        let trim = |trim_algorithm:TrimAlgorithm| {
+            let mut assertions = self.assertions.clone();
+            crate::tree_showing_what_assertions_pruned_leaves::order_assertions_and_remove_unnecessary(&mut assertions,self.winner,self.num_candidates,trim_algorithm,&mut TimeOut::never()).map(|_|assertions)
+        };
+        let (Ok(tree),Ok(minimal)) = (trim(TrimAlgorithm::MinimizeTree),trim(TrimAlgorithm::MinimizeAssertions)) else { return vec![]; };
+        tree.into_iter().filter(|a|!minimal.iter().any(|m|m.assertion==a.assertion)).map(|a|{
+            let (winner,loser) = a.assertion.tallies(votes);
+            let margin = if winner>=loser { winner-loser } else { BallotPaperCount(0) };
+            AssertionAndDifficulty{assertion:a.assertion,margin,difficulty:audit.difficulty(winner,loser),tallies:None}
+        }).collect()
+    }
+
+    /// The overall difficulty if the assertion with the given index were removed, being the maximum difficulty
+    /// of the remaining assertions. This quantifies the benefit of dropping an assertion.
+    ///
+    /// Returns `None` if the assertion is needed, that is, the remaining assertions no longer prove the winner,
+    /// or `RaireError::InvalidAssertionIndex` if `index` is out of range.
+    pub fn difficulty_without(&self,index:usize,timeout:&mut TimeOut) -> Result<Option<f64>,RaireError> {
+        if index>=self.assertions.len() { return Err(RaireError::InvalidAssertionIndex(index)); }
+        let all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
+        let remaining_indices : Vec<usize> = (0..all_assertions.len()).filter(|&i|i!=index).collect();
+        if !self.all_losers_ruled_out(&remaining_indices,&all_assertions,timeout)? { return Ok(None); }
+        Ok(Some(remaining_indices.iter().map(|&i|self.assertions[i].difficulty).fold(0.0,f64::max)))
+    }
+
+    /// Check that each assertion is actually true for the given votes, that is, the winner's tally is greater than the loser's.
+    /// This does not check that the assertions prove the winner; see [Self::verify_result_does_prove_winner] for that.
+    ///
+    /// Returns `RaireError::AssertionDoesNotHold` with the index of the first assertion that is not true,
+    /// or `RaireError::InvalidCandidateNumber` if an assertion refers to a candidate not in the votes.
+    pub fn verify_assertions_hold_for_votes(&self,votes:&Votes) -> Result<(),RaireError> {
+        let valid = |c:&CandidateIndex|c.0<votes.num_candidates();
+        for (index,a) in self.assertions.iter().enumerate() {
+            let continuing_valid = match &a.assertion { Assertion::NEN(nen) => nen.continuing.iter().all(valid), Assertion::NEB(_) => true };
+            if !(valid(&a.assertion.winner()) && valid(&a.assertion.loser()) && continuing_valid) { return Err(RaireError::InvalidCandidateNumber); }
+            let (tally_winner,tally_loser) = a.assertion.tallies(votes);
+            if tally_winner<=tally_loser { return Err(RaireError::AssertionDoesNotHold(index)); }
+        }
+        Ok(())
+    }
+
     /// Note that this can be very slow to check that the winner is not eliminated.
+    ///
+    /// The assertions are first checked for obvious inconsistencies with [check_assertion_consistency].
     pub fn verify_result_does_prove_winner(&self) -> Result<(),RaireError> {
+        check_assertion_consistency(&self.assertions)?;
         let all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
         let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
         for candidate in 0..self.num_candidates {
@@ -76,6 +577,88 @@ impl RaireResult {
     }
 }
 
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+/// An estimate of the effort needed for a human to verify a set of assertions. See [RaireResult::human_verification_effort].
+pub struct HumanEffort {
+    /// The number of assertions.
+    pub num_assertions : usize,
+    /// The total number of nodes in the trees of elimination order suffixes showing each non-winning candidate is ruled out.
+    pub tree_node_count : usize,
+    /// The depth of the deepest such tree, a tree with just a root having depth 1.
+    pub max_tree_depth : usize,
+}
+
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+/// A row of the assertion explainer's table. See [RaireResult::explainer_rows].
+pub struct ExplainerRow {
+    /// The index of the assertion in [RaireResult::assertions].
+    pub index : usize,
+    /// `NEB` or `NEN`.
+    pub assertion_type : String,
+    /// A plain English description of the assertion, see [Assertion::plain_english].
+    pub text : String,
+    pub difficulty : f64,
+    pub margin : BallotPaperCount,
+    /// The current risk measure of the assertion in an audit in progress. RAIRE does not know this, so it is
+    /// always None here; it is filled in by the caller, e.g. from the assertion's `status`.
+    pub risk : Option<f64>,
+}
+
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+/// The effect of adding an assertion with [RaireResult::add_assertion].
+pub struct AdditionEffect {
+    /// The overall difficulty before the assertion was added. The new difficulty is in the RaireResult.
+    pub previous_difficulty : AssertionDifficulty,
+    /// True if the added assertion is not needed given the other assertions.
+    pub new_assertion_redundant : bool,
+    /// Indices of previously necessary assertions that are no longer needed given the new assertion.
+    pub now_redundant : Vec<usize>,
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+/// The kind of argument used to show that a candidate did not win. See [RaireResult::elimination_arguments].
+pub enum ArgumentKind {
+    /// A single NEB assertion shows that some other candidate is not eliminated before this candidate.
+    SingleNEB,
+    /// A tree of possible elimination orders, each of which is ruled out by a NEB assertion.
+    OnlyNEBs,
+    /// A tree of possible elimination orders, each of which is ruled out by a NEN assertion.
+    OnlyNENs,
+    /// A tree of possible elimination orders, ruled out by a mixture of NEB and NEN assertions.
+    Mixed,
+}
+
+/// Count the elimination orders ending in `suffix` allowed by the `relevant` assertions. See [RaireResult::count_elimination_orders_allowed_by_assertions].
+fn count_elimination_orders(suffix:&mut Vec<CandidateIndex>,relevant:&[&Assertion],num_candidates:u32) -> f64 {
+    let mut still_relevant : Vec<&Assertion> = vec![];
+    for &a in relevant {
+        match a.ok_elimination_order_suffix(suffix) {
+            EffectOfAssertionOnEliminationOrderSuffix::Contradiction => { return 0.0; }
+            EffectOfAssertionOnEliminationOrderSuffix::Ok => {}
+            EffectOfAssertionOnEliminationOrderSuffix::NeedsMoreDetail => { still_relevant.push(a); }
+        }
+    }
+    let remaining = num_candidates as usize-suffix.len();
+    if still_relevant.is_empty() { return (1..=remaining).map(|i|i as f64).product(); }
+    let mut total = 0.0;
+    for c in 0..num_candidates {
+        let c = CandidateIndex(c);
+        if !suffix.contains(&c) {
+            suffix.insert(0,c);
+            total+=count_elimination_orders(suffix,&still_relevant,num_candidates);
+            suffix.remove(0);
+        }
+    }
+    total
+}
+
+/// Find whether any node in the tree is pruned by a NEB assertion, and whether any is pruned only by NEN assertions.
+fn find_assertion_kinds_used(node:&TreeNodeShowingWhatAssertionsPrunedIt,all_assertions:&[Assertion],uses_neb:&mut bool,uses_nen:&mut bool) {
+    if node.pruning_assertions.is_empty() {
+        for child in &node.children { find_assertion_kinds_used(child,all_assertions,uses_neb,uses_nen); }
+    } else if node.pruning_assertions.iter().any(|&a|all_assertions[a].is_neb()) { *uses_neb=true; } else { *uses_nen=true; }
+}
+
 #[derive(Debug)]
 /// An entry in the priority queue.
 struct SequenceAndEffort {
@@ -86,6 +669,8 @@ struct SequenceAndEffort {
     best_ancestor_length : usize,
     /// if not null, then a dive has already been done on the specified candidate.
     dive_done : Option<CandidateIndex>,
+    /// The priority in the frontier - higher is expanded first. Depends upon the FrontierOrdering used.
+    priority : f64,
 }
 
 impl SequenceAndEffort {
@@ -97,17 +682,26 @@ impl SequenceAndEffort {
         &self.pi[(self.pi.len()-self.best_ancestor_length)..]
     }
 
-    pub fn extend_by_candidate<A:AuditType>(&self,c:CandidateIndex,votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache)-> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn extend_by_candidate<A:AuditType>(&self,c:CandidateIndex,votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache,ordering:FrontierOrdering,tie_break:TieBreak,max_nen_continuing:Option<usize>,recorder:&mut SearchRecorder)-> Self {
         let mut pi_prime = vec![c];
         pi_prime.extend_from_slice(&self.pi); // π ′ ← [c] ++π
-        let a : AssertionAndDifficulty = find_best_audit(&pi_prime, votes, audit,neb_cache); // a in the original paper
+        let a : AssertionAndDifficulty = find_best_audit(&pi_prime, votes, audit,neb_cache,tie_break,max_nen_continuing,recorder); // a in the original paper
         let (best_ancestor_length,best_assertion_for_ancestor) = if a.difficulty < self.difficulty() { (pi_prime.len(), a.clone()) } else { (self.best_ancestor_length, self.best_assertion_for_ancestor.clone()) };
-        SequenceAndEffort { pi:pi_prime, best_ancestor_length, best_assertion_for_ancestor, dive_done: None }
+        let priority = ordering.priority(best_assertion_for_ancestor.difficulty,pi_prime.len(),votes.num_candidates());
+        SequenceAndEffort { pi:pi_prime, best_ancestor_length, best_assertion_for_ancestor, dive_done: None, priority }
+    }
+
+    /// The form of this saved in a [SearchCheckpoint].
+    fn checkpoint(&self) -> CheckpointedSuffix {
+        let best_assertion = if self.difficulty().is_finite() { Some(self.best_assertion_for_ancestor.clone()) } else { None };
+        CheckpointedSuffix{elimination_order_suffix:self.pi.clone(),best_assertion,best_ancestor_length:self.best_ancestor_length,dive_done:self.dive_done}
     }
 
     /// Called when the only use for this is to take the assertion and add it to the list of assertions.
     /// This checks that it is not already there and removes elements from the frontier that obviously match it.
-    pub fn just_take_assertion(self,assertions:&mut Vec<AssertionAndDifficulty>,frontier:&mut BinaryHeap<SequenceAndEffort>) {
+    pub fn just_take_assertion(self,assertions:&mut Vec<AssertionAndDifficulty>,frontier:&mut BinaryHeap<SequenceAndEffort>,recorder:&mut SearchRecorder) {
+        recorder.decide(&self.pi,&self.best_assertion_for_ancestor,DecisionKind::Accepted);
         if assertions.iter().any(|a|a.assertion==self.best_assertion_for_ancestor.assertion) {
             //println!("Didn't add assertion as it was already there");
         } else {
@@ -122,7 +716,7 @@ impl SequenceAndEffort {
     }
 
     /// Called when a sequence has gone as far as it can - i.e. all candidates are in the exclusion order list.
-    pub fn contains_all_candidates(self,assertions:&mut Vec<AssertionAndDifficulty>,frontier:&mut BinaryHeap<SequenceAndEffort>,bound:&mut f64) -> Result<(),RaireError> {
+    pub fn contains_all_candidates(self,assertions:&mut Vec<AssertionAndDifficulty>,frontier:&mut BinaryHeap<SequenceAndEffort>,bound:&mut f64,recorder:&mut SearchRecorder) -> Result<(),RaireError> {
         if self.difficulty().is_infinite() { // 23 if (ASN (asr[ba[π ′ ]]) = ∞):
             //println!("Couldn't deal with {:?}",new_sequence.pi);
             Err(RaireError::CouldNotRuleOut(self.pi)) // 24 terminate algorithm, full recount necessary
@@ -131,7 +725,7 @@ impl SequenceAndEffort {
                 *bound=self.difficulty(); // 27 LB ← max(LB, ASN (asr[ba[π′]]))
                 log::trace!("Found bound {} on elimination sequence {:?}",*bound,self.pi)
             }
-            self.just_take_assertion(assertions,frontier); // Steps 26 and 28 are same as 14 and 15.
+            self.just_take_assertion(assertions,frontier,recorder); // Steps 26 and 28 are same as 14 and 15.
             Ok(())
         }
     }
@@ -141,45 +735,402 @@ impl SequenceAndEffort {
 
 impl PartialOrd<Self> for SequenceAndEffort {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.difficulty().partial_cmp(&other.difficulty())
+        Some(self.cmp(other))
     }
 }
 
 impl Eq for SequenceAndEffort {}
 
+/// Consistent with [Ord], as required by the priority queue: equal means equal priority.
 impl PartialEq<Self> for SequenceAndEffort {
     fn eq(&self, other: &Self) -> bool {
-        self.pi==other.pi && self.difficulty()==other.difficulty()
+        self.cmp(other)==Ordering::Equal
     }
 }
 
 impl Ord for SequenceAndEffort {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.difficulty().partial_cmp(&other.difficulty()).unwrap_or(Ordering::Equal) // should always unwrap fine as NaN etc not allowed.
+        self.priority.partial_cmp(&other.priority).unwrap_or(Ordering::Equal) // should always unwrap fine as NaN etc not allowed.
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+/// What was done with an elimination order suffix in the search.
+pub enum DecisionKind {
+    /// The best assertion was accepted to rule out the suffix (if it was not already accepted).
+    Accepted,
+    /// The suffix was extended by each possible candidate, looking for a better assertion.
+    Expanded,
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+/// A decision made in the search about an elimination order suffix.
+pub struct Decision {
+    pub elimination_order_suffix : EliminationOrderSuffix,
+    /// The best assertion found ruling out the suffix (or a suffix of it).
+    pub best_assertion : AssertionAndDifficulty,
+    pub kind : DecisionKind,
+}
+
+#[derive(Clone,Debug,Default,Serialize,Deserialize)]
+/// Every decision made in the search, in order. See [raire_with_log].
+pub struct DecisionLog {
+    pub decisions : Vec<Decision>,
+}
+
+impl DecisionLog {
+    /// The distinct assertions accepted, in the order first accepted. Before trimming, these are the assertions in the result.
+    pub fn accepted_assertions(&self) -> Vec<AssertionAndDifficulty> {
+        let mut res : Vec<AssertionAndDifficulty> = vec![];
+        for d in &self.decisions {
+            if d.kind==DecisionKind::Accepted && !res.iter().any(|a|a.assertion==d.best_assertion.assertion) { res.push(d.best_assertion.clone()); }
+        }
+        res
+    }
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+/// An elimination order suffix still in the frontier when the search stopped.
+pub struct PendingSuffix {
+    pub elimination_order_suffix : EliminationOrderSuffix,
+    /// The best assertion found so far ruling out the suffix (or a suffix of it).
+    pub best_assertion : AssertionAndDifficulty,
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+/// The state of the search when it timed out. See [raire_with_partial].
+///
+/// Resuming the search from this state is not supported, but [PartialRaireResult::assertions_if_stopped_now]
+/// gives a valid (if suboptimal and untrimmed) set of assertions if one has been found yet.
+pub struct PartialRaireResult {
+    pub winner : CandidateIndex,
+    pub num_candidates : u32,
+    /// Assertions accepted so far, including those ruling out candidates deferred by the NEB pre-pass.
+    pub assertions : Vec<AssertionAndDifficulty>,
+    /// Elimination order suffixes not yet ruled out by `assertions`.
+    pub frontier : Vec<PendingSuffix>,
+    /// A lower bound on the difficulty of the optimal set of assertions.
+    pub lower_bound : AssertionDifficulty,
+}
+
+impl PartialRaireResult {
+    /// The assertions accepted so far, plus the best assertion for each pending suffix. Together these rule out every
+    /// alternative winner, although the set is generally not optimal and has not been trimmed.
+    /// None if some pending suffix has not yet been ruled out by any assertion.
+    pub fn assertions_if_stopped_now(&self) -> Option<Vec<AssertionAndDifficulty>> {
+        let mut res = self.assertions.clone();
+        for pending in &self.frontier {
+            if !pending.best_assertion.difficulty.is_finite() { return None; }
+            if !res.iter().any(|a|a.assertion==pending.best_assertion.assertion) { res.push(pending.best_assertion.clone()); }
+        }
+        Some(res)
+    }
+    /// The difficulty of [PartialRaireResult::assertions_if_stopped_now], an upper bound on the difficulty of the optimal
+    /// set of assertions. Infinite if no valid set has been found yet.
+    pub fn upper_bound(&self) -> AssertionDifficulty {
+        self.frontier.iter().map(|p|p.best_assertion.difficulty).chain(self.assertions.iter().map(|a|a.difficulty)).fold(self.lower_bound,f64::max)
+    }
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+/// An elimination order suffix in the frontier saved in a [SearchCheckpoint].
+pub struct CheckpointedSuffix {
+    pub elimination_order_suffix : EliminationOrderSuffix,
+    /// The best assertion found so far ruling out the last `best_ancestor_length` candidates of the suffix.
+    /// None if no assertion has been found to rule it out yet (stored this way as JSON cannot represent an infinite difficulty).
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub best_assertion : Option<AssertionAndDifficulty>,
+    pub best_ancestor_length : usize,
+    /// If present, the suffix extended by this candidate has already been searched by diving.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub dive_done : Option<CandidateIndex>,
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+/// The state of the search for assertions, saved periodically by [raire_with_checkpoints] so that a long search
+/// can be continued by [raire_resume], for instance after the process is restarted.
+pub struct SearchCheckpoint {
+    pub winner : CandidateIndex,
+    pub num_candidates : u32,
+    /// Assertions accepted so far.
+    pub assertions : Vec<AssertionAndDifficulty>,
+    /// Elimination order suffixes not yet ruled out by `assertions`.
+    pub frontier : Vec<CheckpointedSuffix>,
+    /// Candidates deferred by the NEB pre-pass and not yet searched, with their NEB assertions, in increasing order of difficulty.
+    pub neb_prepass : Vec<(CandidateIndex,AssertionAndDifficulty)>,
+    /// A lower bound on the difficulty of the optimal set of assertions.
+    pub lower_bound : AssertionDifficulty,
+    /// The units of work done in the search when the checkpoint was made, including any previous runs it was resumed from.
+    pub work_done : u64,
+    /// The [RaireOptions::max_nen_continuing] used for the search, which must also be used when it is resumed.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_nen_continuing : Option<usize>,
+    /// A fingerprint of the votes, audit type and required assertions the search was for. See [problem_fingerprint].
+    pub problem_fingerprint : String,
+}
+
+/// A fingerprint of the inputs to a search that are not otherwise stored in a [SearchCheckpoint] (the votes, the audit type and
+/// the required assertions), so that [raire_resume] can detect a checkpoint being used for a different problem. This is the
+/// 64 bit FNV-1a hash, as hex, of their JSON serialization. It is not cryptographic, but unlike [std::hash::DefaultHasher]
+/// it is the same on every platform and Rust version.
+pub fn problem_fingerprint<A:AuditType+Serialize>(votes:&Votes,audit:&A,required:&[Assertion]) -> String {
+    let json = serde_json::to_vec(&(&votes.votes,audit,required)).expect("votes, audit and assertions should always be serializable");
+    let hash = json.iter().fold(0xcbf29ce484222325u64,|hash,&b|(hash^b as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}",hash)
+}
+
+/// Where and how often [raire_with_checkpoints] saves the state of the search.
+struct Checkpointer<'a> {
+    interval : Duration,
+    /// The clock time (see [TimeOut::clock_time_taken_since_start]) of the last checkpoint, or the start of the search.
+    last : Duration,
+    /// Work done in runs the search was resumed from.
+    previous_work : u64,
+    /// See [SearchCheckpoint::problem_fingerprint].
+    problem_fingerprint : String,
+    on_checkpoint : &'a mut dyn FnMut(&SearchCheckpoint),
+}
+
+/// Records information about the search for diagnostic purposes.
+#[derive(Default)]
+struct SearchRecorder<'a> {
+    /// If Some, every assertion considered.
+    considered : Option<Vec<AssertionAndDifficulty>>,
+    /// If Some, the sets of continuing candidates visited, as bitmasks.
+    visited_continuing_sets : Option<HashSet<u64>>,
+    /// If Some, every decision made in the search.
+    decisions : Option<Vec<Decision>>,
+    /// If true, the state of the search is stored in `partial` if it times out.
+    capture_partial : bool,
+    partial : Option<PartialRaireResult>,
+    /// If Some, the state of the search is saved periodically.
+    checkpointer : Option<Checkpointer<'a>>,
+}
+
+impl <'a> SearchRecorder<'a> {
+    /// Visited continuing sets are recorded if there are fewer than 64 candidates, so they can be stored as bitmasks.
+    fn new(options:&RaireOptions,num_candidates:u32) -> Self {
+        SearchRecorder{
+            considered: if options.record_considered_assertions { Some(vec![]) } else { None },
+            visited_continuing_sets: if options.record_search_stats && num_candidates<64 { Some(HashSet::new()) } else { None },
+            decisions: None,
+            capture_partial: false,
+            partial: None,
+            checkpointer: None,
+        }
+    }
+    fn consider(&mut self,assertion:&AssertionAndDifficulty) {
+        if let Some(considered) = &mut self.considered { considered.push(assertion.clone()); }
+    }
+    fn decide(&mut self,pi:&[CandidateIndex],best_assertion:&AssertionAndDifficulty,kind:DecisionKind) {
+        if let Some(decisions) = &mut self.decisions { decisions.push(Decision{elimination_order_suffix:pi.to_vec(),best_assertion:best_assertion.clone(),kind}); }
+    }
+    /// Note that the continuing candidates are the candidates in the elimination order suffix pi.
+    fn visit(&mut self,pi:&[CandidateIndex]) {
+        if let Some(visited) = &mut self.visited_continuing_sets { visited.insert(pi.iter().fold(0u64,|mask,c|mask|(1<<c.0))); }
+    }
+    /// If checkpoints are being made and the interval has passed since the last one, pass the checkpoint made by `make` to the checkpointer.
+    fn checkpoint_if_due(&mut self,timeout:&TimeOut,make:impl FnOnce()->SearchCheckpoint) {
+        if let Some(checkpointer) = &mut self.checkpointer {
+            let now = timeout.clock_time_taken_since_start();
+            if now.saturating_sub(checkpointer.last)>=checkpointer.interval {
+                let checkpoint = SearchCheckpoint{work_done:checkpointer.previous_work+timeout.get_work_done(),problem_fingerprint:checkpointer.problem_fingerprint.clone(),..make()};
+                (checkpointer.on_checkpoint)(&checkpoint);
+                checkpointer.last = timeout.clock_time_taken_since_start();
+            }
+        }
+    }
+    fn stats(&self,num_candidates:u32) -> Option<SearchStats> {
+        self.visited_continuing_sets.as_ref().map(|visited|SearchStats{visited_continuing_sets:visited.len(),total_continuing_sets:1<<num_candidates})
     }
 }
 
-fn find_best_audit<A:AuditType>(pi:&[CandidateIndex],votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache) -> AssertionAndDifficulty {
+/// Find the best assertion ruling out the elimination order suffix pi, using `tie_break` to choose between equally difficult assertions.
+/// NEN assertions are not considered if there are more than `max_nen_continuing` continuing candidates.
+/// The assertions evaluated (the best NEB and the best NEN) and the continuing candidates are passed to `recorder`.
+fn find_best_audit<A:AuditType>(pi:&[CandidateIndex],votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache,tie_break:TieBreak,max_nen_continuing:Option<usize>,recorder:&mut SearchRecorder) -> AssertionAndDifficulty {
     let c = pi[0];
-    let mut res : AssertionAndDifficulty = AssertionAndDifficulty { assertion: Assertion::NEB(NotEliminatedBefore { winner: c, loser: c }), margin: BallotPaperCount(0), difficulty: f64::INFINITY }; // dummy infinitely bad assertion
+    recorder.visit(pi);
+    let mut res : AssertionAndDifficulty = impossible_assertion(c); // dummy infinitely bad assertion
     // consider WO contests
-    if let Some(assertion) = NotEliminatedBefore::find_best_assertion_using_cache(c, &pi[1..],votes, neb_cache) {
-        if assertion.difficulty < res.difficulty { res=assertion; }
-    }
+    let best_neb = NotEliminatedBefore::find_best_assertion_using_cache(c, &pi[1..],votes, neb_cache,tie_break);
     // consider IRV(c,c′,{c′′ | c′′ ∈ π}): Assertion that c beats some c′ != c ∈ π
-    if let Some(assertion) = NotEliminatedNext::find_best_difficulty(votes, audit, pi, c) {
+    let best_nen = if max_nen_continuing.is_some_and(|max|pi.len()>max) { None } else { NotEliminatedNext::find_best_difficulty_with_tie_break(votes, audit, pi, c, tie_break) };
+    for assertion in [best_neb,best_nen].into_iter().flatten() {
         //println!("{:?}",assertion);
-        if assertion.difficulty < res.difficulty { res=assertion; }
+        recorder.consider(&assertion);
+        if assertion.difficulty < res.difficulty || (assertion.difficulty==res.difficulty && assertion.difficulty.is_finite() && tie_break.prefers(&assertion.assertion,&res.assertion)) { res=assertion; }
     }
     //println!("FindBestAudit({:?})={:?}",pi,res);
     res
 }
 
+/// A dummy assertion with infinite difficulty, used when no assertion has been found yet.
+fn impossible_assertion(c:CandidateIndex) -> AssertionAndDifficulty {
+    AssertionAndDifficulty { assertion: Assertion::NEB(NotEliminatedBefore { winner: c, loser: c }), margin: BallotPaperCount(0), difficulty: f64::INFINITY, tallies: None }
+}
+
+/// Make the single candidate sequence [c] for the initial frontier, with its best assertion.
+#[allow(clippy::too_many_arguments)]
+fn initial_sequence<A:AuditType>(c:CandidateIndex,votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache,ordering:FrontierOrdering,tie_break:TieBreak,max_nen_continuing:Option<usize>,recorder:&mut SearchRecorder) -> SequenceAndEffort {
+    let pi = vec![c];
+    //  asr[π] ← a ⊲ Record best assertion for π
+    let best_assertion_for_pi = find_best_audit(&pi,votes,audit,neb_cache,tie_break,max_nen_continuing,recorder);  // a in the original paper
+    //  ba[π] ← π ⊲ Record best ancestor sequence for π
+    let best_ancestor_length = pi.len();
+    let priority = ordering.priority(best_assertion_for_pi.difficulty,pi.len(),votes.num_candidates());
+    SequenceAndEffort{pi,best_ancestor_length,best_assertion_for_ancestor:best_assertion_for_pi, dive_done: None, priority } // difficulty comes from asr[π].
+}
+
+/// If the winner has an absolute majority of first preferences, then NEB(winner,c) holds for every other candidate c,
+/// and these assertions alone prove the winner. Return them if they are also provably optimal, that is, no more
+/// difficult than [Votes::margin_based_difficulty_bound], which is a lower bound on the difficulty of any set
+/// of assertions. Otherwise return None, and the normal search should be used, as it may find something easier.
+fn majority_winner_assertions<A:AuditType>(votes:&Votes,winner:CandidateIndex,audit:&A,neb_cache:&NotEliminatedBeforeCache) -> Option<Vec<AssertionAndDifficulty>> {
+    let winner_first_preferences = votes.first_preference_only_tally(winner);
+    if winner_first_preferences.0*2<=votes.total_votes().0 { return None; }
+    let assertions : Vec<AssertionAndDifficulty> = (0..votes.num_candidates()).map(CandidateIndex).filter(|&c|c!=winner).map(|loser|{
+        let neb = NotEliminatedBefore{winner,loser};
+        let (difficulty,margin) = neb_cache.difficulty(neb);
+        AssertionAndDifficulty{assertion:Assertion::NEB(neb),margin,difficulty,tallies:None}
+    }).collect();
+    let difficulty = assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max);
+    if difficulty.is_finite() && difficulty<=votes.margin_based_difficulty_bound(audit) { Some(assertions) } else { None }
+}
+
 /// If true, use Michelle's diving search order optimization.
 /// Testing shows that it is almost always a moderate improvement in speed.
 const USE_DIVING : bool = true;
 
+/// Options for the search for assertions, other than the votes, winner, audit type and trim algorithm.
+/// The [Default] options are those used by [raire], so callers can set just the ones they need,
+/// e.g. `RaireOptions{tie_break:TieBreak::PreferShorterNEN,..Default::default()}`.
+#[derive(Clone,Debug,Default)]
+pub struct RaireOptions {
+    /// If true, candidates who can be shown to be behind the winner by a
+    /// NEB assertion are not initially put into the frontier. Instead, after the frontier is exhausted,
+    /// the NEB assertion is used directly if its difficulty is no worse than the lower bound found
+    /// by the search, otherwise the candidate is searched normally. This does not change the
+    /// difficulty of the result, but can dramatically reduce the number of sequences that need to
+    /// be expanded when there are many minor candidates. The assertions may differ from those found
+    /// without it, although they are no harder. Default false.
+    pub use_neb_prepass : bool,
+    /// Which sequence in the frontier is expanded next. It does not change the difficulty of the result.
+    pub frontier_ordering : FrontierOrdering,
+    /// Which of two equally difficult assertions is used to rule out an elimination order suffix.
+    /// It does not change the difficulty of the result.
+    pub tie_break : TieBreak,
+    /// If true, every assertion evaluated during the search, including rejected ones,
+    /// is returned in the `considered` field of the result. This is useful for understanding the choices made, but may be large.
+    pub record_considered_assertions : bool,
+    /// If true, statistics about how effectively the search is pruned are returned in the `stats` field of the result.
+    /// This has a small cost in time and memory, so is off by default.
+    pub record_search_stats : bool,
+    /// If present, any elimination order suffix taken from the frontier whose best assertion has at most
+    /// this difficulty is immediately ruled out by that assertion, rather than looking for a better assertion for a longer suffix.
+    /// This trades optimality of the set of assertions for speed. The winner is still proven.
+    pub auto_accept_difficulty : Option<f64>,
+    /// If present, NEN assertions with more than this many continuing candidates are not used, as they
+    /// can be hard to explain to audit boards. Zero means only NEB assertions are used. This may increase the difficulty, or
+    /// make it impossible to prove the winner (`RaireError::CouldNotRuleOut`).
+    pub max_nen_continuing : Option<usize>,
+    /// Assertions the output must include, for instance ones already committed to in an earlier round of an audit.
+    /// These are included in the search from the start, so the difficulty is at least that of the hardest required
+    /// assertion, and elimination order suffixes they rule out are not searched. They are never trimmed.
+    ///
+    /// Each required assertion must hold for the votes (otherwise `RaireError::AssertionDoesNotHold` is returned with
+    /// its index), must not contradict the winner (`RaireError::AssertionRulesOutWinner`), and must be a valid assertion
+    /// (`RaireError::InvalidAssertion` or `RaireError::InvalidCandidateNumber`).
+    pub required_assertions : Vec<Assertion>,
+}
+
+/// Run the RAIRE algorithm with the default [RaireOptions].
+///
+/// If the winner has an absolute majority of first preferences, and NEB assertions against every other candidate
+/// are provably optimal, they are used directly without searching.
 pub fn raire<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,trim_algorithm:TrimAlgorithm,timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    raire_with_options(votes,winner,audit,trim_algorithm,&RaireOptions::default(),timeout)
+}
+
+/// Run the RAIRE algorithm as [raire] does, with the given `options`.
+pub fn raire_with_options<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,trim_algorithm:TrimAlgorithm,options:&RaireOptions,timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    let mut recorder = SearchRecorder::new(options,votes.num_candidates());
+    raire_with_recorder(votes,winner,audit,None,trim_algorithm,options,&mut recorder,timeout)
+}
+
+/// Run the RAIRE algorithm as [raire_with_options] does, also returning a log of every decision made in the search, so researchers
+/// can replay the decisions. This is heavyweight, and only intended for analysis.
+pub fn raire_with_log<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,trim_algorithm:TrimAlgorithm,options:&RaireOptions,timeout:&mut TimeOut) -> Result<(RaireResult,DecisionLog),RaireError> {
+    let mut recorder = SearchRecorder::new(options,votes.num_candidates());
+    recorder.decisions = Some(vec![]);
+    let result = raire_with_recorder(votes,winner,audit,None,trim_algorithm,options,&mut recorder,timeout)?;
+    Ok((result,DecisionLog{decisions:recorder.decisions.unwrap_or_default()}))
+}
+
+/// Run the RAIRE algorithm as [raire_with_options] does, also returning the state of the search if it times out while looking for
+/// assertions, so callers can see how close it got. The partial result is None if the search did not time out
+/// in the main search loop.
+pub fn raire_with_partial<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,trim_algorithm:TrimAlgorithm,options:&RaireOptions,timeout:&mut TimeOut) -> (Result<RaireResult,RaireError>,Option<PartialRaireResult>) {
+    let mut recorder = SearchRecorder::new(options,votes.num_candidates());
+    recorder.capture_partial = true;
+    let result = raire_with_recorder(votes,winner,audit,None,trim_algorithm,options,&mut recorder,timeout);
+    (result,recorder.partial)
+}
+
+/// Run the RAIRE algorithm as [raire_with_options] does for each of several audit types, such as both a ballot comparison and a
+/// ballot polling audit for planning, determining the winner only once. The limits in `timeout` apply to the whole
+/// computation, not to each audit type separately.
+///
+/// An error determining the winner (such as a tie) is returned as the outer error, otherwise there is a result for each audit type, in order.
+pub fn raire_multi<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audits:&[A],trim_algorithm:TrimAlgorithm,options:&RaireOptions,timeout:&mut TimeOut) -> Result<Vec<Result<RaireResult,RaireError>>,RaireError> {
+    let (winner,irv_result,time_to_determine_winners) = determine_winner(votes,winner,timeout)?;
+    Ok(audits.iter().map(|audit|{
+        let mut recorder = SearchRecorder::new(options,votes.num_candidates());
+        raire_given_winner(votes,winner,&irv_result,time_to_determine_winners,audit,None,trim_algorithm,options,&mut recorder,timeout)
+    }).collect())
+}
+
+/// Run the RAIRE algorithm as [raire_with_options] does, also passing the state of the search for assertions to `on_checkpoint` each
+/// time `checkpoint_interval` of clock time has passed since the last one, for instance to save it as JSON. If the run
+/// is interrupted, for instance by the process being restarted, the search can be continued from the last checkpoint
+/// by [raire_resume]. This is intended for very hard contests.
+#[allow(clippy::too_many_arguments)]
+pub fn raire_with_checkpoints<A:AuditType+Serialize>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,trim_algorithm:TrimAlgorithm,options:&RaireOptions,checkpoint_interval:Duration,on_checkpoint:&mut dyn FnMut(&SearchCheckpoint),timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    let mut recorder = SearchRecorder::new(options,votes.num_candidates());
+    let problem_fingerprint = problem_fingerprint(votes,audit,&options.required_assertions);
+    recorder.checkpointer = Some(Checkpointer{interval:checkpoint_interval,last:timeout.clock_time_taken_since_start(),previous_work:0,problem_fingerprint,on_checkpoint});
+    raire_with_recorder(votes,winner,audit,None,trim_algorithm,options,&mut recorder,timeout)
+}
+
+/// Continue the search for assertions from a `checkpoint` made by [raire_with_checkpoints] (or by an earlier call to this),
+/// and then trim the assertions as [raire] does. The votes and audit type must be the same as for the run that made the
+/// checkpoint (checked using [SearchCheckpoint::problem_fingerprint]). Further checkpoints are passed to `on_checkpoint` as in [raire_with_checkpoints], so the resumed search
+/// can itself be resumed. The winner is determined again, and must be the winner in the checkpoint. The limits in
+/// `timeout`, and the times in the result, are for this run only. The `required_assertions` and `max_nen_continuing` in
+/// `options` must be the same as for the run that made the checkpoint. `use_neb_prepass` is ignored, as the pre-pass has already been done.
+///
+/// Returns [RaireError::WrongNumberOfCandidates], [RaireError::WrongWinner] or [RaireError::InvalidCheckpoint] if the
+/// checkpoint is for a different problem, and [RaireError::InvalidCandidateNumber] or [RaireError::InvalidCheckpoint] if it is malformed.
+#[allow(clippy::too_many_arguments)]
+pub fn raire_resume<A:AuditType+Serialize>(votes:&Votes,audit:&A,checkpoint:&SearchCheckpoint,trim_algorithm:TrimAlgorithm,options:&RaireOptions,checkpoint_interval:Duration,on_checkpoint:&mut dyn FnMut(&SearchCheckpoint),timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    if votes.num_candidates()!=checkpoint.num_candidates { return Err(RaireError::WrongNumberOfCandidates(votes.num_candidates(),checkpoint.num_candidates)); }
+    if checkpoint.winner.0>=checkpoint.num_candidates { return Err(RaireError::InvalidCandidateNumber); }
+    let problem_fingerprint = problem_fingerprint(votes,audit,&options.required_assertions);
+    if problem_fingerprint!=checkpoint.problem_fingerprint { return Err(RaireError::InvalidCheckpoint("made for different votes, audit type or required assertions".to_string())); }
+    let mut recorder = SearchRecorder::new(options,votes.num_candidates());
+    recorder.checkpointer = Some(Checkpointer{interval:checkpoint_interval,last:timeout.clock_time_taken_since_start(),previous_work:checkpoint.work_done,problem_fingerprint,on_checkpoint});
+    raire_with_recorder(votes,Some(checkpoint.winner),audit,Some(checkpoint),trim_algorithm,options,&mut recorder,timeout)
+}
+
+/// The body of [raire_with_options], recording information about the search in `recorder`, continuing from `resume_from` if given.
+#[allow(clippy::too_many_arguments)]
+fn raire_with_recorder<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,resume_from:Option<&SearchCheckpoint>,trim_algorithm:TrimAlgorithm,options:&RaireOptions,recorder:&mut SearchRecorder,timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    let (winner,irv_result,time_to_determine_winners) = determine_winner(votes,winner,timeout)?;
+    raire_given_winner(votes,winner,&irv_result,time_to_determine_winners,audit,resume_from,trim_algorithm,options,recorder,timeout)
+}
+/// The first part of [raire], determining the winner, which must match `winner` if given, and be unique.
+/// Also returns the IRV result and the time taken.
+fn determine_winner(votes:&Votes,winner:Option<CandidateIndex>,timeout:&mut TimeOut) -> Result<(CandidateIndex,IRVResult,TimeTaken),RaireError> {
     log::debug!("Starting raire with {} candidates and {} distinct votes",votes.num_candidates(),votes.votes.len());
     if votes.num_candidates()==0 { return Err(RaireError::InvalidNumberOfCandidates); }
     let irv_result = votes.run_election(timeout)?;
@@ -190,102 +1141,302 @@ pub fn raire<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,tr
     if irv_result.possible_winners.len()!=1 { return Err(RaireError::TiedWinners(irv_result.possible_winners))}
     let winner : CandidateIndex = irv_result.possible_winners[0]; // replace option by actual value.
     log::debug!("IRV winner {} elimination order {:?}",winner,irv_result.elimination_order);
+    Ok((winner,irv_result,time_to_determine_winners))
+}
+
+/// The rest of [raire] once the winner is known: finding and trimming assertions.
+#[allow(clippy::too_many_arguments)]
+fn raire_given_winner<A:AuditType>(votes:&Votes,winner:CandidateIndex,irv_result:&IRVResult,time_to_determine_winners:TimeTaken,audit:&A,resume_from:Option<&SearchCheckpoint>,trim_algorithm:TrimAlgorithm,options:&RaireOptions,recorder:&mut SearchRecorder,timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+    let required = score_required_assertions(&options.required_assertions,votes,audit,&irv_result.elimination_order)?;
+    let start = timeout.time_taken();
+    let (mut assertions,lower_bound) = timeout.within_finding_assertions_limit(|timeout|find_assertions(votes,winner,&irv_result.elimination_order,audit,&required,resume_from,options,recorder,timeout))?;
+    let time_to_find_assertions = timeout.time_taken()-start;
+    log::debug!("Finished generating {} assertions difficulty {}, now need to trim.",assertions.len(),lower_bound);
+    let warning_trim_timed_out = match timeout.within_trimming_limit(|timeout|crate::tree_showing_what_assertions_pruned_leaves::order_assertions_and_remove_unnecessary(&mut assertions,winner,votes.num_candidates(),trim_algorithm,timeout)) {
+        Ok(_) => false,
+        Err(RaireError::TimeoutTrimmingAssertions) => true,
+        Err(e) => {return Err(e);}
+    };
+    let time_to_trim_assertions = timeout.time_taken()-time_to_find_assertions-start;
+    log::debug!("Trimmed assertions down to {}.",assertions.len());
+    include_required_assertions(&mut assertions,&required);
+    let margin = assertions.iter().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0));
+    // simple fast consistency check - make sure that the ostensible elimination order is consistent with all the assertions. If so, then the winner is not ruled out, and all is good.
+    for a in &assertions {
+        match a.assertion.ok_elimination_order_suffix(&irv_result.elimination_order) {
+            EffectOfAssertionOnEliminationOrderSuffix::Ok => {}
+            _ => { return Err(RaireError::InternalErrorRuledOutWinner); }
+        }
+    }
+    Ok(RaireResult{assertions, difficulty: lower_bound, margin, winner,num_candidates:votes.num_candidates(), example_elimination_order:irv_result.elimination_order.clone(), time_to_determine_winners, time_to_find_assertions, time_to_trim_assertions, warning_trim_timed_out, warning_provisional:false, truncated:false, conditional_on_tie_break:false, stats:recorder.stats(votes.num_candidates()), considered:recorder.considered.take().unwrap_or_default() })
+}
+
+/// Check the assertions required by [RaireOptions::required_assertions] and compute their difficulties.
+pub(crate) fn score_required_assertions<A:AuditType>(required:&[Assertion],votes:&Votes,audit:&A,elimination_order:&[CandidateIndex]) -> Result<Vec<AssertionAndDifficulty>,RaireError> {
+    let valid = |c:&CandidateIndex|c.0<votes.num_candidates();
+    required.iter().enumerate().map(|(index,assertion)|{
+        let continuing_valid = match assertion { Assertion::NEN(nen) => nen.continuing.iter().all(valid), Assertion::NEB(_) => true };
+        if !(valid(&assertion.winner()) && valid(&assertion.loser()) && continuing_valid) { return Err(RaireError::InvalidCandidateNumber); }
+        let assertion = match assertion { // make the continuing candidates canonical, as the search relies on them being sorted.
+            Assertion::NEN(nen) => Assertion::NEN(NotEliminatedNext::new(nen.winner,nen.loser,nen.continuing.clone())?),
+            Assertion::NEB(neb) if neb.winner==neb.loser => { return Err(RaireError::InvalidAssertion); }
+            Assertion::NEB(neb) => Assertion::NEB(*neb),
+        };
+        let (difficulty,margin) = assertion.difficulty(votes,audit);
+        if margin.0==0 { return Err(RaireError::AssertionDoesNotHold(index)); }
+        if assertion.ok_elimination_order_suffix(elimination_order)!=EffectOfAssertionOnEliminationOrderSuffix::Ok { return Err(RaireError::AssertionRulesOutWinner); }
+        Ok(AssertionAndDifficulty{assertion,margin,difficulty,tallies:None})
+    }).collect()
+}
+
+/// Add any of the `required` assertions that are not already in `assertions`, for instance if trimming removed them as redundant.
+pub(crate) fn include_required_assertions(assertions:&mut Vec<AssertionAndDifficulty>,required:&[AssertionAndDifficulty]) {
+    for r in required {
+        if !assertions.iter().any(|a|a.assertion==r.assertion) { assertions.push(r.clone()); }
+    }
+}
+
+/// The state of the search in [find_assertions], which can be saved as a [SearchCheckpoint].
+struct SearchState {
+    assertions : Vec<AssertionAndDifficulty>, // A in the original paper
+    lower_bound : AssertionDifficulty, // LB in the original paper. A lower bound on the difficulty of the problem.
+    frontier : BinaryHeap<SequenceAndEffort>, // F in the original paper
+    /// Candidates who are behind the winner by a NEB assertion, with that assertion. Sorted by increasing difficulty.
+    neb_prepass : Vec<(CandidateIndex,AssertionAndDifficulty)>,
+}
+
+impl SearchState {
+    /// The state at the start of the search. The `required` assertions are already included, and elimination order suffixes they rule out are not in the frontier.
+    #[allow(clippy::too_many_arguments)]
+    fn initial<A:AuditType>(votes:&Votes,winner:CandidateIndex,audit:&A,required:&[AssertionAndDifficulty],neb_cache:&NotEliminatedBeforeCache,options:&RaireOptions,recorder:&mut SearchRecorder) -> Self {
+        let mut state = SearchState{assertions:required.to_vec(),lower_bound:required.iter().map(|a|a.difficulty).fold(0.0,f64::max),frontier:BinaryHeap::new(),neb_prepass:vec![]};
+        if let Some(neb_assertions) = majority_winner_assertions(votes,winner,audit,neb_cache) {
+            log::debug!("Winner has a majority of first preferences, NEB assertions are optimal");
+            for assertion in &neb_assertions {
+                recorder.consider(assertion);
+                recorder.decide(&[assertion.assertion.loser()],assertion,DecisionKind::Accepted);
+            }
+            state.lower_bound = neb_assertions.iter().map(|a|a.difficulty).fold(state.lower_bound,f64::max);
+            for a in neb_assertions {
+                if !state.assertions.iter().any(|b|b.assertion==a.assertion) { state.assertions.push(a); }
+            }
+        } else {
+            // Populate F with single-candidate sequences
+            for c in 0..votes.num_candidates() {
+                let c = CandidateIndex(c);
+                if c!=winner && !ruled_out_by_required(required,&[c]) { // 4 for each(c ∈ C \ {c w }):
+                    if options.use_neb_prepass {
+                        let neb = NotEliminatedBefore{winner,loser:c};
+                        let (difficulty,margin) = neb_cache.difficulty(neb);
+                        if difficulty.is_finite() {
+                            let assertion = AssertionAndDifficulty{assertion:Assertion::NEB(neb),margin,difficulty,tallies:None};
+                            recorder.consider(&assertion);
+                            state.neb_prepass.push((c,assertion));
+                            continue;
+                        }
+                    }
+                    state.frontier.push(initial_sequence(c,votes,audit,neb_cache,options.frontier_ordering,options.tie_break,options.max_nen_continuing,recorder));
+                }
+            }
+        }
+        state.neb_prepass.sort_unstable_by(|(_,a),(_,b)|a.difficulty.partial_cmp(&b.difficulty).unwrap_or(Ordering::Equal));
+        state
+    }
+
+    /// The state saved in `checkpoint`, which should be for the same winner and number of candidates.
+    fn from_checkpoint(checkpoint:&SearchCheckpoint,options:&RaireOptions) -> Result<Self,RaireError> {
+        if checkpoint.max_nen_continuing!=options.max_nen_continuing { return Err(RaireError::InvalidCheckpoint(format!("made with max_nen_continuing {:?}, not {:?}",checkpoint.max_nen_continuing,options.max_nen_continuing))); }
+        let num_candidates = checkpoint.num_candidates;
+        let valid = |c:&CandidateIndex|c.0<num_candidates;
+        let valid_assertion = |a:&AssertionAndDifficulty| valid(&a.assertion.winner()) && valid(&a.assertion.loser()) && match &a.assertion {
+            Assertion::NEN(nen) => nen.continuing.iter().all(valid),
+            Assertion::NEB(_) => true,
+        };
+        if !(checkpoint.assertions.iter().all(valid_assertion) && checkpoint.neb_prepass.iter().all(|(c,a)|valid(c)&&valid_assertion(a))) { return Err(RaireError::InvalidCandidateNumber); }
+        let mut frontier = BinaryHeap::new();
+        for suffix in &checkpoint.frontier {
+            let pi = &suffix.elimination_order_suffix;
+            if !(pi.iter().all(valid) && suffix.dive_done.iter().all(valid) && suffix.best_assertion.iter().all(valid_assertion)) { return Err(RaireError::InvalidCandidateNumber); }
+            if pi.is_empty() || pi.len()>=num_candidates as usize || suffix.best_ancestor_length==0 || suffix.best_ancestor_length>pi.len() || pi.last()==Some(&checkpoint.winner) || pi.iter().enumerate().any(|(i,c)|pi[..i].contains(c)) {
+                return Err(RaireError::InvalidCheckpoint(format!("invalid elimination order suffix {:?}",pi)));
+            }
+            let best_assertion_for_ancestor = suffix.best_assertion.clone().unwrap_or_else(||impossible_assertion(pi[pi.len()-suffix.best_ancestor_length]));
+            let priority = options.frontier_ordering.priority(best_assertion_for_ancestor.difficulty,pi.len(),num_candidates);
+            frontier.push(SequenceAndEffort{pi:pi.clone(),best_assertion_for_ancestor,best_ancestor_length:suffix.best_ancestor_length,dive_done:suffix.dive_done,priority});
+        }
+        Ok(SearchState{assertions:checkpoint.assertions.clone(),lower_bound:checkpoint.lower_bound,frontier,neb_prepass:checkpoint.neb_prepass.clone()})
+    }
+}
+
+/// Whether one of the `required` assertions rules out the elimination order suffix `pi`, so it doesn't need to be searched.
+fn ruled_out_by_required(required:&[AssertionAndDifficulty],pi:&[CandidateIndex]) -> bool {
+    required.iter().any(|a|a.assertion.ok_elimination_order_suffix(pi)==EffectOfAssertionOnEliminationOrderSuffix::Contradiction)
+}
+
+/// The search for assertions in [raire], once the winner is known, returning the untrimmed assertions and their difficulty.
+/// The `required` assertions are always included, and elimination order suffixes they rule out are not searched.
+/// If `resume_from` is given, the search continues from that state rather than starting afresh.
+#[allow(clippy::too_many_arguments)]
+fn find_assertions<A:AuditType>(votes:&Votes,winner:CandidateIndex,elimination_order:&[CandidateIndex],audit:&A,required:&[AssertionAndDifficulty],resume_from:Option<&SearchCheckpoint>,options:&RaireOptions,recorder:&mut SearchRecorder,timeout:&mut TimeOut) -> Result<(Vec<AssertionAndDifficulty>,AssertionDifficulty),RaireError> {
+    let RaireOptions{frontier_ordering,tie_break,auto_accept_difficulty,max_nen_continuing,..} = *options;
     let neb_cache = NotEliminatedBeforeCache::new(votes,audit);
     log::trace!("Created NEB cache");
     //println!("Calling raire with {} votes {} candidates winner {}",votes.total_votes(),votes.num_candidates(),winner);
-    let mut assertions : Vec<AssertionAndDifficulty> = vec![]; // A in the original paper
-    let mut lower_bound: AssertionDifficulty = 0.0; // LB in the original paper. A lower bound on the difficulty of the problem.
-    let mut frontier = BinaryHeap::new(); // F in the original paper
+    let SearchState{mut assertions,mut lower_bound,mut frontier,mut neb_prepass} = match resume_from {
+        Some(checkpoint) => SearchState::from_checkpoint(checkpoint,options)?,
+        None => SearchState::initial(votes,winner,audit,required,&neb_cache,options,recorder),
+    };
     let mut last_difficulty:f64 = f64::INFINITY;
-    // Populate F with single-candidate sequences
-    for c in 0..votes.num_candidates() {
-        let c = CandidateIndex(c);
-        if c!=winner { // 4 for each(c ∈ C \ {c w }):
-            let pi = vec![c];
-            //  asr[π] ← a ⊲ Record best assertion for π
-            let best_assertion_for_pi = find_best_audit(&pi,votes,audit,&neb_cache);  // a in the original paper
-            //  ba[π] ← π ⊲ Record best ancestor sequence for π
-            let best_ancestor_length = pi.len();
-            frontier.push(SequenceAndEffort{pi,best_ancestor_length,best_assertion_for_ancestor:best_assertion_for_pi, dive_done: None }); // difficulty comes from asr[π].
-        }
-    }
+    log::trace!("NEB pre-pass deferred {} candidates",neb_prepass.len());
     // Repeatedly expand the sequence with largest ASN in F
-    while let Some(mut sequence_being_considered) = frontier.pop() { // 10-12
-        if timeout.quick_check_timeout() { return Err(RaireError::TimeoutFindingAssertions(sequence_being_considered.difficulty().max(lower_bound))) }
-        if sequence_being_considered.difficulty()!=last_difficulty {
-            last_difficulty=sequence_being_considered.difficulty();
-            log::trace!("Difficulty reduced to {}{}",last_difficulty,if last_difficulty<= lower_bound {" OK"} else {""});
-        }
-        //println!("Considering {:?}",sequence_being_considered);
-        if sequence_being_considered.difficulty()<= lower_bound { // may as well just include.
-            sequence_being_considered.just_take_assertion(&mut assertions,&mut frontier);
-        } else {
-            if USE_DIVING && !sequence_being_considered.dive_done.is_some() {
-                let mut last : Option<SequenceAndEffort> = None;
-                assert_eq!(irv_result.elimination_order.len(),votes.num_candidates() as usize);
-                for &c in irv_result.elimination_order.iter().rev() {
-                    if !sequence_being_considered.pi.contains(&c) {
-                        let new_sequence = match last.take() { // don't repeat work! Mark that this path has already been dealt with.
-                            Some(mut l) => {
-                                l.dive_done=Some(c);
-                                let new_sequence = l.extend_by_candidate(c,votes,audit,&neb_cache);
-                                frontier.push(l);
-                                new_sequence
+    loop {
+        while let Some(mut sequence_being_considered) = frontier.pop() { // 10-12
+            if timeout.quick_check_timeout() {
+                if recorder.capture_partial {
+                    let mut partial_assertions = assertions.clone();
+                    partial_assertions.extend(neb_prepass.iter().map(|(_,a)|a.clone()));
+                    let frontier = std::iter::once(&sequence_being_considered).chain(frontier.iter()).map(|s|PendingSuffix{elimination_order_suffix:s.pi.clone(),best_assertion:s.best_assertion_for_ancestor.clone()}).collect();
+                    recorder.partial = Some(PartialRaireResult{winner,num_candidates:votes.num_candidates(),assertions:partial_assertions,frontier,lower_bound});
+                }
+                return Err(RaireError::TimeoutFindingAssertions(sequence_being_considered.difficulty().max(lower_bound)))
+            }
+            recorder.checkpoint_if_due(timeout,||SearchCheckpoint{winner,num_candidates:votes.num_candidates(),assertions:assertions.clone(),frontier:std::iter::once(&sequence_being_considered).chain(frontier.iter()).map(SequenceAndEffort::checkpoint).collect(),neb_prepass:neb_prepass.clone(),lower_bound,work_done:0,max_nen_continuing,problem_fingerprint:String::new()});
+            if ruled_out_by_required(required,&sequence_being_considered.pi) { continue; }
+            if sequence_being_considered.difficulty()!=last_difficulty {
+                last_difficulty=sequence_being_considered.difficulty();
+                log::trace!("Difficulty reduced to {}{}",last_difficulty,if last_difficulty<= lower_bound {" OK"} else {""});
+            }
+            //println!("Considering {:?}",sequence_being_considered);
+            if sequence_being_considered.difficulty()<= lower_bound { // may as well just include.
+                sequence_being_considered.just_take_assertion(&mut assertions,&mut frontier,recorder);
+            } else if auto_accept_difficulty.is_some_and(|auto_accept|sequence_being_considered.difficulty()<=auto_accept) { // good enough, don't look for better.
+                lower_bound=sequence_being_considered.difficulty();
+                sequence_being_considered.just_take_assertion(&mut assertions,&mut frontier,recorder);
+            } else {
+                recorder.decide(&sequence_being_considered.pi,&sequence_being_considered.best_assertion_for_ancestor,DecisionKind::Expanded);
+                if USE_DIVING && !sequence_being_considered.dive_done.is_some() {
+                    let mut last : Option<SequenceAndEffort> = None;
+                    assert_eq!(elimination_order.len(),votes.num_candidates() as usize);
+                    for &c in elimination_order.iter().rev() {
+                        if !sequence_being_considered.pi.contains(&c) {
+                            let new_sequence = match last.take() { // don't repeat work! Mark that this path has already been dealt with.
+                                Some(mut l) => {
+                                    l.dive_done=Some(c);
+                                    let new_sequence = l.extend_by_candidate(c,votes,audit,&neb_cache,frontier_ordering,tie_break,max_nen_continuing,recorder);
+                                    frontier.push(l);
+                                    new_sequence
+                                }
+                                None => {
+                                    sequence_being_considered.dive_done=Some(c);
+                                    sequence_being_considered.extend_by_candidate(c,votes,audit,&neb_cache,frontier_ordering,tie_break,max_nen_continuing,recorder)
+                                },
+                            };
+                            if ruled_out_by_required(required,&new_sequence.pi) { break; }
+                            if new_sequence.difficulty()<= lower_bound {
+                                new_sequence.just_take_assertion(&mut assertions,&mut frontier,recorder);
+                                break;
+                            } else {
+                                last = Some(new_sequence);
                             }
-                            None => {
-                                sequence_being_considered.dive_done=Some(c);
-                                sequence_being_considered.extend_by_candidate(c,votes,audit,&neb_cache)
-                            },
-                        };
-                        if new_sequence.difficulty()<= lower_bound {
-                            new_sequence.just_take_assertion(&mut assertions,&mut frontier);
-                            break;
-                        } else {
-                            last = Some(new_sequence);
                         }
                     }
-                }
-                if let Some(last) = last {
-                    assert_eq!(last.pi.len(),votes.num_candidates() as usize);
-                    last.contains_all_candidates(&mut assertions,&mut frontier,&mut lower_bound)?;
-                    if sequence_being_considered.difficulty()<= lower_bound { // the lower bound may have changed in such a way that there is no point continuing this assertion.
-                        sequence_being_considered.just_take_assertion(&mut assertions,&mut frontier);
-                        continue;
+                    if let Some(last) = last.filter(|last|!ruled_out_by_required(required,&last.pi)) {
+                        assert_eq!(last.pi.len(),votes.num_candidates() as usize);
+                        last.contains_all_candidates(&mut assertions,&mut frontier,&mut lower_bound,recorder)?;
+                        if sequence_being_considered.difficulty()<= lower_bound { // the lower bound may have changed in such a way that there is no point continuing this assertion.
+                            sequence_being_considered.just_take_assertion(&mut assertions,&mut frontier,recorder);
+                            continue;
+                        }
                     }
                 }
-            }
-            for c in 0..votes.num_candidates() { // for each(c ∈ C \ π):
-                let c = CandidateIndex(c);
-                if !(sequence_being_considered.pi.contains(&c)||sequence_being_considered.dive_done==Some(c)) {
-                    let new_sequence = sequence_being_considered.extend_by_candidate(c,votes,audit,&neb_cache);
-                    if new_sequence.pi.len()==votes.num_candidates() as usize { // 22 if (|π′| = |C|):
-                        new_sequence.contains_all_candidates(&mut assertions,&mut frontier,&mut lower_bound)?;
-                    } else {
-                        frontier.push(new_sequence) // 31 F ← F ∪ {π ′ }
+                for c in 0..votes.num_candidates() { // for each(c ∈ C \ π):
+                    let c = CandidateIndex(c);
+                    if !(sequence_being_considered.pi.contains(&c)||sequence_being_considered.dive_done==Some(c)) {
+                        let new_sequence = sequence_being_considered.extend_by_candidate(c,votes,audit,&neb_cache,frontier_ordering,tie_break,max_nen_continuing,recorder);
+                        if ruled_out_by_required(required,&new_sequence.pi) { continue; }
+                        if new_sequence.pi.len()==votes.num_candidates() as usize { // 22 if (|π′| = |C|):
+                            new_sequence.contains_all_candidates(&mut assertions,&mut frontier,&mut lower_bound,recorder)?;
+                        } else {
+                            frontier.push(new_sequence) // 31 F ← F ∪ {π ′ }
+                        }
                     }
                 }
             }
+            //println!("frontier now includes {} elements",frontier.len())
+        }
+        // The frontier is exhausted. If the hardest deferred candidate's NEB assertion is harder than the bound, it may be possible to do better by searching it.
+        match neb_prepass.last() {
+            Some((c,assertion)) if assertion.difficulty>lower_bound => {
+                frontier.push(initial_sequence(*c,votes,audit,&neb_cache,frontier_ordering,tie_break,max_nen_continuing,recorder));
+                neb_prepass.pop();
+            }
+            _ => break,
         }
-        //println!("frontier now includes {} elements",frontier.len())
     }
-    let time_to_find_assertions = timeout.time_taken()-time_to_determine_winners;
-    log::debug!("Finished generating {} assertions difficulty {}, now need to trim.",assertions.len(),lower_bound);
-    let warning_trim_timed_out = match crate::tree_showing_what_assertions_pruned_leaves::order_assertions_and_remove_unnecessary(&mut assertions,winner,votes.num_candidates(),trim_algorithm,timeout) {
-        Ok(_) => false,
-        Err(RaireError::TimeoutTrimmingAssertions) => true,
-        Err(e) => {return Err(e);}
-    };
-    let time_to_trim_assertions = timeout.time_taken()-time_to_find_assertions-time_to_determine_winners;
-    log::debug!("Trimmed assertions down to {}.",assertions.len());
-    let margin = assertions.iter().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0));
-    // simple fast consistency check - make sure that the ostensible elimination order is consistent with all the assertions. If so, then the winner is not ruled out, and all is good.
-    for a in &assertions {
-        match a.assertion.ok_elimination_order_suffix(&irv_result.elimination_order) {
-            EffectOfAssertionOnEliminationOrderSuffix::Ok => {}
-            _ => { return Err(RaireError::InternalErrorRuledOutWinner); }
+    // All remaining deferred candidates can be ruled out by their NEB assertions without increasing the difficulty.
+    for (c,assertion) in neb_prepass {
+        recorder.decide(&[c],&assertion,DecisionKind::Accepted);
+        if !assertions.iter().any(|a|a.assertion==assertion.assertion) { assertions.push(assertion); }
+    }
+    Ok((assertions,lower_bound))
+}
+
+/// An audit type where any comparison with a margin of at least `threshold` is considered to have zero difficulty.
+/// Used by [prove_margin_at_least].
+struct MarginThresholdAudit<'a,A:AuditType> {
+    audit : &'a A,
+    threshold : BallotPaperCount,
+}
+
+impl <A:AuditType> AuditType for MarginThresholdAudit<'_,A> {
+    fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
+        if lowest_tally_winner>highest_tally_loser && lowest_tally_winner-highest_tally_loser>=self.threshold { 0.0 } else { self.audit.difficulty(lowest_tally_winner,highest_tally_loser) }
+    }
+}
+
+/// Find assertions proving that `winner` won, where it is sufficient for each assertion to have a margin of at least `threshold`,
+/// rather than finding the least difficult assertions. Once an assertion with at least this margin is found for some
+/// part of the search, it is used without looking for anything better. This is faster, and generally produces fewer
+/// assertions, when such a coarse guarantee suffices. If no assertion with the threshold margin can be found for
+/// some part of the search, the least difficult one is used, as in [raire].
+///
+/// The difficulties of the returned assertions are computed with `audit`.
+pub fn prove_margin_at_least<A:AuditType>(votes:&Votes,audit:&A,winner:CandidateIndex,threshold:BallotPaperCount,timeout:&mut TimeOut) -> Result<Vec<AssertionAndDifficulty>,RaireError> {
+    let threshold_audit = MarginThresholdAudit{audit,threshold};
+    let result = raire(votes,Some(winner),&threshold_audit,TrimAlgorithm::MinimizeTree,timeout)?;
+    Ok(result.assertions.into_iter().map(|a|{
+        let (tally_winner,tally_loser) = a.assertion.tallies(votes);
+        let margin = if tally_winner>tally_loser { tally_winner-tally_loser } else { BallotPaperCount(0) };
+        AssertionAndDifficulty{difficulty:audit.difficulty(tally_winner,tally_loser),margin,assertion:a.assertion,tallies:None}
+    }).collect())
+}
+
+/// Find assertions that rule out each of the given (complete) elimination orders, rather than proving the winner.
+/// This is useful for contesting specific claims about the outcome.
+///
+/// For each forbidden elimination order, the least difficult assertion contradicting any suffix of it is used.
+/// This minimizes the overall (maximum) difficulty. Duplicate assertions are only included once.
+///
+/// If an elimination order cannot be ruled out (e.g. it is the actual outcome), `RaireError::CouldNotRuleOut` is returned.
+/// Each forbidden order must be a permutation of all the candidates. If it contains a candidate that does not exist,
+/// `RaireError::InvalidCandidateNumber` is returned, and if it is otherwise not a permutation (it has the wrong length or
+/// repeats a candidate), `RaireError::InvalidAssertion` is returned, as it could not be an assertion to rule out.
+pub fn prove_orders_impossible<A:AuditType>(votes:&Votes,audit:&A,forbidden:&[EliminationOrder],timeout:&mut TimeOut) -> Result<Vec<AssertionAndDifficulty>,RaireError> {
+    let neb_cache = NotEliminatedBeforeCache::new(votes,audit);
+    let mut assertions : Vec<AssertionAndDifficulty> = vec![];
+    for order in forbidden {
+        if order.iter().any(|c|c.0>=votes.num_candidates()) { return Err(RaireError::InvalidCandidateNumber); }
+        if order.len()!=votes.num_candidates() as usize || order.iter().collect::<HashSet<_>>().len()!=order.len() { return Err(RaireError::InvalidAssertion); }
+        if timeout.quick_check_timeout() { return Err(RaireError::TimeoutFindingAssertions(assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max))); }
+        let best = (0..order.len()).map(|start|find_best_audit(&order[start..],votes,audit,&neb_cache,TieBreak::default(),None,&mut SearchRecorder::default())).min_by(|a,b|a.difficulty.partial_cmp(&b.difficulty).unwrap_or(Ordering::Equal));
+        match best {
+            Some(best) if best.difficulty.is_finite() => {
+                if !assertions.iter().any(|a|a.assertion==best.assertion) { assertions.push(best); }
+            }
+            _ => { return Err(RaireError::CouldNotRuleOut(order.clone())); }
         }
     }
-    Ok(RaireResult{assertions, difficulty: lower_bound, margin, winner,num_candidates:votes.num_candidates(), time_to_determine_winners, time_to_find_assertions, time_to_trim_assertions, warning_trim_timed_out })
+    Ok(assertions)
 }
 
 #[derive(Clone,Copy,Debug,Serialize,Deserialize)]
@@ -330,4 +1481,78 @@ pub enum TrimAlgorithm {
     MinimizeTree,
     /// Expand the tree until all all assertions are resolved or an NEB rules the path out, and remove redundant assertions with a simple heuristic. Minimizes the number of assertions, but may increase the size of the tree to verify.
     MinimizeAssertions,
-}
\ No newline at end of file
+}
+
+#[derive(Clone,Copy,Debug,Serialize,Deserialize)]
+/// A tunable objective for choosing between sets of trimmed assertions, used by
+/// [crate::tree_showing_what_assertions_pruned_leaves::order_assertions_and_remove_unnecessary_by_objective].
+///
+/// The cost of a set of assertions is `count_weight*(number of assertions)+difficulty_weight*(maximum difficulty)`. Lower is better.
+pub struct TrimObjective {
+    pub count_weight : f64,
+    pub difficulty_weight : f64,
+}
+
+impl TrimObjective {
+    /// The cost of a set of assertions. Lower is better.
+    pub fn cost(&self,assertions:&[AssertionAndDifficulty]) -> f64 {
+        let max_difficulty = assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max);
+        self.count_weight*assertions.len() as f64+self.difficulty_weight*max_difficulty
+    }
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+/// The order in which sequences in the frontier are expanded.
+///
+/// The original paper always expands the sequence with the highest difficulty first. It is
+/// sometimes faster to prefer longer sequences, as these are closer to being a complete elimination
+/// order which can raise the lower bound, allowing other sequences to be resolved without expansion.
+///
+/// The order does not affect the difficulty of the result, as any sequence with difficulty
+/// above the lower bound will eventually be expanded. It may change the number of sequences
+/// expanded, and which (equally difficult) assertions are chosen.
+pub enum FrontierOrdering {
+    /// Expand the sequence with the highest difficulty first, as in the original paper.
+    #[default]
+    BestFirst,
+    /// Expand the sequence with the highest difficulty multiplied by `1+(length of sequence)/(number of candidates)` first.
+    LengthWeighted,
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+/// How to choose between two equally difficult assertions that could rule out the same elimination order suffix.
+/// This does not change the difficulty of the result, but does change which assertions are produced, which matters
+/// when comparing with other implementations such as raire-java.
+pub enum TieBreak {
+    /// Prefer a NEB assertion to a NEN assertion, as in the original implementation.
+    #[default]
+    PreferNEB,
+    /// Prefer a NEN assertion to a NEB assertion, and a NEN assertion with fewer continuing candidates to one with more.
+    PreferShorterNEN,
+    /// Prefer the assertion with the lower winner index, then the lower loser index, then a NEB assertion.
+    /// A NEN assertion's loser is also the lowest numbered of the continuing candidates with the equal lowest tally.
+    PreferLowerCandidateIndex,
+}
+
+impl TieBreak {
+    /// Whether assertion `a` should be used in preference to the equally difficult assertion `b`.
+    pub fn prefers(self,a:&Assertion,b:&Assertion) -> bool {
+        let is_nen = |x:&Assertion| matches!(x,Assertion::NEN(_));
+        let nen_length = |x:&Assertion| match x { Assertion::NEN(nen) => nen.continuing.len(), Assertion::NEB(_) => usize::MAX };
+        match self {
+            TieBreak::PreferNEB => !is_nen(a) && is_nen(b),
+            TieBreak::PreferShorterNEN => nen_length(a)<nen_length(b),
+            TieBreak::PreferLowerCandidateIndex => (a.winner().0,a.loser().0,is_nen(a))<(b.winner().0,b.loser().0,is_nen(b)),
+        }
+    }
+}
+
+impl FrontierOrdering {
+    /// The priority of a sequence with the given difficulty and length. Higher is expanded first.
+    fn priority(self,difficulty:AssertionDifficulty,sequence_length:usize,num_candidates:u32) -> f64 {
+        match self {
+            FrontierOrdering::BestFirst => difficulty,
+            FrontierOrdering::LengthWeighted => difficulty*(1.0+sequence_length as f64/num_candidates as f64),
+        }
+    }
+}
@@ -9,6 +9,8 @@
 
 
 use std::ops::Sub;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 use serde::Deserialize;
 use serde::Serialize;
@@ -20,18 +22,109 @@ pub struct TimeOut {
     work_done : u64,
     work_limit : Option<u64>,
     duration_limit : Option<Duration>,
+    /// If present, and set to true (e.g. by another thread), the computation is stopped.
+    cancel : Option<Arc<AtomicBool>>,
+    /// If present, a separate limit on the clock time used by [crate::irv::Votes::run_election].
+    winner_computation_limit : Option<Duration>,
+    /// If present, a separate limit on the clock time used finding assertions.
+    finding_assertions_limit : Option<Duration>,
+    /// If present, a separate limit on the clock time used trimming assertions.
+    trimming_limit : Option<Duration>,
+    /// Which limit, if any, has been hit.
+    cause : TimeoutCause,
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+/// Which limit (if any) caused a [TimeOut] to stop a computation. This distinguishes a slow computer (Time)
+/// from a genuinely hard contest (Work).
+pub enum TimeoutCause {
+    /// No limit was hit.
+    #[default]
+    None,
+    /// The clock time limit was exceeded.
+    Time,
+    /// The work limit was exceeded.
+    Work,
+    /// The computation was cancelled. See [TimeOut::with_cancel_flag].
+    Cancel,
+    /// The separate clock time limit for determining the winner was exceeded. See [TimeOut::with_winner_computation_limit].
+    WinnerComputationTime,
+    /// The separate clock time limit for finding assertions was exceeded. See [TimeOut::with_finding_assertions_limit].
+    FindingAssertionsTime,
+    /// The separate clock time limit for trimming assertions was exceeded. See [TimeOut::with_trimming_limit].
+    TrimmingTime,
+}
+
+impl TimeoutCause {
+    pub fn is_none(&self) -> bool { *self==TimeoutCause::None }
 }
 
 /// In case the clock is expensive to check, only check every UNITS_OF_WORK_PER_CLOCK_CHECK units of work.
 const UNITS_OF_WORK_PER_CLOCK_CHECK : u64 = 100;
 
 impl TimeOut {
-    /// Make a new timeout structure.
+    /// Make a new timeout structure. If both limits are given, whichever is exceeded first causes a timeout.
     pub fn new(work_limit : Option<u64>,duration_limit : Option<Duration>) -> Self {
         let start_time = Instant::now();
-        TimeOut{start_time,work_done:0,work_limit,duration_limit}
+        TimeOut{start_time,work_done:0,work_limit,duration_limit,cancel:None,winner_computation_limit:None,finding_assertions_limit:None,trimming_limit:None,cause:TimeoutCause::None}
+    }
+
+    /// Also time out when `cancel` is set to true, for instance by another thread.
+    pub fn with_cancel_flag(self,cancel:Arc<AtomicBool>) -> Self {
+        TimeOut{cancel:Some(cancel),..self}
+    }
+
+    /// Also limit the clock time spent determining the winner (see [crate::irv::Votes::run_election]) to `limit`,
+    /// in addition to the overall limit. This can dominate on pathological contests with many ties, and a separate
+    /// limit lets the cause of a timeout be identified as [TimeoutCause::WinnerComputationTime].
+    pub fn with_winner_computation_limit(self,limit:Duration) -> Self {
+        TimeOut{winner_computation_limit:Some(limit),..self}
+    }
+
+    /// Also limit the clock time spent finding assertions to `limit`, in addition to the overall limit.
+    /// If exceeded, the cause is [TimeoutCause::FindingAssertionsTime].
+    pub fn with_finding_assertions_limit(self,limit:Duration) -> Self {
+        TimeOut{finding_assertions_limit:Some(limit),..self}
+    }
+
+    /// Also limit the clock time spent trimming assertions to `limit`, in addition to the overall limit, so a slow trim
+    /// can be capped without reducing the time available for finding assertions. If exceeded, the cause is [TimeoutCause::TrimmingTime].
+    pub fn with_trimming_limit(self,limit:Duration) -> Self {
+        TimeOut{trimming_limit:Some(limit),..self}
+    }
+
+    /// Run `f`, which determines the winner, within the winner computation limit, if any, as well as the overall limits.
+    pub fn within_winner_computation_limit<T>(&mut self,f:impl FnOnce(&mut TimeOut)->T) -> T {
+        self.within_stage_limit(self.winner_computation_limit,TimeoutCause::WinnerComputationTime,f)
     }
 
+    /// Run `f`, which finds assertions, within the finding assertions limit, if any, as well as the overall limits.
+    pub fn within_finding_assertions_limit<T>(&mut self,f:impl FnOnce(&mut TimeOut)->T) -> T {
+        self.within_stage_limit(self.finding_assertions_limit,TimeoutCause::FindingAssertionsTime,f)
+    }
+
+    /// Run `f`, which trims assertions, within the trimming limit, if any, as well as the overall limits.
+    pub fn within_trimming_limit<T>(&mut self,f:impl FnOnce(&mut TimeOut)->T) -> T {
+        self.within_stage_limit(self.trimming_limit,TimeoutCause::TrimmingTime,f)
+    }
+
+    /// Run `f` with the clock time limited to `limit` from now, if given, as well as the overall limits.
+    /// If the stage limit is exceeded, the cause is `cause`.
+    fn within_stage_limit<T>(&mut self,limit:Option<Duration>,cause:TimeoutCause,f:impl FnOnce(&mut TimeOut)->T) -> T {
+        let Some(limit) = limit else { return f(self); };
+        let overall_limit = self.duration_limit;
+        let stage_limit = self.clock_time_taken_since_start()+limit;
+        let stage_is_tighter = overall_limit.map(|overall|stage_limit<overall).unwrap_or(true);
+        if stage_is_tighter { self.duration_limit=Some(stage_limit); }
+        let res = f(self);
+        self.duration_limit=overall_limit;
+        if stage_is_tighter && self.cause==TimeoutCause::Time { self.cause=cause; }
+        res
+    }
+
+    /// Which limit, if any, has caused a timeout.
+    pub fn cause(&self) -> TimeoutCause { self.cause }
+
     /// make a dummy timer that will never timeout
     pub fn never() -> Self { Self::new(None,None) }
 
@@ -48,13 +141,17 @@ impl TimeOut {
 
     /// increments work_done by 1, and returns true if a limit is exceeded
     /// * only checks duration every 100 calls.
+    /// * records which limit was exceeded, see [Self::cause].
     pub fn quick_check_timeout(&mut self) -> bool {
         self.work_done+=1;
         if let Some(work_limit) = self.work_limit {
-            if self.work_done>work_limit { return true; }
+            if self.work_done>work_limit { self.cause=TimeoutCause::Work; return true; }
+        }
+        if let Some(cancel) = &self.cancel {
+            if cancel.load(Ordering::Relaxed) { self.cause=TimeoutCause::Cancel; return true; }
         }
         if let Some(duration_limit) = self.duration_limit {
-            if self.work_done%UNITS_OF_WORK_PER_CLOCK_CHECK==0 && self.clock_time_taken_since_start()>duration_limit { return true; }
+            if self.work_done%UNITS_OF_WORK_PER_CLOCK_CHECK==0 && self.clock_time_taken_since_start()>duration_limit { self.cause=TimeoutCause::Time; return true; }
         }
         false
     }
@@ -64,6 +161,8 @@ impl TimeOut {
 /// A measure of the time taken to do something, both in units of work and clock time
 pub struct TimeTaken {
     pub work : u64,
+    /// Zero if not known, for instance if read from canonical JSON (see [crate::RaireSolution::to_canonical_json]).
+    #[serde(default)]
     pub seconds : f64,
 }
 
@@ -82,4 +181,42 @@ impl TimeTaken {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_cause() {
+        let mut never = TimeOut::never();
+        for _ in 0..1000 { assert!(!never.quick_check_timeout()); }
+        assert_eq!(TimeoutCause::None,never.cause());
+        let mut work = TimeOut::new(Some(10),Some(Duration::from_secs(1000)));
+        while !work.quick_check_timeout() {}
+        assert_eq!(TimeoutCause::Work,work.cause());
+        assert_eq!(11,work.get_work_done());
+        let mut time = TimeOut::new(Some(u64::MAX),Some(Duration::from_nanos(1)));
+        std::thread::sleep(Duration::from_millis(1));
+        while !time.quick_check_timeout() {}
+        assert_eq!(TimeoutCause::Time,time.cause());
+    }
+
+    #[test]
+    fn test_cancel_flag() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut cancellable = TimeOut::never().with_cancel_flag(cancel.clone());
+        assert!(!cancellable.quick_check_timeout());
+        cancel.store(true,Ordering::Relaxed);
+        assert!(cancellable.quick_check_timeout());
+        assert_eq!(TimeoutCause::Cancel,cancellable.cause());
+    }
 
+    #[test]
+    fn test_stage_limits() {
+        let mut timeout = TimeOut::new(None,Some(Duration::from_secs(1000))).with_finding_assertions_limit(Duration::from_secs(1000)).with_trimming_limit(Duration::from_nanos(1));
+        assert!(!timeout.within_finding_assertions_limit(|timeout|(0..1000).any(|_|timeout.quick_check_timeout())));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(timeout.within_trimming_limit(|timeout|(0..1000).any(|_|timeout.quick_check_timeout())));
+        assert_eq!(TimeoutCause::TrimmingTime,timeout.cause());
+        assert_eq!(Some(Duration::from_secs(1000)),timeout.duration_limit); // the overall limit is restored.
+    }
+}
@@ -12,7 +12,7 @@
 use std::cmp::Ordering;
 use crate::assertions::{Assertion, AssertionAndDifficulty, EffectOfAssertionOnEliminationOrderSuffix};
 use crate::irv::CandidateIndex;
-use crate::raire_algorithm::TrimAlgorithm;
+use crate::raire_algorithm::{TrimAlgorithm, TrimObjective};
 use crate::RaireError;
 use crate::timeout::TimeOut;
 
@@ -69,6 +69,49 @@ impl TreeNodeShowingWhatAssertionsPrunedIt {
         }
         Ok(TreeNodeShowingWhatAssertionsPrunedIt{candidate_being_eliminated_at_this_node,pruning_assertions,children,valid})
     }
+
+    /// The number of nodes in this tree, including this node.
+    pub fn node_count(&self) -> usize {
+        1+self.children.iter().map(|c|c.node_count()).sum::<usize>()
+    }
+
+    /// The depth of this tree, a tree with no children having depth 1.
+    pub fn depth(&self) -> usize {
+        1+self.children.iter().map(|c|c.depth()).max().unwrap_or(0)
+    }
+
+    /// The number of nodes on the shortest path from this node to a leaf, a tree with no children having 1.
+    pub fn shortest_path_to_leaf(&self) -> usize {
+        1+self.children.iter().map(|c|c.shortest_path_to_leaf()).min().unwrap_or(0)
+    }
+
+    /// Compute statistics about the shape of this tree, useful for understanding why trimming is slow or suboptimal.
+    pub fn statistics(&self) -> TreeStats {
+        let mut stats = TreeStats{nodes:1,leaves:0,multi_assertion_nodes:0,max_depth:1};
+        if self.children.is_empty() { stats.leaves=1; }
+        if self.pruning_assertions.len()>1 { stats.multi_assertion_nodes=1; }
+        for child in &self.children {
+            let child_stats = child.statistics();
+            stats.nodes+=child_stats.nodes;
+            stats.leaves+=child_stats.leaves;
+            stats.multi_assertion_nodes+=child_stats.multi_assertion_nodes;
+            stats.max_depth=stats.max_depth.max(1+child_stats.max_depth);
+        }
+        stats
+    }
+}
+
+/// Statistics about the shape of a [TreeNodeShowingWhatAssertionsPrunedIt].
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct TreeStats {
+    /// The total number of nodes in the tree.
+    pub nodes : usize,
+    /// The number of nodes with no children.
+    pub leaves : usize,
+    /// The number of nodes pruned by more than one assertion. These are the nodes where the trimming heuristic has to choose.
+    pub multi_assertion_nodes : usize,
+    /// The depth of the tree, a tree with no children having depth 1.
+    pub max_depth : usize,
 }
 
 #[derive(Copy, Clone,Debug)]
@@ -107,33 +150,35 @@ impl HowFarToContinueSearchTreeWhenPruningAssertionFound {
 /// the rest of the computation. So it is not enabled.
 const CHECK_WINNER_NOT_ELIMINATED:bool=false;
 
-/// Sort the assertions in a human sensible manner, and then trim them.
+/// The human sensible order in which assertions are presented:
+/// all NEBs before NENs, NENs by the number of continuing candidates,
+/// ties sorted by winner, then loser, then continuing candidates.
+pub fn human_order(a:&Assertion,b:&Assertion) -> Ordering {
+    match (a,b) {
+        (Assertion::NEN(_), Assertion::NEB(_)) => Ordering::Greater,
+        (Assertion::NEB(_), Assertion::NEN(_)) => Ordering::Less,
+        (Assertion::NEN(a), Assertion::NEN(b)) => {
+            a.continuing.len().cmp(&b.continuing.len()).then_with(||a.winner.0.cmp(&b.winner.0).then_with(||a.loser.0.cmp(&b.loser.0)).then_with(||{
+                // compare continuing
+                for i in 0..a.continuing.len() {
+                    let res = a.continuing[i].0.cmp(&b.continuing[i].0);
+                    if res!=Ordering::Equal { return res}
+                }
+                Ordering::Equal
+            }))
+        },
+        (Assertion::NEB(a), Assertion::NEB(b)) => a.winner.0.cmp(&b.winner.0).then_with(||a.loser.0.cmp(&b.loser.0)),
+    }
+}
+
+/// Sort the assertions in a human sensible manner (see [human_order]), and then trim them.
 ///
 /// Note that if a timeout error is produced, the assertions array will be sorted but otherwise unchanged
 /// from the original call.
 ///
 /// The algorithm is described in [../AssertionTrimmingAlgorithm.md]
 pub fn order_assertions_and_remove_unnecessary(assertions:&mut Vec<AssertionAndDifficulty>,winner:CandidateIndex,num_candidates:u32,trim_algorithm:TrimAlgorithm,timeout:&mut TimeOut) -> Result<(),RaireError> {
-    assertions.sort_unstable_by(|a,b|{
-        // sort all NEBs before NENs,
-        // sort NENs by length
-        // ties - sort by winner, then loser, then continuing
-        match (&a.assertion,&b.assertion) {
-            (Assertion::NEN(_), Assertion::NEB(_)) => Ordering::Greater,
-            (Assertion::NEB(_), Assertion::NEN(_)) => Ordering::Less,
-            (Assertion::NEN(a), Assertion::NEN(b)) => {
-                a.continuing.len().cmp(&b.continuing.len()).then_with(||a.winner.0.cmp(&b.winner.0).then_with(||a.loser.0.cmp(&b.loser.0)).then_with(||{
-                    // compare continuing
-                    for i in 0..a.continuing.len() {
-                        let res = a.continuing[i].0.cmp(&b.continuing[i].0);
-                        if res!=Ordering::Equal { return res}
-                    }
-                    Ordering::Equal
-                }))
-            },
-            (Assertion::NEB(a), Assertion::NEB(b)) => a.winner.0.cmp(&b.winner.0).then_with(||a.loser.0.cmp(&b.loser.0)),
-        }
-    });
+    assertions.sort_unstable_by(|a,b|human_order(&a.assertion,&b.assertion));
     if let Some(consider_children_of_eliminated_nodes) = match trim_algorithm {
         TrimAlgorithm::None => None,
         TrimAlgorithm::MinimizeTree => Some(HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately),
@@ -168,6 +213,66 @@ pub fn order_assertions_and_remove_unnecessary(assertions:&mut Vec<AssertionAndD
     Ok(())
 }
 
+/// Like [order_assertions_and_remove_unnecessary], but rather than a specific trim algorithm, choose
+/// the trimmed set of assertions with the lowest cost according to the given objective.
+///
+/// Three trims are tried: `MinimizeTree`, `MinimizeAssertions`, and one minimizing the maximum difficulty
+/// (see [remove_unnecessary_minimizing_max_difficulty]). The result with the lowest [TrimObjective::cost] is used.
+/// In case of a tie, the earlier in that list is preferred, `MinimizeTree` first as it produces a smaller tree for
+/// a human to verify. So a large `count_weight` reproduces `MinimizeAssertions`, and a zero `count_weight` reproduces
+/// `MinimizeTree` unless a lower maximum difficulty is possible.
+///
+/// If a timeout occurs after at least one trim has completed, the best completed trim is used, and the cause
+/// is left in `timeout`. If no trim completed, the timeout error is returned and the assertions array is unchanged.
+pub fn order_assertions_and_remove_unnecessary_by_objective(assertions:&mut Vec<AssertionAndDifficulty>,winner:CandidateIndex,num_candidates:u32,objective:TrimObjective,timeout:&mut TimeOut) -> Result<(),RaireError> {
+    let mut best : Option<(f64,Vec<AssertionAndDifficulty>)> = None;
+    for trim_algorithm in [Some(TrimAlgorithm::MinimizeTree),Some(TrimAlgorithm::MinimizeAssertions),None] {
+        let mut trimmed = assertions.clone();
+        let trimmed_ok = match trim_algorithm {
+            Some(trim_algorithm) => order_assertions_and_remove_unnecessary(&mut trimmed,winner,num_candidates,trim_algorithm,timeout),
+            None => remove_unnecessary_minimizing_max_difficulty(&mut trimmed,winner,num_candidates,timeout),
+        };
+        match trimmed_ok {
+            Err(RaireError::TimeoutTrimmingAssertions) if best.is_some() => break,
+            result => result?,
+        }
+        let cost = objective.cost(&trimmed);
+        if best.as_ref().map(|(best_cost,_)|cost<*best_cost).unwrap_or(true) { best=Some((cost,trimmed)); }
+    }
+    if let Some((_,trimmed)) = best { *assertions=trimmed; }
+    Ok(())
+}
+
+/// Trim the assertions to a set with the lowest possible maximum difficulty. The least difficult assertions that still
+/// rule out every candidate other than the winner are found, and these are then trimmed with [TrimAlgorithm::MinimizeTree].
+/// Like [order_assertions_and_remove_unnecessary], the assertions are sorted in a human sensible manner.
+pub fn remove_unnecessary_minimizing_max_difficulty(assertions:&mut Vec<AssertionAndDifficulty>,winner:CandidateIndex,num_candidates:u32,timeout:&mut TimeOut) -> Result<(),RaireError> {
+    let mut thresholds : Vec<f64> = assertions.iter().map(|a|a.difficulty).collect();
+    thresholds.sort_unstable_by(|a,b|a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    thresholds.dedup();
+    let at_most = |threshold:f64| -> Vec<AssertionAndDifficulty> { assertions.iter().filter(|a|a.difficulty<=threshold).cloned().collect() };
+    if !thresholds.is_empty() { // binary search for the lowest threshold that suffices, assuming all the assertions do.
+        let (mut low,mut high) = (0,thresholds.len()-1);
+        while low<high {
+            let mid = (low+high)/2;
+            if rules_out_all_but_winner(&at_most(thresholds[mid]),winner,num_candidates,timeout)? { high=mid; } else { low=mid+1; }
+        }
+        *assertions = at_most(thresholds[high]);
+    }
+    order_assertions_and_remove_unnecessary(assertions,winner,num_candidates,TrimAlgorithm::MinimizeTree,timeout)
+}
+
+/// Whether the assertions rule out every candidate other than `winner` winning.
+fn rules_out_all_but_winner(assertions:&[AssertionAndDifficulty],winner:CandidateIndex,num_candidates:u32,timeout:&mut TimeOut) -> Result<bool,RaireError> {
+    let all_assertions : Vec<Assertion> = assertions.iter().map(|ad|ad.assertion.clone()).collect();
+    let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
+    for candidate in (0..num_candidates).map(CandidateIndex).filter(|&c|c!=winner) {
+        let tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&all_assertion_indices,&all_assertions,num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,timeout)?;
+        if tree.valid { return Ok(false); }
+    }
+    Ok(true)
+}
+
 /// A pretty simple method of computing which assertions are used which may not always
 /// be optimal, but is fast, and, in practice, has turned out to be optimal for every case
 /// I tried it on.
@@ -345,7 +450,7 @@ mod tests {
     use crate::assertions::{Assertion, NotEliminatedBefore, NotEliminatedNext};
     use crate::irv::CandidateIndex;
     use crate::timeout::TimeOut;
-    use crate::tree_showing_what_assertions_pruned_leaves::{HowFarToContinueSearchTreeWhenPruningAssertionFound, TreeNodeShowingWhatAssertionsPrunedIt};
+    use crate::tree_showing_what_assertions_pruned_leaves::{HowFarToContinueSearchTreeWhenPruningAssertionFound, TreeNodeShowingWhatAssertionsPrunedIt, TreeStats};
 
     /// Get the assertions listed in "A guide to RAIRE".
     fn raire_guide_assertions() -> Vec<Assertion> {
@@ -395,4 +500,24 @@ mod tests {
         assert_eq!(0,tree3.children[2].children[1].pruning_assertions.len());
         assert_eq!(vec![0],tree3.children[2].children[1].children[0].pruning_assertions);
     }
+
+    #[test]
+    fn test_statistics() {
+        let all_assertions = raire_guide_assertions();
+        let relevant_assertions : Vec<usize> = (0..all_assertions.len()).collect();
+        let mut timeout = TimeOut::never();
+        let tree0 = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],CandidateIndex(0),&relevant_assertions,&all_assertions,4,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut timeout).unwrap();
+        let tree3 = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],CandidateIndex(3),&relevant_assertions,&all_assertions,4,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut timeout).unwrap();
+        assert_eq!(TreeStats{nodes:6,leaves:4,multi_assertion_nodes:0,max_depth:3},tree0.statistics());
+        assert_eq!(TreeStats{nodes:7,leaves:4,multi_assertion_nodes:0,max_depth:4},tree3.statistics());
+        assert_eq!(tree3.node_count(),tree3.statistics().nodes);
+        assert_eq!(tree3.depth(),tree3.statistics().max_depth);
+        // add another assertion that also prunes the first child of tree0.
+        let mut all_assertions = all_assertions;
+        all_assertions.push(Assertion::NEB(NotEliminatedBefore{winner:CandidateIndex(3),loser:CandidateIndex(1)}));
+        let relevant_assertions : Vec<usize> = (0..all_assertions.len()).collect();
+        let tree0 = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],CandidateIndex(0),&relevant_assertions,&all_assertions,4,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut timeout).unwrap();
+        assert_eq!(vec![4,6],tree0.children[0].pruning_assertions);
+        assert_eq!(1,tree0.statistics().multi_assertion_nodes);
+    }
 }
@@ -12,11 +12,16 @@
 
 
 
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 use serde_json::json;
-use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
-use raire::irv::{BallotPaperCount, CandidateIndex, Vote};
-use raire::raire_algorithm::TrimAlgorithm;
-use raire::{RaireError, RaireProblem};
+use raire::assertions::{Assertion, AssertionTallies, NotEliminatedBefore, NotEliminatedBeforeCache, NotEliminatedNext};
+use raire::audit_type::{Audit, AuditType, BallotComparisonMACRO, BallotPollingBRAVO, BallotComparisonOneOnDilutedMargin, BallotPollingOneOnDilutedMarginSquared};
+use raire::irv::{BallotPaperCount, CandidateIndex, DifficultyClass, Vote, Votes};
+use raire::timeout::{TimeOut, TimeoutCause};
+use raire::raire_algorithm::{raire, raire_with_options, raire_with_partial, raire_with_checkpoints, raire_resume, prove_margin_at_least, FrontierOrdering, RaireOptions, RaireResult, SearchCheckpoint, TieBreak, TrimAlgorithm};
+use raire::{RaireSolution, SOLUTION_FORMAT_VERSION, RaireBatchProblem, RaireBatchSummary, provisional_solve, provisional_solve_with_options, solve_under_interpretations, tie_break_order_solve, worst_case_tied_solve, RaireError, RaireProblem};
 
 #[test]
 /// Test 0 candidates... should produce RaireError::InvalidCandidateNumber
@@ -26,12 +31,9 @@ fn test_zero_candidates() {
             "candidates" : ["Alice","Bob","Chuan","Diego"]
         }),
         num_candidates : 0,
-        votes : vec![],
-        winner : None,
-        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin { total_auditable_ballots : BallotPaperCount(0) }),
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(0))),
         trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
-        difficulty_estimate: None,
-        time_limit_seconds: None,
+        ..Default::default()
     };
     let solution = problem.solve();
     match solution.solution {
@@ -49,12 +51,9 @@ fn test_one_candidates() {
             "candidates" : ["Alice","Bob","Chuan","Diego"]
         }),
         num_candidates : 1,
-        votes : vec![],
-        winner : None,
-        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin { total_auditable_ballots : BallotPaperCount(0) }),
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(0))),
         trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
-        difficulty_estimate: None,
-        time_limit_seconds: None,
+        ..Default::default()
     };
     let solution = problem.solve().solution.unwrap();
     assert_eq!(CandidateIndex(0),solution.winner);
@@ -74,10 +73,10 @@ fn test_efficient_who_wins() {
         num_candidates : 101,
         votes : vec![Vote{ n: BallotPaperCount(1000), prefs: vec![CandidateIndex(0)] }],
         winner : Some(CandidateIndex(0)),
-        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin { total_auditable_ballots : BallotPaperCount(1100) }),
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(1100))),
         trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
-        difficulty_estimate: None,
         time_limit_seconds: Some(10.0), // Even on a very slow computer it shouldn't take a second to run. It takes 8ms on my four year old PC.
+        ..Default::default()
     };
     for i in 1..=100 {
         problem.votes.push(Vote{ n: BallotPaperCount(1), prefs: vec![CandidateIndex(i)] })
@@ -87,3 +86,793 @@ fn test_efficient_who_wins() {
 }
 
 
+
+#[test]
+/// Test the NEB pre-pass on an example with 2 major + 100 minor candidates, similar to test_efficient_who_wins
+/// but without the winner having a majority of first preferences.
+/// The result should have the same difficulty, but far fewer sequences should need to be expanded.
+fn test_neb_prepass_reduces_work() {
+    let mut problem = RaireProblem {
+        metadata : json!({}),
+        num_candidates : 102,
+        votes : vec![Vote{ n: BallotPaperCount(1000), prefs: vec![CandidateIndex(0)] },Vote{ n: BallotPaperCount(900), prefs: vec![CandidateIndex(1)] }],
+        winner : Some(CandidateIndex(0)),
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(2000))),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
+        time_limit_seconds: Some(10.0),
+        use_neb_prepass: Some(false),
+        ..Default::default()
+    };
+    for i in 2..=101 {
+        problem.votes.push(Vote{ n: BallotPaperCount(1), prefs: vec![CandidateIndex(i)] })
+    }
+    let without_prepass = problem.clone().solve().solution.unwrap();
+    problem.use_neb_prepass=Some(true);
+    let with_prepass = problem.solve().solution.unwrap();
+    assert_eq!(without_prepass.winner,with_prepass.winner);
+    assert_eq!(without_prepass.difficulty,with_prepass.difficulty);
+    assert_eq!(without_prepass.assertions,with_prepass.assertions);
+    assert!(with_prepass.time_to_find_assertions.work*10<without_prepass.time_to_find_assertions.work);
+    with_prepass.verify_result_does_prove_winner().unwrap();
+}
+
+#[test]
+/// Test a contest where the winner has an absolute majority of first preferences, and no preferences
+/// flow to the winner. NEB assertions against every other candidate are optimal, so no search should be needed.
+fn test_majority_winner() {
+    let mut problem = RaireProblem {
+        metadata : json!({}),
+        num_candidates : 4,
+        votes : vec![
+            Vote{ n: BallotPaperCount(600), prefs: vec![CandidateIndex(0)] },
+            Vote{ n: BallotPaperCount(250), prefs: vec![CandidateIndex(1)] },
+            Vote{ n: BallotPaperCount(100), prefs: vec![CandidateIndex(2),CandidateIndex(1)] },
+            Vote{ n: BallotPaperCount(50), prefs: vec![CandidateIndex(3),CandidateIndex(1)] },
+        ],
+        winner : Some(CandidateIndex(0)),
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(1000))),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
+        use_neb_prepass: Some(false),
+        ..Default::default()
+    };
+    let solution = problem.clone().solve().solution.unwrap();
+    assert_eq!(0,solution.time_to_find_assertions.work);
+    assert_eq!(3,solution.assertions.len());
+    assert!(solution.assertions.iter().all(|a|matches!(a.assertion,Assertion::NEB(_))));
+    assert!((solution.difficulty-5.0).abs()<1e-9); // 600 vs 400 after 2 and 3 are eliminated.
+    solution.verify_result_does_prove_winner().unwrap();
+    // if preferences flow to the winner, the NEB assertions may not be optimal, so a search is needed.
+    problem.votes[3].prefs=vec![CandidateIndex(3),CandidateIndex(0)];
+    let solution = problem.solve().solution.unwrap();
+    assert!(solution.time_to_find_assertions.work>0);
+    solution.verify_result_does_prove_winner().unwrap();
+}
+
+/// Make a two candidate contest where candidate 0 gets `winner_votes` and candidate 1 gets `loser_votes`.
+fn two_candidate_contest(winner_votes:usize,loser_votes:usize) -> RaireProblem {
+    RaireProblem {
+        metadata : json!({}),
+        num_candidates : 2,
+        votes : vec![Vote{ n: BallotPaperCount(winner_votes), prefs: vec![CandidateIndex(0)] },Vote{ n: BallotPaperCount(loser_votes), prefs: vec![CandidateIndex(1)] }],
+        winner : Some(CandidateIndex(0)),
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(winner_votes+loser_votes))),
+        ..Default::default()
+    }
+}
+
+#[test]
+/// A landslide should be easier to audit than a near tie.
+fn test_auditability_score() {
+    let landslide = two_candidate_contest(9000,1000).solve().solution.unwrap();
+    let near_tie = two_candidate_contest(5001,4999).solve().solution.unwrap();
+    assert!(landslide.auditability_score()>near_tie.auditability_score());
+    assert!((landslide.auditability_score()-80.0).abs()<0.001); // diluted margin 80%
+    assert!((near_tie.auditability_score()-0.02).abs()<0.001); // diluted margin 0.02%
+    let mut undefined = near_tie;
+    undefined.difficulty = f64::NAN;
+    assert_eq!(0.0,undefined.auditability_score()); // an undefined difficulty is not reported as easy.
+}
+
+#[test]
+/// Test whether an audit can be done within a given budget of ballots to sample.
+fn test_feasible_within() {
+    let mut problem = two_candidate_contest(6000,4000);
+    let audit = Audit::MACRO(BallotComparisonMACRO{ confidence: 0.05, error_inflation_factor: 1.1, total_auditable_ballots: BallotPaperCount(10000) });
+    problem.audit = audit.clone();
+    let solution = problem.solve().solution.unwrap();
+    assert!((solution.difficulty-32.95).abs()<0.01); // -ln(0.05)*2*1.1*10000/2000
+    assert!(solution.feasible_within(&audit,BallotPaperCount(100)));
+    assert!(!solution.feasible_within(&audit,BallotPaperCount(30)));
+    // sample sizes are not available for OneOnMargin, so conservatively not feasible.
+    let solution = two_candidate_contest(6000,4000).solve().solution.unwrap();
+    assert!(!solution.feasible_within(&Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(10000))),BallotPaperCount(100000)));
+}
+
+#[test]
+/// Test solving with two interpretations of the ballots that give the same winner with different margins.
+fn test_solve_under_interpretations() {
+    let base = two_candidate_contest(6000,4000);
+    let strict = base.votes.clone();
+    let lenient = vec![Vote{ n: BallotPaperCount(6500), prefs: vec![CandidateIndex(0)] },Vote{ n: BallotPaperCount(3500), prefs: vec![CandidateIndex(1)] }];
+    let solutions = solve_under_interpretations(&base,&[strict,lenient]);
+    assert_eq!(2,solutions.len());
+    let strict = solutions[0].solution.as_ref().unwrap();
+    let lenient = solutions[1].solution.as_ref().unwrap();
+    assert_eq!(strict.winner,lenient.winner);
+    assert!((strict.difficulty-5.0).abs()<1e-9);
+    assert!((lenient.difficulty-10.0/3.0).abs()<1e-9);
+}
+
+#[test]
+/// Test that a margin below the minimum auditable margin is infinitely difficult, so the contest can't be audited.
+fn test_min_auditable_margin() {
+    let audit = BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(10000)).with_min_auditable_margin(BallotPaperCount(100));
+    assert_eq!(f64::INFINITY,audit.difficulty(BallotPaperCount(5040),BallotPaperCount(4960)));
+    assert_eq!(100.0,audit.difficulty(BallotPaperCount(5050),BallotPaperCount(4950)));
+    let audit = BallotPollingOneOnDilutedMarginSquared::new(BallotPaperCount(10000)).with_min_auditable_margin(BallotPaperCount(100));
+    assert_eq!(f64::INFINITY,audit.difficulty(BallotPaperCount(5040),BallotPaperCount(4960)));
+    assert_eq!(10000.0,audit.difficulty(BallotPaperCount(5050),BallotPaperCount(4950)));
+    let mut problem = two_candidate_contest(5040,4960);
+    problem.audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(10000)).with_min_auditable_margin(BallotPaperCount(100)));
+    let solution = problem.solve().solution;
+    assert!(matches!(solution,Err(RaireError::CouldNotRuleOut(_))));
+}
+
+#[test]
+/// Test that all tied candidates are returned as possibly first eliminated.
+fn test_first_eliminated_candidates_with_ties() {
+    let votes = Votes::new(vec![
+        Vote{ n: BallotPaperCount(10), prefs: vec![CandidateIndex(0)] },
+        Vote{ n: BallotPaperCount(3), prefs: vec![CandidateIndex(1),CandidateIndex(0)] },
+        Vote{ n: BallotPaperCount(3), prefs: vec![CandidateIndex(3)] },
+    ],4).unwrap();
+    // candidate 2 has no votes at all, so is eliminated first.
+    assert_eq!(vec![CandidateIndex(2)],votes.first_eliminated_candidates(&mut TimeOut::never()).unwrap());
+    let votes = Votes::new(vec![
+        Vote{ n: BallotPaperCount(10), prefs: vec![CandidateIndex(0)] },
+        Vote{ n: BallotPaperCount(3), prefs: vec![CandidateIndex(1),CandidateIndex(0)] },
+        Vote{ n: BallotPaperCount(3), prefs: vec![CandidateIndex(2)] },
+    ],3).unwrap();
+    assert_eq!(vec![CandidateIndex(1),CandidateIndex(2)],votes.first_eliminated_candidates(&mut TimeOut::never()).unwrap());
+}
+
+#[test]
+/// Test that only needing to prove a margin well below the actual margin produces fewer assertions than a full solve.
+fn test_prove_margin_at_least() {
+    let votes = Votes::new(vec![
+        Vote{ n: BallotPaperCount(1900), prefs: vec![CandidateIndex(1),CandidateIndex(2),CandidateIndex(0)] },
+        Vote{ n: BallotPaperCount(1800), prefs: vec![CandidateIndex(2),CandidateIndex(0)] },
+        Vote{ n: BallotPaperCount(900), prefs: vec![CandidateIndex(1),CandidateIndex(0),CandidateIndex(2)] },
+        Vote{ n: BallotPaperCount(700), prefs: vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(2)] },
+    ],3).unwrap();
+    let audit = BallotComparisonOneOnDilutedMargin::new(votes.total_votes());
+    let full = raire(&votes,None,&audit,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    assert_eq!(CandidateIndex(1),full.winner);
+    assert_eq!(4,full.assertions.len());
+    let assertions = prove_margin_at_least(&votes,&audit,CandidateIndex(1),BallotPaperCount(500),&mut TimeOut::never()).unwrap();
+    assert_eq!(2,assertions.len());
+    assert!(assertions.iter().all(|a|a.margin>=BallotPaperCount(500)));
+    let coarse = RaireResult{assertions,..full.clone()};
+    coarse.verify_result_does_prove_winner().unwrap();
+    // a threshold that cannot be met gives the same difficulty as a full solve.
+    let assertions = prove_margin_at_least(&votes,&audit,CandidateIndex(1),BallotPaperCount(100000),&mut TimeOut::never()).unwrap();
+    assert_eq!(full.difficulty,assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max));
+}
+
+#[test]
+/// Test that a provisional result is marked as such, and that more ballots can change the provisional winner.
+fn test_provisional_solve() {
+    let mut ballots = vec![
+        Vote{ n: BallotPaperCount(600), prefs: vec![CandidateIndex(0),CandidateIndex(2)] },
+        Vote{ n: BallotPaperCount(400), prefs: vec![CandidateIndex(1)] },
+        Vote{ n: BallotPaperCount(100), prefs: vec![CandidateIndex(2),CandidateIndex(1)] },
+    ];
+    let audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(2000)));
+    let votes = Votes::new(ballots.clone(),3).unwrap();
+    let result = provisional_solve(&votes,&audit,&mut TimeOut::never()).solution.unwrap();
+    assert!(result.warning_provisional);
+    assert_eq!(CandidateIndex(0),result.winner);
+    assert!(serde_json::to_value(&result).unwrap().get("warning_provisional").is_some());
+    // Required assertions are included, and must hold for the partial count.
+    let with_required = |required_assertions:Vec<Assertion>|provisional_solve_with_options(&votes,&audit,&RaireOptions{required_assertions,..Default::default()},&mut TimeOut::never()).solution;
+    let zero_beats_one = Assertion::NEB(NotEliminatedBefore{winner:CandidateIndex(0),loser:CandidateIndex(1)});
+    let result = with_required(vec![zero_beats_one.clone()]).unwrap();
+    assert!(result.assertions.iter().any(|a|a.assertion==zero_beats_one));
+    let one_beats_zero = Assertion::NEB(NotEliminatedBefore{winner:CandidateIndex(1),loser:CandidateIndex(0)});
+    assert!(matches!(with_required(vec![one_beats_zero]),Err(RaireError::AssertionDoesNotHold(0))));
+    // Later counted ballots favour candidate 1.
+    ballots.push(Vote{ n: BallotPaperCount(900), prefs: vec![CandidateIndex(1)] });
+    let votes = Votes::new(ballots,3).unwrap();
+    let result = provisional_solve(&votes,&audit,&mut TimeOut::never()).solution.unwrap();
+    assert!(result.warning_provisional);
+    assert_eq!(CandidateIndex(1),result.winner);
+}
+
+#[test]
+/// Test the tally conservation check catches ballots lost or changed by a buggy importer.
+fn test_verify_tally_conservation() {
+    let ballots = vec![
+        Vote{ n: BallotPaperCount(10), prefs: vec![CandidateIndex(0),CandidateIndex(1)] },
+        Vote{ n: BallotPaperCount(7), prefs: vec![CandidateIndex(1)] },
+        Vote{ n: BallotPaperCount(3), prefs: vec![] },
+    ];
+    let votes = Votes::new(ballots.clone(),2).unwrap();
+    votes.verify_tally_conservation().unwrap();
+    // An importer that added a ballot with an invalid candidate directly, bypassing the checks in Votes::new.
+    let mut corrupted = Votes::new(ballots.clone(),2).unwrap();
+    corrupted.votes.push(Vote{ n: BallotPaperCount(5), prefs: vec![CandidateIndex(2)] });
+    assert!(matches!(corrupted.verify_tally_conservation(),Err(RaireError::TallyNotConserved(BallotPaperCount(17),BallotPaperCount(3),BallotPaperCount(25)))));
+    // An importer that changed ballots after the first preference tallies were computed.
+    let mut corrupted = Votes::new(ballots,2).unwrap();
+    corrupted.votes[1].prefs = vec![CandidateIndex(0)];
+    assert!(matches!(corrupted.verify_tally_conservation(),Err(RaireError::FirstPreferenceTallyChanged(CandidateIndex(0),BallotPaperCount(17),BallotPaperCount(10)))));
+}
+
+#[test]
+/// Test the worst case over tied winners. Alice and Bob tie for elimination. If Alice is eliminated, her votes go to Bob,
+/// who beats Chuan by 8 votes. If Bob is eliminated, his votes exhaust, and Chuan beats Alice by 2 votes.
+fn test_worst_case_tied_solve() {
+    let votes = Votes::new(vec![
+        Vote{n:BallotPaperCount(10),prefs:vec![CandidateIndex(0),CandidateIndex(1)]},
+        Vote{n:BallotPaperCount(10),prefs:vec![CandidateIndex(1)]},
+        Vote{n:BallotPaperCount(12),prefs:vec![CandidateIndex(2)]},
+    ],3).unwrap();
+    let audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(32)));
+    match raire(&votes,None,&audit,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()) {
+        Err(RaireError::TiedWinners(mut tied)) => {
+            tied.sort_unstable_by_key(|c|c.0);
+            assert_eq!(vec![CandidateIndex(1),CandidateIndex(2)],tied);
+        }
+        _ => panic!("Expecting a tie"),
+    }
+    let worst = worst_case_tied_solve(&votes,&audit,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let difficulty_if_won_by = |winner:CandidateIndex,tie_break_order:&[CandidateIndex]| {
+        tie_break_order_solve(&votes,winner,&audit,tie_break_order,TrimAlgorithm::MinimizeTree,&RaireOptions::default(),&mut TimeOut::never()).unwrap().difficulty
+    };
+    let if_bob = difficulty_if_won_by(CandidateIndex(1),&[CandidateIndex(0),CandidateIndex(2),CandidateIndex(1)]);
+    let if_chuan = difficulty_if_won_by(CandidateIndex(2),&[CandidateIndex(1),CandidateIndex(0),CandidateIndex(2)]);
+    assert_eq!(4.0,if_bob);
+    assert_eq!(16.0,if_chuan);
+    assert_eq!(CandidateIndex(2),worst.winner);
+    assert_eq!(if_chuan,worst.difficulty);
+    assert!(worst.conditional_on_tie_break);
+    // With no tie, it is the same as raire.
+    let problem = two_candidate_contest(60,40);
+    let votes = Votes::new(problem.votes.clone(),2).unwrap();
+    let normal = raire(&votes,None,&problem.audit,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    assert_eq!(normal.difficulty,worst_case_tied_solve(&votes,&problem.audit,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap().difficulty);
+}
+
+#[test]
+/// Test the heuristic detection of multi-winner STV data.
+fn test_looks_like_multiwinner() {
+    let num_candidates = 24;
+    let ticket = |first:u32| -> Vec<CandidateIndex> { (0..num_candidates).map(|i|CandidateIndex((first+i)%num_candidates)).collect() };
+    // Typical STV: most ballots follow party tickets ranking every candidate.
+    let mut stv : Vec<Vote> = (0..3).map(|party|Vote{n:BallotPaperCount(300),prefs:ticket(party*8)}).collect();
+    // Typical IRV with the same number of candidates: short, varied rankings.
+    let mut irv : Vec<Vote> = vec![];
+    for first in 0..num_candidates {
+        let short = |len:usize| Vote{n:BallotPaperCount(5),prefs:ticket(first)[..len].to_vec()};
+        stv.push(short(2));
+        irv.push(short(1));
+        irv.push(short(3));
+    }
+    assert!(Votes::new(stv,num_candidates as usize).unwrap().looks_like_multiwinner());
+    assert!(!Votes::new(irv,num_candidates as usize).unwrap().looks_like_multiwinner());
+    // A small contest is never considered multi-winner, even if everyone follows the same complete ranking.
+    let small = Votes::new(vec![Vote{n:BallotPaperCount(100),prefs:vec![CandidateIndex(1),CandidateIndex(0),CandidateIndex(2)]}],3).unwrap();
+    assert!(!small.looks_like_multiwinner());
+}
+
+#[test]
+/// Test an example where the Coombs method and IRV produce different winners.
+/// IRV eliminates Bob, and Alice wins. Coombs eliminates Chuan, ranked last by 65%, and Bob wins.
+fn test_coombs() {
+    let (a,b,c) = (CandidateIndex(0),CandidateIndex(1),CandidateIndex(2));
+    let votes = Votes::new(vec![
+        Vote{n:BallotPaperCount(35),prefs:vec![c,b,a]},
+        Vote{n:BallotPaperCount(33),prefs:vec![a,b,c]},
+        Vote{n:BallotPaperCount(32),prefs:vec![b,a,c]},
+    ],3).unwrap();
+    assert_eq!(vec![BallotPaperCount(35),BallotPaperCount(0),BallotPaperCount(65)],votes.restricted_last_place_tallies(&[a,b,c]));
+    let irv = votes.run_election(&mut TimeOut::never()).unwrap();
+    assert_eq!(vec![a],irv.possible_winners);
+    let coombs = votes.run_coombs(&mut TimeOut::never()).unwrap();
+    assert_eq!(vec![b],coombs.possible_winners);
+    assert_eq!(vec![c,a,b],coombs.elimination_order);
+}
+
+/// The votes in the example contest in the "Guide to RAIRE", in JSON. The candidates are Alice, Bob, Chuan and Diego, and Chuan wins.
+fn guide_votes_json() -> serde_json::Value {
+    json!([ { "n" : 5000, "prefs" : [2,1,0] }, { "n" : 1000, "prefs" : [1,2,3] }, { "n" : 1500, "prefs" : [3,0] }, { "n" : 4000, "prefs" : [0,3] }, { "n" : 2000, "prefs" : [3] } ])
+}
+
+/// The votes in the example contest in the "Guide to RAIRE", see [guide_votes_json].
+fn guide_votes() -> Votes {
+    Votes::new(serde_json::from_value(guide_votes_json()).unwrap(),4).unwrap()
+}
+
+/// The example contest in the "Guide to RAIRE" with the audit used there, and any other `fields` (as a JSON object) added.
+fn guide_problem(fields:serde_json::Value) -> RaireProblem {
+    let mut problem = json!({ "num_candidates" : 4, "votes" : guide_votes_json(), "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 13500 } });
+    problem.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+    serde_json::from_value(problem).unwrap()
+}
+
+#[test]
+/// Check that a work limit causes a timeout error, and that the cause of the timeout is reported.
+fn test_work_limit() {
+    let mut problem = RaireProblem {
+        trim_algorithm: Some(TrimAlgorithm::MinimizeTree),
+        time_limit_seconds: Some(1000.0),
+        work_limit: Some(2),
+        ..guide_problem(json!({ "metadata" : { "candidates" : ["Alice","Bob","Chuan","Diego"] } }))
+    };
+    let solution = problem.clone().solve();
+    assert!(solution.solution.is_err());
+    assert_eq!(TimeoutCause::Work,solution.timeout_cause);
+    problem.work_limit=Some(0);
+    assert!(matches!(problem.clone().solve().solution,Err(RaireError::InvalidTimeout)));
+    problem.work_limit=None;
+    let solution = problem.solve();
+    assert!(solution.solution.is_ok());
+    assert_eq!(TimeoutCause::None,solution.timeout_cause);
+}
+
+#[test]
+/// Test that a solve can be cancelled by setting a flag, independently of any time limit.
+fn test_solve_cancellable() {
+    let problem = RaireProblem {
+        trim_algorithm: Some(TrimAlgorithm::MinimizeTree),
+        ..guide_problem(json!({ "metadata" : { "candidates" : ["Alice","Bob","Chuan","Diego"] } }))
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+    let solution = problem.clone().solve_cancellable(cancel.clone());
+    assert!(solution.solution.is_ok());
+    assert_eq!(TimeoutCause::None,solution.timeout_cause);
+    cancel.store(true,std::sync::atomic::Ordering::Relaxed);
+    let solution = problem.solve_cancellable(cancel);
+    assert!(solution.solution.is_err());
+    assert_eq!(TimeoutCause::Cancel,solution.timeout_cause);
+}
+
+#[test]
+/// Test giving the winner by name instead of by index.
+fn test_winner_by_name() {
+    let problem = |candidates:serde_json::Value,winner:serde_json::Value| guide_problem(json!({ "metadata" : { "candidates" : candidates }, "winner" : winner }));
+    let names = json!(["Alice","Bob","Chuan","Diego"]);
+    assert_eq!(Some(CandidateIndex(2)),problem(names.clone(),json!("Chuan")).winner);
+    assert_eq!(Some(CandidateIndex(2)),problem(names.clone(),json!(2)).winner);
+    assert_eq!(CandidateIndex(2),problem(names.clone(),json!("Chuan")).solve().solution.unwrap().winner);
+    assert!(matches!(problem(names.clone(),json!("Alice")).solve().solution,Err(RaireError::WrongWinner(_))));
+    // a name is written back as an index.
+    assert_eq!(json!(2),serde_json::to_value(problem(names.clone(),json!("Chuan"))).unwrap()["winner"]);
+    let error = |candidates:serde_json::Value| serde_json::from_value::<RaireProblem>(json!({ "metadata" : { "candidates" : candidates }, "winner" : "Chuan", "num_candidates" : 4, "votes" : guide_votes_json(), "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 13500 } })).unwrap_err().to_string();
+    assert_eq!(RaireError::UnknownCandidateName("Chuan".to_string()).to_string(),error(json!(["Alice","Bob"])));
+    assert_eq!(RaireError::AmbiguousCandidateName("Chuan".to_string()).to_string(),error(json!(["Alice","Bob","Chuan","Chuan"])));
+}
+
+#[test]
+/// Test solving several contests with shared settings.
+fn test_batch_problem() {
+    let batch : RaireBatchProblem = serde_json::from_value(json!({
+        "shared" : { "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 13500 }, "trim_algorithm" : "MinimizeTree" },
+        "contests" : [
+            { "metadata" : { "contest" : "Guide" }, "num_candidates" : 4, "winner" : 2, "votes" : guide_votes_json() },
+            { "metadata" : { "contest" : "Tied" }, "num_candidates" : 2, "votes" : [ { "n" : 10, "prefs" : [0] }, { "n" : 10, "prefs" : [1] } ] },
+            { "metadata" : { "contest" : "Landslide" }, "num_candidates" : 2, "votes" : [ { "n" : 100, "prefs" : [0] }, { "n" : 10, "prefs" : [1] } ], "audit" : { "total_auditable_ballots" : 110 } },
+            { "metadata" : { "contest" : "Bad" }, "num_candidates" : "two", "votes" : [] }
+        ]
+    })).unwrap();
+    let solved = batch.solve_all();
+    assert_eq!(4,solved.solutions.len());
+    assert_eq!(27.0,solved.solutions[0].solution.as_ref().unwrap().difficulty);
+    assert!(matches!(solved.solutions[1].solution,Err(RaireError::TiedWinners(_))));
+    assert_eq!(110.0/90.0,solved.solutions[2].solution.as_ref().unwrap().difficulty);
+    assert!(matches!(solved.solutions[3].solution,Err(RaireError::InvalidContest(_))));
+    assert_eq!(json!({ "contest" : "Bad" }),solved.solutions[3].metadata);
+    assert_eq!(RaireBatchSummary{solved:2,failed:2,total_assertions:solved.solutions[0].solution.as_ref().unwrap().assertions.len()+1,hardest_contest:Some(0),max_difficulty:Some(27.0)},solved.summary);
+}
+
+#[test]
+/// Test that solutions record where they came from, and that solutions without this can still be read.
+fn test_provenance() {
+    let problem = |n:u64| -> RaireProblem { serde_json::from_value(json!({
+        "num_candidates" : 2,
+        "votes" : [ { "n" : n, "prefs" : [0] }, { "n" : 10, "prefs" : [1] } ],
+        "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 110 },
+        "include_provenance" : true
+    })).unwrap() };
+    // Provenance is only included if requested.
+    assert!(RaireProblem{include_provenance:None,..problem(100)}.solve().provenance.is_none());
+    let solution = problem(100).solve();
+    let provenance = solution.provenance.clone().unwrap();
+    assert_eq!(SOLUTION_FORMAT_VERSION,provenance.format_version);
+    assert_eq!(env!("CARGO_PKG_VERSION"),provenance.solver_version);
+    assert!(provenance.timestamp>0);
+    #[cfg(feature="sha2")]
+    {
+        assert_eq!(Some(problem(100).sha256()),provenance.problem_sha256);
+        assert_eq!(64,provenance.problem_sha256.as_ref().unwrap().len());
+        assert_ne!(Some(problem(99).sha256()),provenance.problem_sha256);
+    }
+    #[cfg(not(feature="sha2"))]
+    assert_eq!(None,provenance.problem_sha256);
+    let json = serde_json::to_value(&solution).unwrap();
+    let reread : RaireSolution = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(Some(provenance),reread.provenance);
+    let mut legacy = json;
+    legacy.as_object_mut().unwrap().remove("provenance");
+    let legacy : RaireSolution = serde_json::from_value(legacy).unwrap();
+    assert!(legacy.provenance.is_none());
+}
+
+#[test]
+/// Test that the canonical JSON of a solution is the same for different runs, and does not depend on the order of the assertions.
+fn test_canonical_json() {
+    let problem = guide_problem(json!({ "metadata" : { "candidates" : ["Alice","Bob","Chuan","Diego"], "contest" : "Guide" }, "include_provenance" : true }));
+    let first = problem.clone().solve();
+    let mut second = problem.solve();
+    if let Ok(result) = &mut second.solution { result.assertions.reverse(); }
+    let canonical = first.to_canonical_json();
+    assert_eq!(canonical,second.to_canonical_json());
+    assert!(!canonical.contains(' ') && !canonical.contains('\n'));
+    assert!(!canonical.contains("seconds") && !canonical.contains("timestamp"));
+    assert!(canonical.starts_with(r#"{"metadata":{"candidates":["Alice","Bob","Chuan","Diego"],"contest":"Guide"},"provenance":{"format_version":1,"#));
+    let reread : RaireSolution = serde_json::from_str(&canonical).unwrap();
+    let assertions = reread.solution.unwrap().assertions;
+    assert!(assertions[0].assertion.is_neb());
+    assert!(!assertions[assertions.len()-1].assertion.is_neb());
+}
+
+#[test]
+/// Test that the tallies compared by each assertion are included in the output if requested.
+fn test_include_tallies() {
+    let mut problem = guide_problem(json!({}));
+    let without = problem.clone().solve().solution.unwrap();
+    assert!(without.assertions.iter().all(|a|a.tallies.is_none()));
+    assert!(!serde_json::to_string(&without).unwrap().contains("tallies"));
+    problem.include_tallies=Some(true);
+    let with = problem.solve().solution.unwrap();
+    assert_eq!(without.assertions.len(),with.assertions.len());
+    for a in &with.assertions {
+        let tallies = a.tallies.unwrap();
+        assert_eq!(a.margin,tallies.winner-tallies.loser);
+    }
+    // Chuan NEB Bob compares Chuan's 5000 first preferences with the 1000 ballots preferring Bob to Chuan.
+    let chuan_neb_bob = with.assertions.iter().find(|a|a.assertion==Assertion::NEB(NotEliminatedBefore{winner:CandidateIndex(2),loser:CandidateIndex(1)})).unwrap();
+    assert_eq!(Some(AssertionTallies{winner:BallotPaperCount(5000),loser:BallotPaperCount(1000)}),chuan_neb_bob.tallies);
+    let json = serde_json::to_value(chuan_neb_bob).unwrap();
+    assert_eq!(json!({"winner":5000,"loser":1000}),json["tallies"]);
+}
+
+#[test]
+/// Test that rescoring an existing result for a different number of auditable ballots changes the difficulties
+/// as expected, without changing the assertions, and agrees with solving from scratch.
+fn test_rescore() {
+    let mut problem = guide_problem(json!({}));
+    let original = problem.clone().solve().solution.unwrap();
+    problem.audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(27000)));
+    let mut rescored = original.clone();
+    problem.rescore(&mut rescored).unwrap();
+    assert_eq!(original.assertions.iter().map(|a|&a.assertion).collect::<Vec<_>>(),rescored.assertions.iter().map(|a|&a.assertion).collect::<Vec<_>>());
+    for (before,after) in original.assertions.iter().zip(rescored.assertions.iter()) {
+        assert_eq!(before.margin,after.margin);
+        assert!((2.0*before.difficulty-after.difficulty).abs()<1e-9);
+    }
+    assert!((2.0*original.difficulty-rescored.difficulty).abs()<1e-9);
+    assert_eq!(original.margin,rescored.margin);
+    let fresh = problem.clone().solve().solution.unwrap();
+    assert!((fresh.difficulty-rescored.difficulty).abs()<1e-9);
+    rescored.verify_result_does_prove_winner().unwrap();
+    problem.num_candidates = 5;
+    assert!(matches!(problem.rescore(&mut rescored),Err(RaireError::WrongNumberOfCandidates(5,4))));
+}
+
+#[test]
+/// Test that the state of the search is returned when it runs out of work part way through finding assertions,
+/// and that the assertions if stopped then prove the winner, once every pending suffix has some assertion ruling it out.
+fn test_partial_result_on_timeout() {
+    let votes = guide_votes();
+    let audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(13500)));
+    let solve = |work_limit:Option<u64>| raire_with_partial(&votes,None,&audit,TrimAlgorithm::MinimizeTree,&RaireOptions::default(),&mut TimeOut::new(work_limit,None));
+    let (full,partial) = solve(None);
+    let full = full.unwrap();
+    assert!(partial.is_none());
+    let (result,partial) = solve(Some(5)); // Diego has not yet been ruled out.
+    assert!(matches!(result,Err(RaireError::TimeoutFindingAssertions(_))));
+    let partial = partial.unwrap();
+    assert_eq!(full.winner,partial.winner);
+    assert!(partial.assertions_if_stopped_now().is_none());
+    assert_eq!(f64::INFINITY,partial.upper_bound());
+    let (result,partial) = solve(Some(10));
+    assert!(matches!(result,Err(RaireError::TimeoutFindingAssertions(_))));
+    let partial = partial.unwrap();
+    assert!(!partial.frontier.is_empty());
+    assert!(partial.lower_bound<=full.difficulty);
+    assert!(partial.upper_bound()>=full.difficulty && partial.upper_bound().is_finite());
+    let stopped_now = RaireResult{assertions:partial.assertions_if_stopped_now().unwrap(),..full};
+    assert!(stopped_now.verify_result_does_prove_winner().is_ok());
+}
+
+#[test]
+/// Test that the search can be resumed from any checkpoint, after saving it as JSON, giving the same difficulty as an uninterrupted search.
+fn test_checkpoint_and_resume() {
+    let votes = guide_votes();
+    let audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(13500)));
+    let mut saved : Vec<String> = vec![];
+    let full = raire_with_checkpoints(&votes,None,&audit,TrimAlgorithm::MinimizeTree,&RaireOptions::default(),Duration::ZERO,&mut |checkpoint|saved.push(serde_json::to_string(checkpoint).unwrap()),&mut TimeOut::never()).unwrap();
+    assert!(!saved.is_empty());
+    for json in &saved {
+        let checkpoint : SearchCheckpoint = serde_json::from_str(json).unwrap();
+        assert_eq!(full.winner,checkpoint.winner);
+        let mut resumed_checkpoints = 0;
+        let resumed = raire_resume(&votes,&audit,&checkpoint,TrimAlgorithm::MinimizeTree,&RaireOptions::default(),Duration::ZERO,&mut |c|{ assert!(c.work_done>checkpoint.work_done); resumed_checkpoints+=1; },&mut TimeOut::never()).unwrap();
+        assert!((full.difficulty-resumed.difficulty).abs()<1e-9);
+        resumed.verify_result_does_prove_winner().unwrap();
+        assert!(resumed_checkpoints<=saved.len());
+    }
+    let checkpoint : SearchCheckpoint = serde_json::from_str(&saved[0]).unwrap();
+    let three_candidates = Votes::new(vec![Vote{n:BallotPaperCount(5),prefs:vec![CandidateIndex(0)]}],3).unwrap();
+    assert!(matches!(raire_resume(&three_candidates,&audit,&checkpoint,TrimAlgorithm::MinimizeTree,&RaireOptions::default(),Duration::MAX,&mut |_|{},&mut TimeOut::never()),Err(RaireError::WrongNumberOfCandidates(3,4))));
+    // a checkpoint cannot be resumed with different votes, audit type or required assertions.
+    let resume = |votes:&Votes,audit:&Audit,options:&RaireOptions|raire_resume(votes,audit,&checkpoint,TrimAlgorithm::MinimizeTree,options,Duration::MAX,&mut |_|{},&mut TimeOut::never());
+    let mut changed_ballots = votes.votes.clone();
+    changed_ballots[0].n=BallotPaperCount(5001);
+    assert!(matches!(resume(&Votes::new(changed_ballots,4).unwrap(),&audit,&RaireOptions::default()),Err(RaireError::InvalidCheckpoint(_))));
+    let changed_audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(13501)));
+    assert!(matches!(resume(&votes,&changed_audit,&RaireOptions::default()),Err(RaireError::InvalidCheckpoint(_))));
+    let required = RaireOptions{required_assertions:vec![full.assertions[0].assertion.clone()],..Default::default()};
+    assert!(matches!(resume(&votes,&audit,&required),Err(RaireError::InvalidCheckpoint(_))));
+    let neb_only = RaireOptions{max_nen_continuing:Some(0),..Default::default()};
+    assert!(matches!(raire_resume(&votes,&audit,&checkpoint,TrimAlgorithm::MinimizeTree,&neb_only,Duration::MAX,&mut |_|{},&mut TimeOut::never()),Err(RaireError::InvalidCheckpoint(_))));
+    let wrong_winner = SearchCheckpoint{winner:CandidateIndex(0),..checkpoint};
+    assert!(matches!(raire_resume(&votes,&audit,&wrong_winner,TrimAlgorithm::MinimizeTree,&RaireOptions::default(),Duration::MAX,&mut |_|{},&mut TimeOut::never()),Err(RaireError::WrongWinner(_))));
+}
+
+#[test]
+/// Test a contest only decided by statutory tie resolution. Bob and Chuan are tied for elimination first. If Bob is
+/// eliminated, Chuan wins, and if Chuan is eliminated, Bob wins.
+fn test_tie_break_order() {
+    let mut problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan"]
+        }),
+        num_candidates : 3,
+        votes : vec![Vote{n:BallotPaperCount(8),prefs:vec![CandidateIndex(0)]},Vote{n:BallotPaperCount(5),prefs:vec![CandidateIndex(1),CandidateIndex(2)]},Vote{n:BallotPaperCount(5),prefs:vec![CandidateIndex(2),CandidateIndex(1)]}],
+        winner : Some(CandidateIndex(2)),
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(18))),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeTree),
+        ..Default::default()
+    };
+    assert!(matches!(problem.clone().solve().solution,Err(RaireError::TiedWinners(_))));
+    problem.tie_break_order=Some(vec![CandidateIndex(2),CandidateIndex(1)]); // Chuan eliminated first, so Bob wins.
+    assert!(matches!(problem.clone().solve().solution,Err(RaireError::WrongWinner(_))));
+    problem.tie_break_order=Some(vec![CandidateIndex(1),CandidateIndex(2)]); // Bob eliminated first, so Chuan wins.
+    let result = problem.clone().solve().solution.unwrap();
+    assert!(result.conditional_on_tie_break);
+    assert_eq!(CandidateIndex(2),result.winner);
+    assert_eq!(vec![CandidateIndex(1),CandidateIndex(0),CandidateIndex(2)],result.example_elimination_order);
+    assert!(result.assertions.iter().any(|a|a.margin==BallotPaperCount(0))); // the tie resolution.
+    assert_eq!(BallotPaperCount(2),result.margin); // Chuan beats Alice 10 to 8 in the final round.
+    assert!((result.difficulty-9.0).abs()<0.001);
+    // Required assertions are checked against the actual votes, and included.
+    let chuan_beats_alice = Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(2),loser:CandidateIndex(0),continuing:vec![CandidateIndex(0),CandidateIndex(2)]});
+    // Without NEN assertions, Chuan cannot be shown to beat Alice once Bob is eliminated.
+    assert!(matches!(RaireProblem{max_nen_continuing:Some(0),..problem.clone()}.solve().solution,Err(RaireError::CouldNotRuleOut(_))));
+    problem.required_assertions=vec![chuan_beats_alice.clone()];
+    let result = problem.clone().solve().solution.unwrap();
+    assert!(result.conditional_on_tie_break);
+    assert!(result.assertions.iter().any(|a|a.assertion==chuan_beats_alice));
+    let alice_beats_bob = Assertion::NEB(NotEliminatedBefore{winner:CandidateIndex(0),loser:CandidateIndex(1)}); // Bob is ahead of Alice on 10 ballots.
+    problem.required_assertions=vec![chuan_beats_alice,alice_beats_bob];
+    assert!(matches!(problem.solve().solution,Err(RaireError::AssertionDoesNotHold(1))));
+}
+
+#[test]
+/// Test that the tie resolution does not change the assertions found when the tie is not relevant. The search in
+/// [tie_break_order_solve] is done on scaled up votes, which should give the same result as solving the actual votes
+/// directly, including when the audit has a minimum auditable margin.
+fn test_tie_break_order_solve_matches_direct_solve() {
+    let votes = guide_votes();
+    for audit in [Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(13500))),Audit::BRAVO(BallotPollingBRAVO{confidence:0.05,total_auditable_ballots:BallotPaperCount(13500)})] {
+        for auto_accept_difficulty in [None,Some(10.0),Some(100.0)] {
+            let options = RaireOptions{auto_accept_difficulty,..RaireOptions::default()};
+            let direct = raire_with_options(&votes,Some(CandidateIndex(2)),&audit,TrimAlgorithm::MinimizeTree,&options,&mut TimeOut::never()).unwrap();
+            let tie_broken = tie_break_order_solve(&votes,CandidateIndex(2),&audit,&[CandidateIndex(0),CandidateIndex(1)],TrimAlgorithm::MinimizeTree,&options,&mut TimeOut::never()).unwrap();
+            assert!(tie_broken.conditional_on_tie_break);
+            assert_eq!(direct.difficulty,tie_broken.difficulty);
+            assert_eq!(direct.margin,tie_broken.margin);
+            assert_eq!(direct.assertions.iter().map(|a|&a.assertion).collect::<Vec<_>>(),tie_broken.assertions.iter().map(|a|&a.assertion).collect::<Vec<_>>());
+        }
+    }
+    // The minimum auditable margin applies to the actual votes, so the assertions with a margin of 500 can't be used in either case.
+    let min_margin_audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(13500)).with_min_auditable_margin(BallotPaperCount(600)));
+    assert!(matches!(raire(&votes,Some(CandidateIndex(2)),&min_margin_audit,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()),Err(RaireError::CouldNotRuleOut(_))));
+    assert!(matches!(tie_break_order_solve(&votes,CandidateIndex(2),&min_margin_audit,&[CandidateIndex(0),CandidateIndex(1)],TrimAlgorithm::MinimizeTree,&RaireOptions::default(),&mut TimeOut::never()),Err(RaireError::CouldNotRuleOut(_))));
+}
+
+#[test]
+/// Test that a landslide is estimated to be cheaper to solve than a near tie with the same candidates.
+fn test_estimate_solve_cost() {
+    let make_votes = |first_preferences:&[usize]| {
+        let votes = first_preferences.iter().enumerate().map(|(i,&n)|Vote{n:BallotPaperCount(n),prefs:vec![CandidateIndex(i as u32),CandidateIndex(((i+1)%first_preferences.len()) as u32)]}).collect();
+        Votes::new(votes,first_preferences.len()).unwrap()
+    };
+    let landslide = make_votes(&[400,100,100,100,100,100,100,100,100,100]).estimate_solve_cost();
+    let near_tie = make_votes(&[120,119,118,117,116,115,114,113,112,111]).estimate_solve_cost();
+    assert_eq!(10,landslide.candidates);
+    assert_eq!(10,landslide.distinct_votes);
+    assert_eq!(DifficultyClass::Easy,landslide.rough_difficulty_class);
+    assert_eq!(DifficultyClass::Hard,near_tie.rough_difficulty_class);
+    assert!(landslide.rough_difficulty_class<near_tie.rough_difficulty_class);
+}
+
+#[test]
+/// Test that minor candidates in a landslide are irrelevant, but the top contenders are not.
+fn test_irrelevant_candidates() {
+    let first_preferences = [5,450,60,400,40,50];
+    let votes = first_preferences.iter().enumerate().map(|(i,&n)|Vote{n:BallotPaperCount(n),prefs:vec![CandidateIndex(i as u32),CandidateIndex(((i+1)%first_preferences.len()) as u32)]}).collect();
+    let votes = Votes::new(votes,first_preferences.len()).unwrap();
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(4),CandidateIndex(5)],votes.irrelevant_candidates());
+    // Removing them does not change the winner.
+    let winner = votes.run_election(&mut TimeOut::never()).unwrap().possible_winners;
+    assert_eq!(winner,votes.project(&votes.irrelevant_candidates()).run_election(&mut TimeOut::never()).unwrap().possible_winners);
+    // With a close third candidate, only the smallest candidates are irrelevant.
+    let first_preferences = [5,450,380,400,40,50];
+    let votes = first_preferences.iter().enumerate().map(|(i,&n)|Vote{n:BallotPaperCount(n),prefs:vec![CandidateIndex(i as u32)]}).collect();
+    let votes = Votes::new(votes,first_preferences.len()).unwrap();
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(4),CandidateIndex(5)],votes.irrelevant_candidates());
+}
+
+#[test]
+/// Test patching the trim algorithm and time limit of a problem, leaving everything else unchanged.
+fn test_apply_patch() {
+    let original = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan"],
+            "contest" : "Mayor"
+        }),
+        num_candidates : 3,
+        votes : vec![Vote{n:BallotPaperCount(8),prefs:vec![CandidateIndex(0)]},Vote{n:BallotPaperCount(5),prefs:vec![CandidateIndex(1),CandidateIndex(2)]},Vote{n:BallotPaperCount(4),prefs:vec![CandidateIndex(2),CandidateIndex(1)]}],
+        winner : Some(CandidateIndex(1)),
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(17))),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeTree),
+        use_neb_prepass: Some(false),
+        ..Default::default()
+    };
+    let mut problem = original.clone();
+    problem.apply_patch(json!({"trim_algorithm":"MinimizeAssertions","time_limit_seconds":10.0,"use_neb_prepass":null,"metadata":{"contest":"Deputy Mayor"}})).unwrap();
+    assert!(matches!(problem.trim_algorithm,Some(TrimAlgorithm::MinimizeAssertions)));
+    assert_eq!(Some(10.0),problem.time_limit_seconds);
+    assert_eq!(None,problem.use_neb_prepass);
+    assert_eq!(json!({"candidates" : ["Alice","Bob","Chuan"],"contest" : "Deputy Mayor"}),problem.metadata);
+    let unpatched = |p:&RaireProblem|serde_json::to_value(RaireProblem{trim_algorithm:None,time_limit_seconds:None,use_neb_prepass:None,metadata:json!(null),..p.clone()}).unwrap();
+    assert_eq!(unpatched(&original),unpatched(&problem));
+    // Invalid patches leave the problem unchanged.
+    let before = serde_json::to_value(&problem).unwrap();
+    assert!(matches!(problem.apply_patch(json!({"time_limit_seconds":-1.0})),Err(RaireError::InvalidTimeout)));
+    assert!(matches!(problem.apply_patch(json!({"num_candidates":2})),Err(RaireError::InvalidCandidateNumber)));
+    assert!(matches!(problem.apply_patch(json!({"trim_algorithm":"Fastest"})),Err(RaireError::InvalidPatch(_))));
+    assert!(matches!(problem.apply_patch(json!({"votes":[]})),Err(RaireError::InvalidPatch(_))));
+    assert_eq!(before,serde_json::to_value(&problem).unwrap());
+}
+
+#[test]
+/// Test the separate time limit for determining the winner, with a contest that is easy to audit (Alice beats everyone
+/// by NEB) but expensive to tabulate, as there are many tied minor candidates who cannot be bulk eliminated.
+fn test_winner_computation_time_limit() {
+    let mut votes = vec![Vote{n:BallotPaperCount(1000),prefs:vec![CandidateIndex(0)]},Vote{n:BallotPaperCount(900),prefs:vec![CandidateIndex(1)]}];
+    for c in 2..16 { votes.push(Vote{n:BallotPaperCount(100),prefs:vec![CandidateIndex(c)]}); }
+    let mut problem = RaireProblem {
+        metadata : json!({}),
+        num_candidates : 16,
+        votes,
+        audit : Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(3300))),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeTree),
+        time_limit_seconds: Some(1000.0),
+        winner_computation_time_limit: Some(1e-6),
+        ..Default::default()
+    };
+    let solution = problem.clone().solve();
+    assert!(matches!(solution.solution,Err(RaireError::TimeoutCheckingWinner)));
+    assert_eq!(TimeoutCause::WinnerComputationTime,solution.timeout_cause);
+    problem.time_limit_trimming=Some(-1.0);
+    assert!(matches!(problem.clone().solve().solution,Err(RaireError::InvalidTimeout)));
+    problem.time_limit_trimming=Some(1000.0);
+    problem.time_limit_finding_assertions=Some(f64::NAN);
+    assert!(matches!(problem.clone().solve().solution,Err(RaireError::InvalidTimeout)));
+    problem.time_limit_finding_assertions=Some(1000.0);
+    problem.winner_computation_time_limit=Some(0.0);
+    assert!(matches!(problem.clone().solve().solution,Err(RaireError::InvalidTimeout)));
+    problem.winner_computation_time_limit=None;
+    let solution = problem.solve();
+    assert_eq!(CandidateIndex(0),solution.solution.unwrap().winner);
+    assert_eq!(TimeoutCause::None,solution.timeout_cause);
+}
+
+#[test]
+/// Test restricting the size of the continuing sets of NEN assertions. Alice beats Bob by 5 votes without any of Chuan's
+/// preferences, so NEB assertions alone prove she won, but it is easier to compare Alice and Bob once Chuan is eliminated.
+fn test_max_nen_continuing() {
+    let problem : RaireProblem = serde_json::from_value(json!({
+        "num_candidates" : 3,
+        "votes" : [{"n":40,"prefs":[0]},{"n":30,"prefs":[1]},{"n":25,"prefs":[2,0]},{"n":5,"prefs":[2,1]}],
+        "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 100 }
+    })).unwrap();
+    let solve = |max_nen_continuing:Option<usize>|RaireProblem{max_nen_continuing,..problem.clone()}.solve().solution.unwrap();
+    let unrestricted = solve(None);
+    assert!(unrestricted.assertions.iter().any(|a|matches!(a.assertion,Assertion::NEN(_))));
+    let neb_only = solve(Some(0));
+    assert!(neb_only.assertions.iter().all(|a|matches!(a.assertion,Assertion::NEB(_))));
+    assert_eq!(20.0,neb_only.difficulty);
+    assert!(unrestricted.difficulty<neb_only.difficulty);
+    neb_only.verify_result_does_prove_winner().unwrap();
+    let pairs_only = solve(Some(2));
+    assert!(pairs_only.assertions.iter().all(|a|match &a.assertion { Assertion::NEN(nen) => nen.continuing.len()<=2, Assertion::NEB(_) => true }));
+    assert_eq!(unrestricted.difficulty,pairs_only.difficulty);
+    let votes = Votes::new(problem.votes.clone(),problem.num_candidates).unwrap();
+    let provisional = provisional_solve_with_options(&votes,&problem.audit,&RaireOptions{max_nen_continuing:Some(0),..Default::default()},&mut TimeOut::never()).solution.unwrap();
+    assert!(provisional.assertions.iter().all(|a|matches!(a.assertion,Assertion::NEB(_))));
+    assert_eq!(20.0,provisional.difficulty);
+}
+
+#[test]
+/// Test that the frontier ordering can be given in a problem, and does not change the difficulty.
+fn test_frontier_ordering() {
+    let problem : RaireProblem = serde_json::from_value(json!({
+        "num_candidates" : 3,
+        "votes" : [{"n":40,"prefs":[0]},{"n":30,"prefs":[1]},{"n":25,"prefs":[2,0]},{"n":5,"prefs":[2,1]}],
+        "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 100 },
+        "frontier_ordering" : "LengthWeighted"
+    })).unwrap();
+    assert_eq!(FrontierOrdering::LengthWeighted,problem.options().frontier_ordering);
+    let length_weighted = problem.clone().solve().solution.unwrap();
+    let best_first = RaireProblem{frontier_ordering:None,..problem}.solve().solution.unwrap();
+    assert_eq!(best_first.difficulty,length_weighted.difficulty);
+    length_weighted.verify_result_does_prove_winner().unwrap();
+}
+
+#[test]
+/// Test that the choice between equally difficult assertions is made by the tie break.
+fn test_tie_break() {
+    let (a,b,c) = (CandidateIndex(0),CandidateIndex(1),CandidateIndex(2));
+    // With only a, b and c continuing, b and c each have 30 votes, so a NEN b and a NEN c are equally difficult.
+    let votes = Votes::new(vec![
+        Vote{ n: BallotPaperCount(100), prefs: vec![a] },
+        Vote{ n: BallotPaperCount(30), prefs: vec![b] },
+        Vote{ n: BallotPaperCount(10), prefs: vec![c,b] },
+        Vote{ n: BallotPaperCount(20), prefs: vec![c] },
+    ],3).unwrap();
+    let audit = BallotComparisonOneOnDilutedMargin::new(votes.total_votes());
+    let nen_a_b = Assertion::NEN(NotEliminatedNext{winner:a,loser:b,continuing:vec![a,b,c]});
+    let nen_a_c = Assertion::NEN(NotEliminatedNext{winner:a,loser:c,continuing:vec![a,b,c]});
+    // By default, as in the original implementation, the loser is the last of the equal lowest tallies in the continuing candidates.
+    for (continuing,expected) in [([a,b,c],&nen_a_c),([a,c,b],&nen_a_b),([c,b,a],&nen_a_b)] {
+        assert_eq!(*expected,NotEliminatedNext::find_best_difficulty(&votes,&audit,&continuing,a).unwrap().assertion);
+        for tie_break in [TieBreak::PreferNEB,TieBreak::PreferShorterNEN] {
+            assert_eq!(*expected,NotEliminatedNext::find_best_difficulty_with_tie_break(&votes,&audit,&continuing,a,tie_break).unwrap().assertion);
+        }
+        assert_eq!(nen_a_b,NotEliminatedNext::find_best_difficulty_with_tie_break(&votes,&audit,&continuing,a,TieBreak::PreferLowerCandidateIndex).unwrap().assertion);
+    }
+    // c NEB a and b NEB c both have a margin of 40.
+    let votes = Votes::new(vec![
+        Vote{ n: BallotPaperCount(10), prefs: vec![a] },
+        Vote{ n: BallotPaperCount(90), prefs: vec![b] },
+        Vote{ n: BallotPaperCount(50), prefs: vec![c] },
+    ],3).unwrap();
+    let audit = BallotComparisonOneOnDilutedMargin::new(votes.total_votes());
+    let cache = NotEliminatedBeforeCache::new(&votes,&audit);
+    let c_neb_a = Assertion::NEB(NotEliminatedBefore{winner:c,loser:a});
+    let b_neb_c = Assertion::NEB(NotEliminatedBefore{winner:b,loser:c});
+    for (tie_break,expected) in [(TieBreak::PreferNEB,&c_neb_a),(TieBreak::PreferShorterNEN,&c_neb_a),(TieBreak::PreferLowerCandidateIndex,&b_neb_c)] {
+        assert_eq!(*expected,NotEliminatedBefore::find_best_assertion_using_cache(c,&[a],&votes,&cache,tie_break).unwrap().assertion);
+        assert_eq!(*expected,NotEliminatedBefore::find_best_assertion(c,&[a],&votes,&audit,tie_break).unwrap().assertion);
+    }
+    // Between a NEB and a NEN assertion.
+    let a_neb_c = Assertion::NEB(NotEliminatedBefore{winner:a,loser:c});
+    assert!(TieBreak::PreferNEB.prefers(&a_neb_c,&nen_a_b) && !TieBreak::PreferNEB.prefers(&nen_a_b,&a_neb_c));
+    assert!(TieBreak::PreferShorterNEN.prefers(&nen_a_b,&a_neb_c) && !TieBreak::PreferShorterNEN.prefers(&a_neb_c,&nen_a_b));
+    assert!(TieBreak::PreferLowerCandidateIndex.prefers(&nen_a_b,&a_neb_c) && !TieBreak::PreferLowerCandidateIndex.prefers(&a_neb_c,&nen_a_b));
+}
@@ -12,12 +12,13 @@
 
 
 use serde_json::json;
-use raire::assertions::{NotEliminatedNext, NotEliminatedBefore};
-use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
+use raire::assertions::{NotEliminatedNext, NotEliminatedBefore, AssertionAndDifficulty, Assertion, EffectOfAssertionOnEliminationOrderSuffix, all_elimination_orders, check_assertion_consistency};
+use raire::audit_type::{Audit, BallotComparisonMACRO, BallotComparisonOneOnDilutedMargin, BallotPollingOneOnDilutedMarginSquared};
 use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
-use raire::raire_algorithm::TrimAlgorithm;
-use raire::RaireProblem;
-use raire::timeout::TimeOut;
+use raire::raire_algorithm::{raire, raire_with_log, raire_with_options, prove_orders_impossible, ArgumentKind, DecisionKind, RaireOptions, RaireResult, SearchStats, TrimAlgorithm, TrimObjective};
+use raire::tree_showing_what_assertions_pruned_leaves::{order_assertions_and_remove_unnecessary, order_assertions_and_remove_unnecessary_by_objective};
+use raire::{solve_across_confidence, solve_batch_with_overrides, BatchEntry, trim_comparison, trim_pareto, winner_stable_across_confidence, RaireError, RaireProblem, TrimComparison};
+use raire::timeout::{TimeOut, TimeoutCause};
 
 const A : CandidateIndex = CandidateIndex(0); // Alice
 const B : CandidateIndex = CandidateIndex(1); // Bob
@@ -37,7 +38,7 @@ fn get_votes() -> Votes {
 }
 
 /// The audit used in the examples.
-const AUDIT : BallotComparisonOneOnDilutedMargin = BallotComparisonOneOnDilutedMargin { total_auditable_ballots : BallotPaperCount(13500) };
+const AUDIT : BallotComparisonOneOnDilutedMargin = BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(13500));
 
 
 #[test]
@@ -54,6 +55,21 @@ fn test_votes_structure() {
     let result = votes.run_election(&mut TimeOut::never()).unwrap();
     assert_eq!(vec![C],result.possible_winners);
     assert_eq!(vec![B,D,A,C],result.elimination_order);
+    assert_eq!(vec![B],votes.first_eliminated_candidates(&mut TimeOut::never()).unwrap());
+}
+
+#[test]
+/// Test the pairwise matrix against hand computed counts.
+fn test_pairwise_matrix() {
+    let votes = get_votes();
+    let matrix = votes.pairwise_matrix();
+    let expected : Vec<Vec<BallotPaperCount>> = [
+        [0,0,0,4000], // Alice is above Diego on 4000 ballots, and not above anyone else on any ballot also containing them.
+        [5000,0,1000,1000], // Bob is above Alice on the Chuan,Bob,Alice ballots, and above Chuan and Diego on the Bob,Chuan,Diego ballots.
+        [5000,5000,0,1000],
+        [1500,0,0,0], // Diego is only above Alice on the Diego,Alice ballots; the Diego only ballots don't mention anyone else.
+    ].iter().map(|row|row.iter().map(|&n|BallotPaperCount(n)).collect()).collect();
+    assert_eq!(expected,matrix);
 }
 
 #[test]
@@ -102,6 +118,7 @@ fn test_raire() {
         trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
         difficulty_estimate: None,
         time_limit_seconds: None,
+        ..Default::default()
     };
     println!("{}",serde_json::to_string_pretty(&problem).unwrap());
     let solution = problem.solve();
@@ -112,3 +129,727 @@ fn test_raire() {
     assert_eq!(5,solution.assertions.len());
 }
 
+#[test]
+/// Test that just the assertions can be extracted as JSON, and that they can be read back.
+fn test_assertions_only_json() {
+    let problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan","Diego"]
+        }),
+        num_candidates : 4,
+        votes : get_votes().votes,
+        winner : Some(CandidateIndex(2)),
+        audit : Audit::OneOnMargin(AUDIT),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
+        ..Default::default()
+    };
+    let solution = problem.solve().solution.unwrap();
+    let json = solution.assertions_only_json();
+    assert!(json.is_array());
+    assert!(json[0].get("difficulty").is_some());
+    assert!(json[0].get("time_to_find_assertions").is_none());
+    let assertions : Vec<AssertionAndDifficulty> = serde_json::from_value(json).unwrap();
+    assert_eq!(solution.assertions,assertions);
+}
+
+#[test]
+/// Test that extreme weights in the trim objective reproduce the named trim algorithms.
+fn test_trim_objective() {
+    let votes = get_votes();
+    let untrimmed = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::None,&mut TimeOut::never()).unwrap().assertions;
+    let trim = |trim_algorithm:TrimAlgorithm| {
+        let mut assertions = untrimmed.clone();
+        order_assertions_and_remove_unnecessary(&mut assertions,C,4,trim_algorithm,&mut TimeOut::never()).unwrap();
+        assertions
+    };
+    let trim_by_objective = |count_weight:f64,difficulty_weight:f64| {
+        let mut assertions = untrimmed.clone();
+        order_assertions_and_remove_unnecessary_by_objective(&mut assertions,C,4,TrimObjective{count_weight,difficulty_weight},&mut TimeOut::never()).unwrap();
+        assertions
+    };
+    let minimize_tree = trim(TrimAlgorithm::MinimizeTree);
+    let minimize_assertions = trim(TrimAlgorithm::MinimizeAssertions);
+    assert_eq!(6,minimize_tree.len());
+    assert_eq!(5,minimize_assertions.len());
+    assert_eq!(minimize_assertions,trim_by_objective(1.0,0.0));
+    assert_eq!(minimize_assertions,trim_by_objective(1e6,1.0));
+    assert_eq!(minimize_tree,trim_by_objective(0.0,1.0));
+}
+
+#[test]
+/// Test that the trim objective can minimize the maximum difficulty, when a difficult assertion is not needed. Chuan NEB Alice
+/// rules out Alice winning immediately, so both MinimizeTree and MinimizeAssertions use it, but it can instead be ruled out
+/// by the easier assertions that Chuan beats Alice when only they remain, and Chuan NEB Bob.
+fn test_trim_objective_minimize_max_difficulty() {
+    let with_difficulty = |assertion:Assertion,difficulty:f64| AssertionAndDifficulty{assertion,difficulty,margin:BallotPaperCount(1),tallies:None};
+    let chuan_neb_alice = with_difficulty(Assertion::NEB(NotEliminatedBefore{winner:C,loser:A}),50.0);
+    let chuan_neb_bob = with_difficulty(Assertion::NEB(NotEliminatedBefore{winner:C,loser:B}),2.0);
+    let chuan_beats_alice = with_difficulty(Assertion::NEN(NotEliminatedNext{winner:C,loser:A,continuing:vec![A,C]}),3.0);
+    let untrimmed = vec![chuan_neb_alice.clone(),chuan_neb_bob.clone(),chuan_beats_alice.clone()];
+    let trim_by_objective = |count_weight:f64,difficulty_weight:f64| {
+        let mut assertions = untrimmed.clone();
+        order_assertions_and_remove_unnecessary_by_objective(&mut assertions,C,3,TrimObjective{count_weight,difficulty_weight},&mut TimeOut::never()).unwrap();
+        assertions
+    };
+    for trim_algorithm in [TrimAlgorithm::MinimizeTree,TrimAlgorithm::MinimizeAssertions] {
+        let mut assertions = untrimmed.clone();
+        order_assertions_and_remove_unnecessary(&mut assertions,C,3,trim_algorithm,&mut TimeOut::never()).unwrap();
+        assert_eq!(vec![chuan_neb_alice.clone(),chuan_neb_bob.clone()],assertions);
+    }
+    assert_eq!(vec![chuan_neb_bob.clone(),chuan_beats_alice.clone()],trim_by_objective(0.0,1.0));
+    // All the trims have two assertions, so counting alone prefers MinimizeTree, but any weight on difficulty prefers the easier set.
+    assert_eq!(vec![chuan_neb_alice,chuan_neb_bob.clone()],trim_by_objective(1.0,0.0));
+    assert_eq!(vec![chuan_neb_bob,chuan_beats_alice],trim_by_objective(1e6,1.0));
+    // If the time limit is reached, the best trim completed is used.
+    let mut assertions = untrimmed.clone();
+    let mut timeout = TimeOut::new(Some(10),None);
+    order_assertions_and_remove_unnecessary_by_objective(&mut assertions,C,3,TrimObjective{count_weight:0.0,difficulty_weight:1.0},&mut timeout).unwrap();
+    assert_eq!(TimeoutCause::Work,timeout.cause());
+    assert_eq!(2,assertions.len());
+}
+
+#[test]
+/// Test that MinimizeTree produces a smaller tree to verify, at the cost of more assertions, than MinimizeAssertions.
+fn test_human_verification_effort() {
+    let votes = get_votes();
+    let effort = |trim_algorithm:TrimAlgorithm| raire(&votes,Some(C),&AUDIT,trim_algorithm,&mut TimeOut::never()).unwrap().human_verification_effort(&mut TimeOut::never()).unwrap();
+    let minimize_tree = effort(TrimAlgorithm::MinimizeTree);
+    let minimize_assertions = effort(TrimAlgorithm::MinimizeAssertions);
+    assert_eq!(6,minimize_tree.num_assertions);
+    assert_eq!(5,minimize_assertions.num_assertions);
+    assert!(minimize_tree.tree_node_count<minimize_assertions.tree_node_count);
+}
+
+#[test]
+/// Test comparing the trim algorithms. A1 (Alice NEN Diego | {Alice,Diego}) is retained by MinimizeTree but dropped by MinimizeAssertions.
+fn test_trim_comparison() {
+    let problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan","Diego"]
+        }),
+        num_candidates : 4,
+        votes : get_votes().votes,
+        winner : Some(C),
+        audit : Audit::OneOnMargin(AUDIT),
+        ..Default::default()
+    };
+    let comparison = trim_comparison(&problem).unwrap();
+    let votes = get_votes();
+    let minimize_tree = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let minimize_assertions = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    let a1 = Assertion::NEN(NotEliminatedNext{ winner: A, loser: D, continuing: vec![A,D] });
+    assert!(minimize_tree.assertions.iter().any(|a|a.assertion==a1));
+    assert!(!minimize_assertions.assertions.iter().any(|a|a.assertion==a1));
+    assert_eq!(TrimComparison{tree_count:6,assertions_count:5,tree_node_count:minimize_tree.human_verification_effort(&mut TimeOut::never()).unwrap().tree_node_count},comparison);
+}
+
+#[test]
+/// Test the trim Pareto points for each trim algorithm.
+fn test_trim_pareto() {
+    let problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan","Diego"]
+        }),
+        num_candidates : 4,
+        votes : get_votes().votes,
+        winner : Some(C),
+        audit : Audit::OneOnMargin(AUDIT),
+        ..Default::default()
+    };
+    let points = trim_pareto(&problem).unwrap();
+    assert_eq!(3,points.len());
+    let (none,minimize_tree,minimize_assertions) = (points[0],points[1],points[2]);
+    assert_eq!(6,minimize_tree.0);
+    assert_eq!(5,minimize_assertions.0);
+    assert!(minimize_assertions.0<=none.0);
+    for point in points { assert_eq!(27.0,point.1); }
+}
+
+#[test]
+/// Test the checked constructor for NotEliminatedNext.
+fn test_not_eliminated_next_new() {
+    let nen = NotEliminatedNext::new(C,D,vec![D,A,C,A]).unwrap();
+    assert_eq!(NotEliminatedNext{winner:C,loser:D,continuing:vec![A,C,D]},nen);
+    check_assertion_consistency(&[AssertionAndDifficulty{assertion:Assertion::NEN(nen),difficulty:1.0,margin:BallotPaperCount(1),tallies:None}]).unwrap();
+    assert!(matches!(NotEliminatedNext::new(B,D,vec![A,C,D]),Err(RaireError::InvalidAssertion)));
+    assert!(matches!(NotEliminatedNext::new(C,B,vec![A,C,D]),Err(RaireError::InvalidAssertion)));
+    assert!(matches!(NotEliminatedNext::new(C,C,vec![A,C,D]),Err(RaireError::InvalidAssertion)));
+}
+
+#[test]
+#[cfg(feature="sha2")]
+/// Test that the certificate hash is stable, independent of assertion order, timing and tallies, and changes when an assertion changes.
+fn test_certificate_hash() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    let hash = result.certificate_hash();
+    assert_eq!(64,hash.len());
+    let again = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    assert_eq!(hash,again.certificate_hash());
+    let mut reversed = result.clone();
+    reversed.assertions.reverse();
+    assert_eq!(hash,reversed.certificate_hash());
+    let mut with_tallies = result.clone();
+    with_tallies.add_tallies(&votes);
+    assert_eq!(hash,with_tallies.certificate_hash());
+    let problem = |include_tallies:bool| RaireProblem{
+        metadata : json!({}),
+        num_candidates : 4,
+        votes : votes.votes.clone(),
+        winner : Some(C),
+        audit : Audit::OneOnMargin(AUDIT),
+        trim_algorithm : Some(TrimAlgorithm::MinimizeAssertions),
+        include_tallies : Some(include_tallies),
+        ..Default::default()
+    }.solve().solution.unwrap().certificate_hash();
+    assert_eq!(hash,problem(false));
+    assert_eq!(hash,problem(true));
+    let mut altered = result.clone();
+    altered.assertions[0].margin += BallotPaperCount(1);
+    assert_ne!(hash,altered.certificate_hash());
+    let mut altered = result.clone();
+    altered.assertions[0].assertion = Assertion::NEB(NotEliminatedBefore{winner:C,loser:D});
+    assert_ne!(hash,altered.certificate_hash());
+}
+
+#[test]
+/// Test the difficulty after removing an assertion. A1 (Alice NEN Diego | {Alice,Diego}) is redundant and not binding, so removing
+/// it leaves the difficulty unchanged. Removing Chuan NEB Bob means Bob is no longer ruled out.
+fn test_difficulty_without() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let index_of = |assertion:Assertion| result.assertions.iter().position(|a|a.assertion==assertion).unwrap();
+    let a1 = index_of(Assertion::NEN(NotEliminatedNext{ winner: A, loser: D, continuing: vec![A,D] }));
+    assert_eq!(3.0,result.assertions[a1].difficulty);
+    assert_eq!(Some(27.0),result.difficulty_without(a1,&mut TimeOut::never()).unwrap());
+    let neb = index_of(Assertion::NEB(NotEliminatedBefore{ winner: C, loser: B }));
+    assert_eq!(None,result.difficulty_without(neb,&mut TimeOut::never()).unwrap());
+    let out_of_range = result.assertions.len();
+    assert!(matches!(result.difficulty_without(out_of_range,&mut TimeOut::never()),Err(RaireError::InvalidAssertionIndex(i)) if i==out_of_range));
+}
+
+#[test]
+/// Test ruling out a specific elimination order, rather than proving the winner.
+fn test_prove_orders_impossible() {
+    let votes = get_votes();
+    let forbidden = vec![B,C,A,D];
+    let assertions = prove_orders_impossible(&votes,&AUDIT,std::slice::from_ref(&forbidden),&mut TimeOut::never()).unwrap();
+    assert_eq!(1,assertions.len());
+    assert_eq!(EffectOfAssertionOnEliminationOrderSuffix::Contradiction,assertions[0].assertion.ok_elimination_order_suffix(&forbidden));
+    let actual = votes.run_election(&mut TimeOut::never()).unwrap().elimination_order;
+    assert_eq!(EffectOfAssertionOnEliminationOrderSuffix::Ok,assertions[0].assertion.ok_elimination_order_suffix(&actual));
+    // the actual elimination order can't be ruled out.
+    assert!(matches!(prove_orders_impossible(&votes,&AUDIT,std::slice::from_ref(&actual),&mut TimeOut::never()),Err(RaireError::CouldNotRuleOut(order)) if order==actual));
+    // the forbidden orders must be permutations of the candidates.
+    let prove = |order:Vec<CandidateIndex>| prove_orders_impossible(&votes,&AUDIT,&[order],&mut TimeOut::never());
+    assert!(matches!(prove(vec![B,C,A,CandidateIndex(4)]),Err(RaireError::InvalidCandidateNumber)));
+    assert!(matches!(prove(vec![B,C,A]),Err(RaireError::InvalidAssertion)));
+    assert!(matches!(prove(vec![B,C,A,D,D]),Err(RaireError::InvalidAssertion)));
+    assert!(matches!(prove(vec![B,C,A,A]),Err(RaireError::InvalidAssertion)));
+}
+
+#[test]
+/// Test that the diluted margin of each assertion is a sensible fraction, and consistent with the difficulty for this audit type.
+fn test_margin_as_fraction_of_ballots() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let fractions = result.margin_as_fraction_of_ballots(AUDIT.total_auditable_ballots);
+    assert_eq!(result.assertions.len(),fractions.len());
+    for (fraction,assertion) in fractions.iter().zip(result.assertions.iter()) {
+        assert!(*fraction>0.0 && *fraction<=1.0);
+        assert!((1.0/fraction-assertion.difficulty).abs()<1e-6);
+    }
+}
+
+#[test]
+/// The example elimination order should be the one from the IRV count, ending with the winner.
+fn test_example_elimination_order() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    assert_eq!(vec![B,D,A,C],result.example_elimination_order);
+    assert_eq!(Some(&result.winner),result.example_elimination_order.last());
+}
+
+#[test]
+/// The binding assertion in the Guide example is Chuan beats Alice when only they remain, difficulty 27.
+/// There is another equally difficult assertion, Alice > Diego if only {Alice,Chuan,Diego} remain, but it comes later in the list.
+fn test_binding_pair() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    let binding = result.binding_assertion().unwrap();
+    assert_eq!(result.difficulty,binding.difficulty);
+    assert_eq!(Some((binding.assertion.winner(),binding.assertion.loser())),result.binding_pair());
+    assert_eq!(Some((C,A)),result.binding_pair());
+}
+
+#[test]
+/// Alice is the last candidate eliminated before Chuan wins.
+fn test_runner_up() {
+    let votes = get_votes();
+    let mut result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    assert_eq!(Some(A),result.runner_up(&votes));
+    result.example_elimination_order.clear(); // should rerun the election.
+    assert_eq!(Some(A),result.runner_up(&votes));
+}
+
+#[test]
+/// If Bob is disqualified, his votes go straight to Chuan, who still wins.
+fn test_solve_with_disqualified() {
+    let problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan","Diego"]
+        }),
+        num_candidates : 4,
+        votes : get_votes().votes,
+        winner : Some(C),
+        audit : Audit::OneOnMargin(AUDIT),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
+        ..Default::default()
+    };
+    let projected = get_votes().project(&[B]);
+    assert_eq!(BallotPaperCount(0),projected.first_preference_only_tally(B));
+    assert_eq!(BallotPaperCount(6000),projected.first_preference_only_tally(C));
+    let solution = problem.solve_with_disqualified(&[B]).solution.unwrap();
+    assert_eq!(C,solution.winner);
+    solution.verify_result_does_prove_winner().unwrap();
+    // disqualifying the winner means the winner is wrong.
+    assert!(matches!(problem.solve_with_disqualified(&[C]).solution,Err(RaireError::WrongWinner(_))));
+}
+
+#[test]
+/// Test that contradictory assertions are detected, and that the assertions RAIRE produces are consistent.
+fn test_check_assertion_consistency() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    check_assertion_consistency(&result.assertions).unwrap();
+    let neb = |winner,loser| AssertionAndDifficulty{assertion:Assertion::NEB(NotEliminatedBefore{winner,loser}),difficulty:1.0,margin:BallotPaperCount(1),tallies:None};
+    let nen = |winner,loser,continuing| AssertionAndDifficulty{assertion:Assertion::NEN(NotEliminatedNext{winner,loser,continuing}),difficulty:1.0,margin:BallotPaperCount(1),tallies:None};
+    assert!(matches!(check_assertion_consistency(&[neb(A,B),neb(C,D),neb(B,A)]),Err(RaireError::InconsistentAssertions(0,2))));
+    assert!(matches!(check_assertion_consistency(&[neb(A,B),nen(A,C,vec![A,C]),nen(C,A,vec![A,C])]),Err(RaireError::InconsistentAssertions(1,2))));
+    assert!(matches!(check_assertion_consistency(&[neb(A,B),nen(A,C,vec![A,B])]),Err(RaireError::InconsistentAssertions(1,1))));
+    assert!(matches!(check_assertion_consistency(&[neb(A,A)]),Err(RaireError::InconsistentAssertions(0,0))));
+    check_assertion_consistency(&[neb(A,B),nen(A,C,vec![A,C]),nen(C,A,vec![A,B,C])]).unwrap();
+}
+
+#[test]
+/// Test the plain English description of assertions names the correct candidates and includes the margin.
+fn test_plain_english() {
+    let votes = get_votes();
+    let names : Vec<String> = ["Alice","Bob","Chuan","Diego"].iter().map(|s|s.to_string()).collect();
+    let neb = Assertion::NEB(NotEliminatedBefore{winner:C,loser:B});
+    let sentence = neb.plain_english(&names,&votes);
+    assert_eq!("Chuan is never eliminated before Bob, because Chuan has 5000 first preference votes, more than the 1000 votes Bob could have while Chuan is continuing, a margin of 4000 votes (29.6% of all ballots).",sentence);
+    let nen = Assertion::NEN(NotEliminatedNext{winner:C,loser:D,continuing:vec![A,C,D]});
+    let sentence = nen.plain_english(&names,&votes);
+    assert_eq!("Chuan is not eliminated next when only Alice, Chuan, Diego remain, because Chuan has 6000 votes at that point, more than Diego with 3500, a margin of 2500 votes (18.5% of all ballots).",sentence);
+    let false_assertion = Assertion::NEB(NotEliminatedBefore{winner:B,loser:C});
+    assert!(false_assertion.plain_english(&names,&votes).contains("not supported"));
+}
+
+#[test]
+/// Test that the elimination orders are counted correctly, and adding assertions reduces the entropy.
+fn test_outcome_entropy() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let mut partial = result.clone();
+    partial.assertions.clear();
+    let mut last_entropy = partial.outcome_entropy(4);
+    assert!((last_entropy-24.0f64.ln()).abs()<1e-9); // 4! elimination orders.
+    for a in &result.assertions {
+        partial.assertions.push(a.clone());
+        assert_eq!(partial.possible_elimination_orders_allowed_by_assertions(4).len() as f64,partial.count_elimination_orders_allowed_by_assertions(4));
+        let entropy = partial.outcome_entropy(4);
+        assert!(entropy<=last_entropy);
+        last_entropy=entropy;
+    }
+    assert!(last_entropy<24.0f64.ln());
+    assert!(last_entropy>=0.0);
+}
+
+#[test]
+/// Chuan NEB Bob is the only NEB assertion in the Guide example.
+fn test_candidates_proven_behind_winner() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    assert_eq!(vec![B],result.candidates_proven_behind_winner());
+}
+
+#[test]
+/// Test the classification of the arguments used to show each candidate did not win.
+fn test_elimination_arguments() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    // Bob is ruled out by Chuan NEB Bob. Alice and Diego need trees using the Chuan NEB Bob assertion as well as NEN assertions.
+    assert_eq!(vec![(A,ArgumentKind::Mixed),(B,ArgumentKind::SingleNEB),(D,ArgumentKind::Mixed)],result.elimination_arguments());
+}
+
+#[test]
+/// Test adding an assertion that is not needed, A1 in the TrimAlgorithm documentation.
+fn test_add_redundant_assertion() {
+    let votes = get_votes();
+    let mut result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    assert_eq!(5,result.assertions.len());
+    let a1 = NotEliminatedNext{ winner: A, loser: D, continuing: vec![A,D] };
+    let a1_difficulty = a1.difficulty(&votes,&AUDIT);
+    assert!((a1_difficulty-3.0).abs()<0.001);
+    let (tally_winner,tally_loser) = a1.tallies(&votes);
+    let a1 = AssertionAndDifficulty{ assertion: Assertion::NEN(a1), margin: tally_winner-tally_loser, difficulty: a1_difficulty, tallies: None };
+    let effect = result.add_assertion(a1,&mut TimeOut::never()).unwrap();
+    assert!(effect.new_assertion_redundant);
+    assert!(effect.now_redundant.is_empty());
+    assert_eq!(27.0,effect.previous_difficulty);
+    assert_eq!(27.0,result.difficulty);
+    assert_eq!(6,result.assertions.len());
+    // Adding an assertion that Bob beats Chuan rules out the winner.
+    let bad = AssertionAndDifficulty{ assertion: Assertion::NEB(NotEliminatedBefore{winner:B,loser:C}), margin: BallotPaperCount(0), difficulty: f64::INFINITY, tallies: None };
+    assert!(result.add_assertion(bad,&mut TimeOut::never()).is_err());
+    assert_eq!(6,result.assertions.len());
+}
+
+#[test]
+/// Check that the search statistics count the continuing candidate sets visited, and are deterministic.
+fn test_search_stats() {
+    let votes = get_votes();
+    let stats = |record_search_stats:bool| raire_with_options(&votes,Some(C),&AUDIT,TrimAlgorithm::None,&RaireOptions{use_neb_prepass:false,record_search_stats,..Default::default()},&mut TimeOut::never()).unwrap().stats;
+    assert_eq!(None,stats(false)); // only if requested.
+    let first = stats(true).unwrap();
+    assert_eq!(SearchStats{visited_continuing_sets:12,total_continuing_sets:16},first); // 2^4 sets, fewer than the 4!=24 elimination orders.
+    assert_eq!(Some(first),stats(true));
+}
+
+/// Parse a DIMACS CNF formula over 16 variables, checking it is well formed, and count the elimination orders of 4 candidates
+/// satisfying it, interpreting variable `c*4+k+1` as candidate `c` being in position `k`.
+fn count_orders_satisfying_cnf(cnf:&str) -> usize {
+    let mut lines = cnf.lines().filter(|line|!line.starts_with('c'));
+    let header : Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+    assert_eq!(vec!["p","cnf","16"],header[..3]);
+    let clauses : Vec<Vec<i64>> = lines.map(|line|{
+        let literals : Vec<i64> = line.split_whitespace().map(|l|l.parse().unwrap()).collect();
+        assert_eq!(Some(&0),literals.last());
+        literals[..literals.len()-1].to_vec()
+    }).collect();
+    assert_eq!(header[3].parse::<usize>().unwrap(),clauses.len());
+    for clause in &clauses { for &literal in clause { assert!(literal!=0 && literal.abs()<=16); } }
+    all_elimination_orders(4).into_iter().filter(|order|{
+        let is_true = |literal:i64| { let v = (literal.abs()-1) as usize; (order[v%4].0 as usize==v/4)==(literal>0) };
+        clauses.iter().all(|clause|clause.iter().any(|&literal|is_true(literal)))
+    }).count()
+}
+
+#[test]
+/// Check the DIMACS CNF encoding is well formed, and that no elimination order satisfies it, as the assertions prove the winner.
+/// Any satisfying assignment has to be a permutation, so it suffices to check all elimination orders.
+fn test_to_cnf() {
+    let result = raire(&get_votes(),Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    assert_eq!(0,count_orders_satisfying_cnf(&result.to_cnf().unwrap()));
+    // without the assertions, the 18 elimination orders not ending in C satisfy it.
+    let no_assertions = RaireResult{assertions:vec![],..result.clone()};
+    assert_eq!(18,count_orders_satisfying_cnf(&no_assertions.to_cnf().unwrap()));
+    // a result claiming fewer candidates than its assertions mention is rejected.
+    let too_few_candidates = RaireResult{num_candidates:3,..result};
+    assert!(matches!(too_few_candidates.to_cnf(),Err(RaireError::InvalidCandidateNumber)));
+}
+
+#[test]
+/// Test the per-candidate safety margins.
+fn test_candidate_safety_margins() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let margins = result.candidate_safety_margins(&votes);
+    // Chuan beats Alice by 500 with {Alice,Chuan} continuing, Bob by 4000 as an NEB, and Diego by 2500 with {Alice,Chuan,Diego} continuing.
+    assert_eq!(vec![Some(BallotPaperCount(500)),Some(BallotPaperCount(4000)),None,Some(BallotPaperCount(2500))],margins);
+}
+
+#[test]
+/// Test solving once with both trim algorithms.
+fn test_solve_both_trims() {
+    let problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan","Diego"]
+        }),
+        num_candidates : 4,
+        votes : get_votes().votes,
+        winner : Some(C),
+        audit : Audit::OneOnMargin(AUDIT),
+        ..Default::default()
+    };
+    let (minimize_tree,minimize_assertions) = problem.solve_both_trims();
+    let minimize_tree = minimize_tree.solution.unwrap();
+    let minimize_assertions = minimize_assertions.solution.unwrap();
+    assert_eq!(C,minimize_tree.winner);
+    assert_eq!(C,minimize_assertions.winner);
+    assert_eq!(6,minimize_tree.assertions.len());
+    assert!(minimize_assertions.assertions.len()<=minimize_tree.assertions.len());
+    minimize_tree.verify_result_does_prove_winner().unwrap();
+    minimize_assertions.verify_result_does_prove_winner().unwrap();
+    assert_eq!(minimize_tree.assertions,RaireProblem{trim_algorithm:Some(TrimAlgorithm::MinimizeTree),..problem.clone()}.solve().solution.unwrap().assertions);
+}
+
+#[test]
+/// Test that solving once with both trim algorithms gives the same results as solving twice,
+/// including the tallies and truncation requested in the problem.
+fn test_solve_both_trims_same_as_solve() {
+    let problem = RaireProblem {
+        num_candidates : 4,
+        votes : get_votes().votes,
+        winner : Some(C),
+        audit : Audit::OneOnMargin(AUDIT),
+        include_tallies : Some(true),
+        max_assertions_returned : Some(3),
+        ..Default::default()
+    };
+    let (minimize_tree,minimize_assertions) = problem.solve_both_trims();
+    for (both,trim_algorithm) in [(minimize_tree,TrimAlgorithm::MinimizeTree),(minimize_assertions,TrimAlgorithm::MinimizeAssertions)] {
+        let both = both.solution.unwrap();
+        let once = RaireProblem{trim_algorithm:Some(trim_algorithm),..problem.clone()}.solve().solution.unwrap();
+        assert!(both.assertions.iter().all(|a|a.tallies.is_some()));
+        assert_eq!(once.assertions,both.assertions);
+        assert_eq!(once.truncated,both.truncated);
+        assert_eq!(once.difficulty,both.difficulty);
+        assert_eq!(once.margin,both.margin);
+    }
+}
+
+#[test]
+/// Test the top two runoff. Chuan and Alice lead on first preferences with 5000 and 4000;
+/// in the runoff Chuan gets 5000+1000 (from Bob) and Alice gets 4000+1500 (from Diego).
+/// There is no runoff with only one candidate.
+fn test_top_two_runoff() {
+    assert_eq!(Some((C,A,BallotPaperCount(500))),get_votes().top_two_runoff());
+    let one_candidate = Votes::new(vec![Vote{n:BallotPaperCount(10),prefs:vec![A]}],1).unwrap();
+    assert_eq!(None,one_candidate.top_two_runoff());
+}
+
+#[test]
+/// Test capping the number of assertions returned for display.
+fn test_max_assertions_returned() {
+    let problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan","Diego"]
+        }),
+        num_candidates : 4,
+        votes : get_votes().votes,
+        winner : Some(C),
+        audit : Audit::OneOnMargin(AUDIT),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeTree),
+        max_assertions_returned: Some(3),
+        ..Default::default()
+    };
+    let truncated = problem.clone().solve().solution.unwrap();
+    assert!(truncated.truncated);
+    assert_eq!(3,truncated.assertions.len());
+    assert_eq!(vec![27.0,27.0,5.4],truncated.assertions.iter().map(|a|a.difficulty).collect::<Vec<_>>());
+    assert_eq!(27.0,truncated.difficulty);
+    assert!(truncated.verify_result_does_prove_winner().is_err());
+    // a cap at least the number of assertions changes nothing.
+    let not_truncated = RaireProblem{max_assertions_returned:Some(6),..problem}.solve().solution.unwrap();
+    assert!(!not_truncated.truncated);
+    assert_eq!(6,not_truncated.assertions.len());
+}
+
+#[test]
+/// Test that a NEN assertion whose winner is not continuing is never found, and is infinitely difficult.
+fn test_nen_winner_not_continuing() {
+    let votes = get_votes();
+    assert!(NotEliminatedNext::find_best_difficulty(&votes,&AUDIT,&[A,B,D],C).is_none());
+    assert!(NotEliminatedNext::find_best_difficulty(&votes,&AUDIT,&[A,B,C,D],C).is_some());
+    assert_eq!(f64::INFINITY,NotEliminatedNext{winner:C,loser:A,continuing:vec![A,B,D]}.difficulty(&votes,&AUDIT));
+}
+
+#[test]
+/// Test that the assertions accepted in the decision log are the (untrimmed) assertions in the result.
+fn test_raire_with_log() {
+    for use_neb_prepass in [false,true] {
+        let (result,log) = raire_with_log(&get_votes(),Some(C),&AUDIT,TrimAlgorithm::None,&RaireOptions{use_neb_prepass,..Default::default()},&mut TimeOut::never()).unwrap();
+        assert!(log.decisions.iter().any(|d|d.kind==DecisionKind::Expanded));
+        let mut accepted : Vec<String> = log.accepted_assertions().iter().map(|a|format!("{:?}",a)).collect();
+        let mut assertions : Vec<String> = result.assertions.iter().map(|a|format!("{:?}",a)).collect();
+        accepted.sort();
+        assertions.sort();
+        assert_eq!(assertions,accepted);
+    }
+}
+
+#[test]
+/// Test grouping assertions by winner. In the MinimizeTree solution Chuan wins 3 assertions and Alice 3.
+fn test_assertions_by_winner() {
+    let result = raire(&get_votes(),Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let by_winner = result.assertions_by_winner();
+    assert_eq!(3,by_winner[&C].len());
+    assert_eq!(3,by_winner[&A].len());
+    assert_eq!(2,by_winner.len());
+    for (winner,indices) in by_winner {
+        for index in indices { assert_eq!(winner,result.assertions[index].assertion.winner()); }
+    }
+}
+
+#[test]
+/// Check that the winner does not depend upon the confidence, but the difficulty does.
+fn test_winner_stable_across_confidence() {
+    let problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan","Diego"]
+        }),
+        num_candidates : 4,
+        votes : get_votes().votes,
+        audit : Audit::MACRO(BallotComparisonMACRO{ confidence: 0.05, error_inflation_factor: 1.1, total_auditable_ballots: AUDIT.total_auditable_ballots }),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeTree),
+        ..Default::default()
+    };
+    let confidences = [0.01,0.05,0.1];
+    assert!(winner_stable_across_confidence(&problem,&confidences));
+    let difficulties : Vec<f64> = solve_across_confidence(&problem,&confidences).into_iter().map(|s|s.solution.unwrap().difficulty).collect();
+    assert!(difficulties[0]>difficulties[1]);
+    assert!(difficulties[1]>difficulties[2]);
+}
+
+#[test]
+/// Test the extra assertions useful for escalation. This should be just A1 in the TrimAlgorithm documentation.
+fn test_escalation_assertions() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::None,&mut TimeOut::never()).unwrap();
+    let escalation = result.escalation_assertions(&votes,&Audit::OneOnMargin(AUDIT));
+    assert_eq!(1,escalation.len());
+    assert_eq!(Assertion::NEN(NotEliminatedNext{ winner: A, loser: D, continuing: vec![A,D] }),escalation[0].assertion);
+    assert_eq!(BallotPaperCount(4500),escalation[0].margin);
+    assert!((escalation[0].difficulty-3.0).abs()<0.001);
+    let minimal = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never()).unwrap();
+    assert!(minimal.escalation_assertions(&votes,&Audit::OneOnMargin(AUDIT)).is_empty());
+}
+
+#[test]
+/// Test a batch of two copies of the same contest, one with its audit overridden.
+fn test_solve_batch_with_overrides() {
+    let problem = RaireProblem {
+        metadata : json!({
+            "candidates" : ["Alice","Bob","Chuan","Diego"]
+        }),
+        num_candidates : 4,
+        votes : get_votes().votes,
+        audit : Audit::OneOnMargin(AUDIT),
+        trim_algorithm: Some(TrimAlgorithm::MinimizeTree),
+        ..Default::default()
+    };
+    let squared = Audit::OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared::new(AUDIT.total_auditable_ballots));
+    let batch = vec![BatchEntry{problem:problem.clone(),audit_override:None},BatchEntry{problem,audit_override:Some(squared)}];
+    let difficulties : Vec<f64> = solve_batch_with_overrides(batch).into_iter().map(|s|s.solution.unwrap().difficulty).collect();
+    assert!((difficulties[0]-27.0).abs()<0.001);
+    assert!((difficulties[1]-729.0).abs()<0.001);
+}
+
+#[test]
+/// Test solving for both a ballot comparison and a ballot polling audit at once gives the same as solving each separately.
+fn test_solve_multi() {
+    let problem : RaireProblem = serde_json::from_value(json!({
+        "num_candidates" : 4,
+        "votes" : get_votes().votes,
+        "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 13500 },
+        "include_provenance" : true
+    })).unwrap();
+    let squared = Audit::OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared::new(AUDIT.total_auditable_ballots));
+    let audits = vec![Audit::OneOnMargin(AUDIT),squared.clone()];
+    let solutions = problem.solve_multi(&audits);
+    assert_eq!(2,solutions.len());
+    let difficulties : Vec<f64> = solutions.iter().map(|s|s.solution.as_ref().unwrap().difficulty).collect();
+    assert!((difficulties[0]-27.0).abs()<0.001);
+    assert!((difficulties[1]-729.0).abs()<0.001);
+    let separately = RaireProblem{audit:squared,..problem.clone()}.solve();
+    assert_eq!(separately.solution.unwrap().assertions,solutions[1].solution.as_ref().unwrap().assertions);
+    assert_eq!(separately.provenance.unwrap().problem_sha256,solutions[1].provenance.as_ref().unwrap().problem_sha256);
+    // An error determining the winner applies to every audit type.
+    let wrong_winner = RaireProblem{winner:Some(A),..problem}.solve_multi(&audits);
+    assert!(wrong_winner.iter().all(|s|matches!(s.solution,Err(RaireError::WrongWinner(_)))));
+}
+
+#[test]
+/// Test the decisiveness of first preferences. Only Chuan beats anyone by NEB, Bob with 4000 (5000-1000) votes.
+fn test_first_preference_decisiveness() {
+    let votes = get_votes();
+    let decisiveness = votes.first_preference_decisiveness();
+    assert_eq!(vec![0.0,0.0,4000.0/13500.0,0.0],decisiveness);
+    let winner = (0..4).max_by(|&a,&b|decisiveness[a].partial_cmp(&decisiveness[b]).unwrap()).unwrap();
+    assert_eq!(C.0 as usize,winner);
+}
+
+#[test]
+/// Test the rows of the assertion explainer's table are in a stable order, most difficult first, with all columns populated.
+fn test_explainer_rows() {
+    let votes = get_votes();
+    let names : Vec<String> = ["Alice","Bob","Chuan","Diego"].iter().map(|s|s.to_string()).collect();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let rows = result.explainer_rows(&names,&votes);
+    assert_eq!(result.assertions.len(),rows.len());
+    assert_eq!(rows,result.explainer_rows(&names,&votes));
+    let mut indices : Vec<usize> = rows.iter().map(|r|r.index).collect();
+    indices.sort();
+    assert_eq!((0..result.assertions.len()).collect::<Vec<_>>(),indices);
+    assert!(rows.windows(2).all(|w|w[0].difficulty>=w[1].difficulty));
+    assert_eq!(result.difficulty,rows[0].difficulty);
+    for row in &rows {
+        let assertion = &result.assertions[row.index];
+        assert_eq!(if assertion.assertion.is_neb() {"NEB"} else {"NEN"},row.assertion_type);
+        assert_eq!(assertion.assertion.plain_english(&names,&votes),row.text);
+        assert!(!row.text.is_empty());
+        assert_eq!(assertion.margin,row.margin);
+        assert_eq!(assertion.difficulty,row.difficulty);
+        assert_eq!(None,row.risk);
+    }
+}
+
+#[test]
+/// Test the length of the shortest chains of eliminations ruling out each candidate. Bob is ruled out by Chuan NEB Bob.
+/// Alice is ruled out when Bob is eliminated second last by Chuan NEB Bob, and Diego when Alice is eliminated second
+/// last by Alice beating Diego when only they remain, each a chain of two eliminations.
+fn test_elimination_chain_length() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let lengths = result.elimination_chain_length();
+    assert_eq!(vec![Some(2),Some(1),None,Some(2)],lengths);
+    for (candidate,kind) in result.elimination_arguments() {
+        if kind==ArgumentKind::SingleNEB { assert_eq!(Some(1),lengths[candidate.0 as usize]); }
+        else { assert!(lengths[candidate.0 as usize].unwrap()>1); }
+    }
+}
+
+#[test]
+/// Test the difficulty curve. The binding assertion has a margin of 500, which is erased by shifting 250 ballots.
+fn test_difficulty_curve() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let curve = result.difficulty_curve(&votes,&Audit::OneOnMargin(AUDIT),11,BallotPaperCount(500));
+    assert_eq!(11,curve.len());
+    assert_eq!((BallotPaperCount(0),27.0),curve[0]);
+    assert_eq!((BallotPaperCount(50),33.75),curve[1]); // 13500/400
+    assert_eq!(BallotPaperCount(500),curve[10].0);
+    assert!(curve.windows(2).all(|w|w[0].1<=w[1].1));
+    assert!(curve[4].1.is_finite());
+    assert_eq!(f64::INFINITY,curve[5].1);
+}
+
+#[test]
+/// Test that required assertions are included in the output even if redundant, and that invalid ones are rejected.
+fn test_required_assertions() {
+    let problem : RaireProblem = serde_json::from_value(json!({
+        "num_candidates" : 4,
+        "votes" : get_votes().votes,
+        "audit" : { "type" : "OneOnMargin", "total_auditable_ballots" : 13500 },
+        "trim_algorithm" : "MinimizeAssertions"
+    })).unwrap();
+    let with_required = |required:Vec<Assertion>|RaireProblem{required_assertions:required,..problem.clone()};
+    // Diego beats Bob 7500 to 6000 if only they are left. True, but not needed, as Chuan can't be eliminated before Bob.
+    let redundant = Assertion::NEN(NotEliminatedNext{winner:D,loser:B,continuing:vec![D,B]});
+    let canonical = Assertion::NEN(NotEliminatedNext{winner:D,loser:B,continuing:vec![B,D]});
+    assert!(!problem.clone().solve().solution.unwrap().assertions.iter().any(|a|a.assertion==canonical));
+    let solution = with_required(vec![redundant.clone()]).solve().solution.unwrap();
+    assert!(solution.assertions.iter().any(|a|a.assertion==canonical));
+    assert_eq!(27.0,solution.difficulty);
+    solution.verify_result_does_prove_winner().unwrap();
+    let (minimize_tree,minimize_assertions) = with_required(vec![redundant]).solve_both_trims();
+    for solution in [minimize_tree,minimize_assertions] {
+        assert!(solution.solution.unwrap().assertions.iter().any(|a|a.assertion==canonical));
+    }
+    let error = |required:Assertion|with_required(vec![required]).solve().solution.unwrap_err();
+    assert!(matches!(error(Assertion::NEB(NotEliminatedBefore{winner:B,loser:C})),RaireError::AssertionDoesNotHold(0)));
+    assert!(matches!(error(Assertion::NEB(NotEliminatedBefore{winner:A,loser:A})),RaireError::InvalidAssertion));
+    assert!(matches!(error(Assertion::NEB(NotEliminatedBefore{winner:A,loser:CandidateIndex(7)})),RaireError::InvalidCandidateNumber));
+}
@@ -31,6 +31,8 @@ fn test_folder(folder:&str) {
         if file_name.ends_with(".json")&& !file_name.ends_with("_out.json") {
             println!("Processing {:?}",file_name);
             let problem : RaireProblem = serde_json::from_reader(File::open(&entry.path()).unwrap()).unwrap();
+            let mut problem_with_prepass = problem.clone();
+            problem_with_prepass.use_neb_prepass=Some(true);
             let solution = problem.solve();
             let solution_file : PathBuf = PathBuf::from(folder).join(format!("{}_out.json",file_name.strip_suffix(".json").unwrap()));
             // The line below was originally used to create the expected output. See header comment about this being a poor test case.
@@ -41,6 +43,8 @@ fn test_folder(folder:&str) {
             let computed_difficulty = solution.solution.as_ref().unwrap().difficulty;
             println!("Expected difficulty for {} : {}, computed difficulty : {}",file_name,expected_difficulty,computed_difficulty);
             assert!((expected_difficulty-computed_difficulty).abs()<0.001);
+            // The NEB pre-pass is an optimization that does not change the difficulty, and for these contests does not change the assertions either.
+            assert_eq!(solution.solution.unwrap().assertions,problem_with_prepass.solve().solution.unwrap().assertions);
         }
     }
 }
@@ -11,11 +11,12 @@
 //! Test the examples given in https://arxiv.org/pdf/1903.08804.pdf
 
 
-use raire::assertions::{NotEliminatedNext, SpecificLoserAmongstContinuing, NotEliminatedBefore};
-use raire::audit_type::{BallotComparisonMACRO, BallotPollingBRAVO};
+use raire::assertions::{NotEliminatedNext, SpecificLoserAmongstContinuing, NotEliminatedBefore, Assertion, AssertionAndDifficulty, NotEliminatedBeforeCache};
+use raire::audit_type::{Audit, AuditType, BallotComparisonMACRO, BallotComparisonOneOnDilutedMargin, BallotPollingBRAVO, BayesianAudit, TwoPhaseAudit};
 use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
-use raire::raire_algorithm::{raire, TrimAlgorithm};
+use raire::raire_algorithm::{raire, raire_with_options, FrontierOrdering, RaireOptions, TrimAlgorithm};
 use raire::timeout::TimeOut;
+use raire::RaireError;
 
 /// Get the votes in table 1.
 fn get_votes_in_table1() -> Votes {
@@ -127,6 +128,19 @@ fn test_example2() {
 }
 
 
+#[test]
+/// Check that a smaller risk limit makes assertions more difficult, and the original confidence reproduces the difficulty.
+fn test_difficulty_at_confidence() {
+    let (winner,loser) = (BallotPaperCount(30000),BallotPaperCount(26000));
+    assert_eq!(BRAVO_EG1.difficulty(winner,loser),BRAVO_EG1.difficulty_at_confidence(winner,loser,BRAVO_EG1.confidence));
+    assert_eq!(MACRO.difficulty(winner,loser),MACRO.difficulty_at_confidence(winner,loser,MACRO.confidence));
+    let confidences = [0.2,0.1,0.05,0.01,0.001];
+    let bravo : Vec<f64> = confidences.iter().map(|&confidence|BRAVO_EG1.difficulty_at_confidence(winner,loser,confidence)).collect();
+    let macro_difficulties : Vec<f64> = confidences.iter().map(|&confidence|MACRO.difficulty_at_confidence(winner,loser,confidence)).collect();
+    assert!(bravo.windows(2).all(|w|w[0]<w[1]));
+    assert!(macro_difficulties.windows(2).all(|w|w[0]<w[1]));
+}
+
 #[test]
 /// Check the final ASN for example 3 in the paper
 fn test_example3() {
@@ -366,4 +380,275 @@ fn test_example12_raire_macro() {
         let winning_candidate = e.last().cloned();
         assert_eq!(Some(CandidateIndex(0)),winning_candidate);
     }
-}
\ No newline at end of file
+}
+
+#[test]
+/// Test the number of ballots needed to flip assertions in example 12, compared to hand computed tallies.
+fn test_example12_flipping_ballots() {
+    let votes = get_votes_for_example12();
+    // c1 has 15500 votes vs c2 11500 when only they are continuing. Margin 4000.
+    let assertion1 = Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(1), continuing: vec![CandidateIndex(0), CandidateIndex(1)] });
+    assert_eq!((BallotPaperCount(15500),BallotPaperCount(11500)),assertion1.tallies(&votes));
+    assert_eq!(BallotPaperCount(2000),assertion1.flipping_ballots(&votes));
+    // c1 has 15000 votes vs c3 5500 with c1,c2,c3 continuing. Margin 9500.
+    let assertion2 = Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(1), CandidateIndex(2)] });
+    assert_eq!(BallotPaperCount(4750),assertion2.flipping_ballots(&votes));
+    // c1 has 10000 first preferences vs c4 5000. Margin 5000.
+    let assertion3 = Assertion::NEB(NotEliminatedBefore { winner:CandidateIndex(0), loser: CandidateIndex(3) });
+    assert_eq!(BallotPaperCount(2500),assertion3.flipping_ballots(&votes));
+    // c2 has 22000 votes vs c4 5000 when only they are continuing. Margin 17000.
+    let assertion4 = Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(1), loser: CandidateIndex(3), continuing: vec![CandidateIndex(1), CandidateIndex(3)] });
+    assert_eq!(BallotPaperCount(8500),assertion4.flipping_ballots(&votes));
+    // c4 does not beat c1, so it is already false.
+    let assertion5 = Assertion::NEB(NotEliminatedBefore { winner:CandidateIndex(3), loser: CandidateIndex(0) });
+    assert_eq!(BallotPaperCount(0),assertion5.flipping_ballots(&votes));
+}
+
+#[test]
+/// The binding assertion in example 12 is c1 beating c2 when only they remain, with a margin of 4000, so 2000 ballots need to be altered.
+fn test_example12_critical_ballot_count() {
+    let votes = get_votes_for_example12();
+    let res = raire(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    assert_eq!(BallotPaperCount(4000),res.binding_assertion().unwrap().margin);
+    assert_eq!(BallotPaperCount(2000),res.critical_ballot_count(&votes));
+}
+
+#[test]
+/// Test that the frontier ordering does not affect the difficulty of the result for example 12.
+fn test_example12_frontier_ordering() {
+    let votes = get_votes_for_example12();
+    for use_neb_prepass in [false,true] {
+        let best_first = raire_with_options(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::MinimizeTree,&RaireOptions{use_neb_prepass,..Default::default()},&mut TimeOut::never()).unwrap();
+        let length_weighted = raire_with_options(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::MinimizeTree,&RaireOptions{use_neb_prepass,frontier_ordering:FrontierOrdering::LengthWeighted,..Default::default()},&mut TimeOut::never()).unwrap();
+        assert!((best_first.difficulty-44.49).abs()<0.01);
+        assert_eq!(best_first.difficulty,length_weighted.difficulty);
+        assert!(best_first.time_to_find_assertions.work>0);
+        assert!(length_weighted.time_to_find_assertions.work>0);
+        length_weighted.verify_result_does_prove_winner().unwrap();
+    }
+}
+
+#[test]
+/// Test that the margin based difficulty bound is a lower bound on the difficulty found by RAIRE.
+fn test_margin_based_difficulty_bound() {
+    fn check<A:AuditType>(votes:&Votes,audit:&A) {
+        let bound = votes.margin_based_difficulty_bound(audit);
+        let res = raire(votes,None,audit,TrimAlgorithm::None,&mut TimeOut::never()).unwrap();
+        assert!(bound>0.0);
+        assert!(bound<=res.difficulty);
+    }
+    check(&get_votes_in_table1(),&BRAVO_EG1);
+    check(&get_votes_in_table1(),&MACRO);
+    check(&get_votes_for_example5(),&BRAVO_EG5);
+    check(&get_votes_for_example5(),&MACRO_EG5);
+    check(&get_votes_for_example9(),&BRAVO_EG5);
+    check(&get_votes_for_example9(),&MACRO_EG5);
+    check(&get_votes_for_example12(),&BRAVO_EG12);
+    check(&get_votes_for_example12(),&MACRO_EG12);
+}
+
+#[test]
+/// Test that rescoring a NEB cache for a different audit type gives the same result as building it from scratch.
+fn test_neb_cache_rescore() {
+    let votes = get_votes_in_table1();
+    let mut cache = NotEliminatedBeforeCache::new(&votes,&BRAVO_EG1);
+    cache.rescore(&votes,&MACRO);
+    assert_eq!(NotEliminatedBeforeCache::new(&votes,&MACRO).cache,cache.cache);
+    assert_ne!(NotEliminatedBeforeCache::new(&votes,&BRAVO_EG1).cache,cache.cache);
+    let votes = get_votes_for_example5(); // different number of candidates
+    cache.rescore(&votes,&BRAVO_EG5);
+    assert_eq!(NotEliminatedBeforeCache::new(&votes,&BRAVO_EG5).cache,cache.cache);
+}
+
+#[test]
+/// Test recording the assertions considered during the search on a tiny contest.
+fn test_record_considered_assertions() {
+    let votes = get_votes_for_example9();
+    let without = raire_with_options(&votes,None,&MACRO_EG5,TrimAlgorithm::MinimizeTree,&RaireOptions{use_neb_prepass:false,..Default::default()},&mut TimeOut::never()).unwrap();
+    assert!(without.considered.is_empty());
+    let with = raire_with_options(&votes,None,&MACRO_EG5,TrimAlgorithm::MinimizeTree,&RaireOptions{use_neb_prepass:false,record_considered_assertions:true,..Default::default()},&mut TimeOut::never()).unwrap();
+    assert_eq!(without.difficulty,with.difficulty);
+    assert_eq!(without.assertions,with.assertions);
+    for a in &with.assertions {
+        assert!(with.considered.contains(a),"{:?} not in considered",a);
+    }
+    assert!(with.considered.len()>with.assertions.len());
+}
+
+#[test]
+/// Test the Bayesian audit type on example 12, comparing the ordering of assertions with MACRO.
+fn test_example12_bayesian() {
+    let bayesian = BayesianAudit{ upset_prior: 0.05, total_auditable_ballots: BallotPaperCount(27000) };
+    // s=0.6, so z²s(1-s)/(s-½)² = 24z² where z≈1.6449 is the 95% quantile of the standard normal.
+    assert!((BayesianAudit{ upset_prior: 0.05, total_auditable_ballots: BallotPaperCount(100) }.difficulty(BallotPaperCount(60),BallotPaperCount(40))-64.94).abs()<0.01);
+    assert!(bayesian.difficulty(BallotPaperCount(40),BallotPaperCount(40)).is_infinite());
+    let votes = get_votes_for_example12();
+    let macro_result = raire(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let bayesian_result = raire(&votes,Some(CandidateIndex(0)),&bayesian,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    // The MACRO assertions are ranked similarly by both audit types. The exception is NEB(c1,c4), which MACRO ranks as
+    // harder than NEN(c1,c3|{c1,c3}) as it has a smaller margin, but the Bayesian audit ranks as easier as the winner's share is larger.
+    let rank = |difficulty:&dyn Fn(&Assertion)->f64| {
+        let mut assertions : Vec<Assertion> = macro_result.assertions.iter().map(|a|a.assertion.clone()).collect();
+        assertions.sort_by(|a,b|difficulty(a).partial_cmp(&difficulty(b)).unwrap());
+        assertions
+    };
+    let by_macro = rank(&|a|{ let (w,l) = a.tallies(&votes); MACRO_EG12.difficulty(w,l) });
+    let by_bayesian = rank(&|a|{ let (w,l) = a.tallies(&votes); bayesian.difficulty(w,l) });
+    let c1 = CandidateIndex(0);
+    let c2 = CandidateIndex(1);
+    let c3 = CandidateIndex(2);
+    let c4 = CandidateIndex(3);
+    let nen = |winner,loser,continuing| Assertion::NEN(NotEliminatedNext{winner,loser,continuing});
+    let neb = Assertion::NEB(NotEliminatedBefore{winner:c1,loser:c4});
+    assert_eq!(vec![nen(c1,c3,vec![c1,c2,c3]),nen(c1,c3,vec![c1,c3]),neb.clone(),nen(c1,c2,vec![c1,c2])],by_macro);
+    assert_eq!(vec![nen(c1,c3,vec![c1,c2,c3]),neb,nen(c1,c3,vec![c1,c3]),nen(c1,c2,vec![c1,c2])],by_bayesian);
+    // and the hardest assertion is the same.
+    assert_eq!(macro_result.binding_assertion().unwrap().assertion,bayesian_result.binding_assertion().unwrap().assertion);
+    let (w,l) = bayesian_result.binding_assertion().unwrap().assertion.tallies(&votes);
+    assert_eq!(bayesian.difficulty(w,l),bayesian_result.difficulty);
+    // check the JSON tag
+    let audit : Audit = serde_json::from_str(r#"{"type":"Bayesian","upset_prior":0.05,"total_auditable_ballots":27000}"#).unwrap();
+    assert_eq!(bayesian_result.difficulty,audit.difficulty(w,l));
+    // The upset prior must be strictly between 0 and 0.5.
+    for upset_prior in [0.0,0.5,0.9,-0.1,f64::NAN] {
+        assert!(matches!(BayesianAudit::new(upset_prior,BallotPaperCount(27000)),Err(RaireError::InvalidUpsetPrior(_))));
+    }
+    assert!(serde_json::from_str::<Audit>(r#"{"type":"Bayesian","upset_prior":0.5,"total_auditable_ballots":27000}"#).is_err());
+    // An audit made directly with an invalid prior gives an undefined difficulty rather than failing to terminate.
+    assert!(BayesianAudit{ upset_prior: f64::NAN, total_auditable_ballots: BallotPaperCount(100) }.difficulty(BallotPaperCount(60),BallotPaperCount(40)).is_nan());
+}
+
+#[test]
+/// Test rescoring the assertions found for MACRO under BRAVO, which should be no better than what RAIRE finds directly for BRAVO.
+fn test_example12_difficulty_under() {
+    let votes = get_votes_for_example12();
+    let macro_result = raire(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let bravo_result = raire(&votes,Some(CandidateIndex(0)),&BRAVO_EG12,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    assert_eq!(macro_result.difficulty,macro_result.difficulty_under(&votes,&Audit::MACRO(MACRO_EG12)));
+    let macro_assertions_under_bravo = macro_result.difficulty_under(&votes,&Audit::BRAVO(BRAVO_EG12));
+    assert!(macro_assertions_under_bravo>=bravo_result.difficulty);
+    assert!(macro_assertions_under_bravo>macro_result.difficulty); // ballot polling is harder than ballot comparison.
+}
+
+#[test]
+/// Test that auto accepting easy assertions reduces the work in the search, and still proves the winner.
+fn test_example9_auto_accept_difficulty() {
+    let votes = get_votes_for_example9();
+    let solve = |auto_accept_difficulty:Option<f64>| raire_with_options(&votes,None,&MACRO_EG5,TrimAlgorithm::MinimizeTree,&RaireOptions{use_neb_prepass:false,auto_accept_difficulty,..Default::default()},&mut TimeOut::never()).unwrap();
+    let optimal = solve(None);
+    let auto_accepted = solve(Some(100.0));
+    auto_accepted.verify_result_does_prove_winner().unwrap();
+    assert!(auto_accepted.time_to_find_assertions.work<optimal.time_to_find_assertions.work);
+    assert!(auto_accepted.difficulty>optimal.difficulty); // slightly worse, as an assertion was accepted without looking for a better one.
+    assert!(auto_accepted.difficulty<=100.0);
+}
+
+#[test]
+/// Test the risk headroom during an audit: clean samples increase it, overstatements decrease it, and sampling the
+/// MACRO difficulty's worth of clean ballots gives approximately 1.
+fn test_example12_risk_headroom() {
+    let votes = get_votes_for_example12();
+    let audit = Audit::MACRO(MACRO_EG12);
+    let result = raire(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let hardest = result.assertions.iter().max_by(|a,b|a.difficulty.total_cmp(&b.difficulty)).unwrap();
+    let headroom = |sampled:usize,overstatements:u32| hardest.assertion.risk_headroom(&votes,&audit,BallotPaperCount(sampled),overstatements);
+    assert_eq!(0.0,headroom(0,0));
+    assert!(headroom(10,0)>0.0);
+    assert!(headroom(20,0)>headroom(10,0));
+    assert!(headroom(20,1)<headroom(20,0));
+    let at_difficulty = headroom(hardest.difficulty.round() as usize,0);
+    assert!((at_difficulty-1.0).abs()<0.05);
+    assert!(hardest.assertion.risk_headroom(&votes,&Audit::BRAVO(BRAVO_EG12),BallotPaperCount(10),0).is_nan());
+}
+
+#[test]
+/// Test the expected fraction of ballots to examine for example 12 with BRAVO.
+fn test_example12_expected_sample_fraction() {
+    let votes = get_votes_for_example12();
+    let res = raire(&votes,Some(CandidateIndex(0)),&BRAVO_EG12,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let fraction = res.expected_sample_fraction(&Audit::BRAVO(BRAVO_EG12),votes.total_votes()).unwrap();
+    assert!(fraction>0.0 && fraction<=1.0);
+    assert!((fraction-278.25/27000.0).abs()<0.0001);
+    assert_eq!(None,res.expected_sample_fraction(&Audit::BRAVO(BRAVO_EG12),BallotPaperCount(0)));
+    assert_eq!(None,res.expected_sample_fraction(&Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(votes.total_votes())),votes.total_votes()));
+}
+
+#[test]
+/// Test a two phase audit for example 12, starting with BRAVO and escalating to MACRO.
+fn test_example12_two_phase() {
+    let votes = get_votes_for_example12();
+    let audit = Audit::TwoPhase(TwoPhaseAudit{initial:Box::new(Audit::BRAVO(BRAVO_EG12)),escalation:Box::new(Audit::MACRO(MACRO_EG12))});
+    let res = raire(&votes,Some(CandidateIndex(0)),&audit,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    assert!((res.difficulty -278.25).abs()<0.01); // same as BRAVO alone.
+    let Audit::TwoPhase(two_phase) = &audit else { panic!() };
+    let difficulties = two_phase.assertion_difficulties(&res.assertions,&votes);
+    assert_eq!(res.assertions.len(),difficulties.len());
+    for (assertion,&(initial,escalation)) in res.assertions.iter().zip(difficulties.iter()) {
+        assert!((assertion.difficulty-initial).abs()<1e-9);
+        assert!((initial-escalation).abs()>1.0);
+    }
+}
+
+/// The assertions whose ballot polling ASNs are given for example 12 in the paper.
+fn example12_bravo_assertions() -> [Assertion;4] {
+    [
+        Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(1), continuing: vec![CandidateIndex(0), CandidateIndex(1)] }),
+        Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(2)] }),
+        Assertion::NEB(NotEliminatedBefore { winner:CandidateIndex(0), loser: CandidateIndex(3) }),
+        Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(1), CandidateIndex(2)] }),
+    ]
+}
+
+/// The assertions whose ballot comparison ASNs are given for example 12 in the paper.
+fn example12_macro_assertions() -> [Assertion;6] {
+    [
+        Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(1), continuing: vec![CandidateIndex(0), CandidateIndex(1)] }),
+        Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(1), CandidateIndex(2)] }),
+        Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(2)] }),
+        Assertion::NEB(NotEliminatedBefore { winner:CandidateIndex(0), loser: CandidateIndex(3) }),
+        Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(1), loser: CandidateIndex(3), continuing: vec![CandidateIndex(1), CandidateIndex(3)] }),
+        Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(2), loser: CandidateIndex(3), continuing: vec![CandidateIndex(2), CandidateIndex(3)] }),
+    ]
+}
+
+/// The assertion with its difficulty and margin for the given votes and audit.
+fn scored(assertion:Assertion,votes:&Votes,audit:&Audit) -> AssertionAndDifficulty {
+    let (difficulty,margin) = assertion.difficulty(votes,audit);
+    AssertionAndDifficulty{assertion,margin,difficulty,tallies:None}
+}
+
+#[test]
+/// Check the margins and sample sizes as percentages for the assertions in example 12 whose ASNs are checked in [test_example12_asns].
+/// Every ballot ranks Alice, so the continuing ballots for BRAVO are all 27000 ballots when she is continuing, and the
+/// percentages are the same as the diluted margins.
+fn test_example12_margin_percent() {
+    let votes = get_votes_for_example12();
+    let bravo = Audit::BRAVO(BRAVO_EG12);
+    let macro_audit = Audit::MACRO(MACRO_EG12);
+    let margin_percents = |assertions:&[Assertion],audit:&Audit| -> Vec<f64> { assertions.iter().map(|a|scored(a.clone(),&votes,audit).margin_percent(&votes,audit)).collect() };
+    // Alice 15500 vs Bob 11500; Alice 16500 vs Chuan 10500; Alice's 10000 first preferences vs 5000 preferring Diego; Alice 15000 vs Chuan 5500.
+    let expected_bravo = [4000.0,6000.0,5000.0,9500.0].map(|margin|100.0*margin/27000.0);
+    for (expected,actual) in expected_bravo.iter().zip(margin_percents(&example12_bravo_assertions(),&bravo)) { assert!((expected-actual).abs()<1e-9,"expected {expected} got {actual}"); }
+    // as above, then Bob 22000 vs Diego 5000 and Chuan 22000 vs Diego 5000 (every ballot ranks Bob or Diego, and Chuan or Diego).
+    let expected_macro = [4000.0,9500.0,6000.0,5000.0,17000.0,17000.0].map(|margin|100.0*margin/27000.0);
+    for (expected,actual) in expected_macro.iter().zip(margin_percents(&example12_macro_assertions(),&macro_audit)) { assert!((expected-actual).abs()<1e-9,"expected {expected} got {actual}"); }
+    for (expected,actual) in expected_macro.iter().zip(margin_percents(&example12_macro_assertions(),&bravo)) { assert!((expected-actual).abs()<1e-9,"expected {expected} got {actual}"); }
+    // With only Bob and Chuan continuing, the 5000 ballots ranking just Diego and Alice are exhausted, so for BRAVO
+    // Bob's margin of 11500-10500 is out of the 22000 continuing ballots, rather than all 27000.
+    let bob_vs_chuan = scored(Assertion::NEN(NotEliminatedNext { winner:CandidateIndex(1), loser: CandidateIndex(2), continuing: vec![CandidateIndex(1), CandidateIndex(2)] }),&votes,&bravo);
+    assert!((bob_vs_chuan.margin_percent(&votes,&bravo)-100.0*1000.0/22000.0).abs()<1e-9);
+    assert!((bob_vs_chuan.margin_percent(&votes,&macro_audit)-100.0*1000.0/27000.0).abs()<1e-9);
+    // The expected sample sizes as percentages match the ASN percentages in [test_example12_asns].
+    let sample_size_percents = |assertions:&[Assertion],audit:&Audit| -> Vec<f64> { assertions.iter().map(|a|scored(a.clone(),&votes,audit).sample_size_percent(audit,votes.total_votes()).unwrap()).collect() };
+    for (expected,actual) in [1.0,0.5,0.4,0.1].iter().zip(sample_size_percents(&example12_bravo_assertions(),&bravo)) { assert!((expected-actual).abs()<0.1,"expected {expected} got {actual}"); }
+    for (expected,actual) in [0.17,0.07,0.11,0.13,0.04,0.04].iter().zip(sample_size_percents(&example12_macro_assertions(),&macro_audit)) { assert!((expected-actual).abs()<0.01,"expected {expected} got {actual}"); }
+}
+
+#[test]
+/// Check the distinct sets of continuing candidates in the assertions for example 12 with MACRO.
+fn test_example12_distinct_continuing_sets() {
+    let votes = get_votes_for_example12();
+    let res = raire(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::MinimizeTree,&mut TimeOut::never()).unwrap();
+    let (c1,c2,c3) = (CandidateIndex(0),CandidateIndex(1),CandidateIndex(2));
+    assert_eq!(vec![vec![c1,c2],vec![c1,c2,c3],vec![c1,c3]],res.distinct_continuing_sets());
+}
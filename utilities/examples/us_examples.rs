@@ -12,7 +12,7 @@
 
 use std::time::SystemTime;
 use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
-use raire::irv::BallotPaperCount;
+use raire::irv::{BallotPaperCount, SkippedRankRule};
 use raire::raire_algorithm::TrimAlgorithm;
 use utilities::parse_michelle_format::Contest;
 use utilities::table_of_results::TableOfResults;
@@ -25,11 +25,11 @@ fn main() -> anyhow::Result<()> {
     for entry in std::fs::read_dir(folder)? {
         let entry = entry?;
         if entry.file_name().to_string_lossy().ends_with(".raire") {
-            let contests = Contest::parse(&entry.path())?;
+            let contests = Contest::parse(&entry.path(),SkippedRankRule::default())?;
             for contest_index in 0..contests.len() {
                 let contest = &contests[contest_index];
                 let num_ballots : usize = contest.votes.values().sum();
-                let mut problem = contests[contest_index].to_raire_problem(Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{total_auditable_ballots:BallotPaperCount(num_ballots)}))?;
+                let mut problem = contests[contest_index].to_raire_problem(Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(num_ballots))))?;
                 for (trim,table) in vec![TrimAlgorithm::None,TrimAlgorithm::MinimizeTree,TrimAlgorithm::MinimizeAssertions].into_iter().zip(summaries.iter_mut()) {
                     let mut problem = problem.clone();
                     problem.trim_algorithm=Some(trim);
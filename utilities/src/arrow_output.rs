@@ -0,0 +1,69 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Write RAIRE results in the Arrow columnar (IPC) file format, convenient for loading thousands of contests into data frames.
+//! Only available with the `arrow` feature.
+
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use arrow::array::{ArrayRef, Float64Array, StringArray, UInt32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use raire::assertions::Assertion;
+use raire::raire_algorithm::RaireResult;
+
+/// Write the assertions in a set of results (each labeled with a contest name) to an Arrow IPC file, with one row per assertion.
+/// The columns are `contest`, `type` (`NEB` or `NEN`), `winner`, `loser`, `difficulty` and `margin`.
+pub fn write_assertions_arrow(results:&[(String,RaireResult)],path:&Path) -> anyhow::Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("contest",DataType::Utf8,false),
+        Field::new("type",DataType::Utf8,false),
+        Field::new("winner",DataType::UInt32,false),
+        Field::new("loser",DataType::UInt32,false),
+        Field::new("difficulty",DataType::Float64,false),
+        Field::new("margin",DataType::UInt64,false),
+    ]));
+    let rows : Vec<(&str,&raire::assertions::AssertionAndDifficulty)> = results.iter().flat_map(|(contest,result)|result.assertions.iter().map(move |a|(contest.as_str(),a))).collect();
+    let columns : Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(rows.iter().map(|(contest,_)|*contest))),
+        Arc::new(StringArray::from_iter_values(rows.iter().map(|(_,a)|match a.assertion { Assertion::NEB(_) => "NEB", Assertion::NEN(_) => "NEN" }))),
+        Arc::new(UInt32Array::from_iter_values(rows.iter().map(|(_,a)|a.assertion.winner().0))),
+        Arc::new(UInt32Array::from_iter_values(rows.iter().map(|(_,a)|a.assertion.loser().0))),
+        Arc::new(Float64Array::from_iter_values(rows.iter().map(|(_,a)|a.difficulty))),
+        Arc::new(UInt64Array::from_iter_values(rows.iter().map(|(_,a)|a.margin.0 as u64))),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(),columns)?;
+    let mut writer = FileWriter::try_new(File::create(path)?,&schema)?;
+    writer.write(&batch)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::ipc::reader::FileReader;
+    use crate::random_contest::generate_contest;
+
+    #[test]
+    fn test_write_and_read_back() {
+        let results : Vec<(String,RaireResult)> = (0..3).map(|seed|(format!("Contest {seed}"),generate_contest(seed,5,1000,3).solve().solution.unwrap())).collect();
+        let path = std::env::temp_dir().join("raire_test_write_assertions_arrow.arrow");
+        write_assertions_arrow(&results,&path).unwrap();
+        let reader = FileReader::try_new(File::open(&path).unwrap(),None).unwrap();
+        let batches : Vec<RecordBatch> = reader.collect::<Result<_,_>>().unwrap();
+        let num_rows : usize = batches.iter().map(|b|b.num_rows()).sum();
+        assert_eq!(results.iter().map(|(_,r)|r.assertions.len()).sum::<usize>(),num_rows);
+        assert_eq!(6,batches[0].num_columns());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -13,9 +13,8 @@ use std::fs::File;
 use std::path::PathBuf;
 
 use clap::{Parser};
-use raire::irv::{CandidateIndex};
 use raire::{RaireSolution};
-use raire::assertions::Assertion;
+use utilities::describe::{AssertionOrder, describe_solution};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -23,33 +22,15 @@ use raire::assertions::Assertion;
 struct CliOptions {
     /// The output from RAIRE
     input_file : PathBuf,
+    /// The order in which to list the assertions.
+    #[arg(long,value_enum,default_value_t)]
+    sort : AssertionOrder,
 }
 
 
 fn main() -> anyhow::Result<()> {
     let args = CliOptions::parse();
     let input : RaireSolution = serde_json::from_reader(File::open(&args.input_file)?)?;
-    let name = |c:CandidateIndex| {
-        if let Some(metadata_name) = input.metadata["candidates"][c.0 as usize].as_str() {
-            metadata_name.to_string()
-        } else {
-            format!("#{}",c.0)
-        }
-    };
-    match &input.solution {
-        Ok(solution) => {
-            println!("Solution overall difficulty {}",solution.difficulty);
-            for a in &solution.assertions {
-                match &a.assertion {
-                    Assertion::NEB(neb) => print!("{} NEB {}",name(neb.winner),name(neb.loser)),
-                    Assertion::NEN(nen) => print!("{} > {} with {:?} continuing",name(nen.winner),name(nen.loser),nen.continuing.iter().cloned().map(name).collect::<Vec<_>>()),
-                }
-                println!("  Difficulty {}",a.difficulty);
-            }
-        }
-        Err(e) => {
-            println!("Could not find a solution because {:?}",e)
-        }
-    }
+    print!("{}",describe_solution(&input,args.sort));
     Ok(())
 }
\ No newline at end of file
@@ -15,7 +15,7 @@ use anyhow::anyhow;
 
 use clap::{Parser};
 use raire::audit_type::{Audit, BallotComparisonMACRO, BallotComparisonOneOnDilutedMargin, BallotPollingBRAVO, BallotPollingOneOnDilutedMarginSquared};
-use raire::irv::BallotPaperCount;
+use raire::irv::{BallotPaperCount, SkippedRankRule};
 use utilities::parse_michelle_format::Contest;
 
 #[derive(Parser)]
@@ -43,11 +43,15 @@ struct CliOptions {
     /// the error_inflation_factor (for MACRO).
     #[arg(long)]
     error_inflation_factor : Option<f64>,
+    /// set if a ballot with a skipped rank should exhaust at that rank (default is to skip over it, using later preferences)
+    #[arg(long)]
+    exhaust_at_skipped_rank : bool,
 }
 
 fn main() -> anyhow::Result<()> {
     let args = CliOptions::parse();
-    let input : Vec<Contest> = Contest::parse(&args.input_raire_file)?;
+    let skipped_rank_rule = if args.exhaust_at_skipped_rank { SkippedRankRule::Exhaust } else { SkippedRankRule::SkipOver };
+    let input : Vec<Contest> = Contest::parse(&args.input_raire_file,skipped_rank_rule)?;
     let output = {
         let index = args.contest.unwrap_or(1);
         if index<1 || index>input.len() { return Err(anyhow!("Contest number must be between 1 and {}",input.len()))}
@@ -56,8 +60,8 @@ fn main() -> anyhow::Result<()> {
         println!("{num_ballots} ballots of which {} are unique",contest.votes.len());
         let total_auditable_ballots = BallotPaperCount(args.total_ballots.unwrap_or(num_ballots));
         let audit : Audit = match (args.ballot_polling,args.confidence) {
-            (false,None) => Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots }),
-            (true,None) => Audit::OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared{ total_auditable_ballots }),
+            (false,None) => Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(total_auditable_ballots)),
+            (true,None) => Audit::OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared::new(total_auditable_ballots)),
             (false,Some(confidence)) => Audit::MACRO(BallotComparisonMACRO{total_auditable_ballots,confidence,error_inflation_factor:args.error_inflation_factor.unwrap_or(1.0)}),
             (true,Some(confidence)) => Audit::BRAVO(BallotPollingBRAVO{total_auditable_ballots,confidence}),
         };
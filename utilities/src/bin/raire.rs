@@ -9,8 +9,10 @@
 
 use std::fs::File;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use clap::{Parser};
+use raire::raire_algorithm::SearchCheckpoint;
 use raire::RaireProblem;
 
 #[derive(Parser)]
@@ -21,12 +23,39 @@ struct CliOptions {
     input_json_file : PathBuf,
     /// The file to store the output. Default is the input file name, with path and extension if present removed and `_out.json` added.
     output_json_file : Option<PathBuf>,
+    /// If present, the state of the search is saved to this file every `checkpoint_interval` seconds, so that a long run can be continued with `--resume` if interrupted.
+    #[arg(long)]
+    checkpoint : Option<PathBuf>,
+    /// The number of seconds between saving checkpoints.
+    #[arg(long,default_value_t=60.0)]
+    checkpoint_interval : f64,
+    /// Continue the search from a checkpoint file saved by `--checkpoint` for the same input file.
+    #[arg(long)]
+    resume : Option<PathBuf>,
 }
 
 fn main() -> anyhow::Result<()> {
     let args = CliOptions::parse();
     let input : RaireProblem = serde_json::from_reader(File::open(&args.input_json_file)?)?;
-    let output = input.solve();
+    let output = if args.checkpoint.is_some() || args.resume.is_some() {
+        let resume_from : Option<SearchCheckpoint> = match &args.resume {
+            Some(path) => Some(serde_json::from_reader(File::open(path)?)?),
+            None => None,
+        };
+        let interval = if args.checkpoint.is_some() { Duration::try_from_secs_f64(args.checkpoint_interval)? } else { Duration::MAX };
+        input.solve_with_checkpoints(resume_from.as_ref(),interval,&mut |checkpoint|{
+            if let Some(path) = &args.checkpoint {
+                // write to a temporary file and rename, so an interruption never leaves a half written checkpoint.
+                let temporary = path.with_extension("tmp");
+                let saved = (||->anyhow::Result<()>{
+                    serde_json::to_writer(File::create(&temporary)?,checkpoint)?;
+                    std::fs::rename(&temporary,path)?;
+                    Ok(())
+                })();
+                if let Err(e) = saved { eprintln!("Could not save checkpoint to {}: {}",path.display(),e); }
+            }
+        })
+    } else { input.solve() };
     let output_file : PathBuf = args.output_json_file.unwrap_or_else(||{
         let mut stem = args.input_json_file.file_stem().map(|s|PathBuf::from(s)).unwrap_or_else(||PathBuf::from("output"));
         stem.as_mut_os_string().push("_out.json");
@@ -0,0 +1,47 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Recompute the difficulties of the assertions in a previously computed solution for a different audit type,
+//! for instance if the risk limit or number of auditable ballots has changed since the assertions were generated.
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::{Parser};
+use raire::{RaireProblem, RaireSolution};
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This reads the output of a RAIRE computation and recomputes the difficulty of each assertion for the audit type
+/// in a RAIRE problem, without searching for assertions again.
+struct CliOptions {
+    /// The output from RAIRE
+    solution_json_file : PathBuf,
+    /// The JSON file containing the command to RAIRE, with the (possibly changed) audit type. The votes should be the same as used to produce the solution.
+    problem_json_file : PathBuf,
+    /// The file to store the output. Default is the solution file name, with path and extension if present removed and `_rescored.json` added.
+    output_json_file : Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let mut solution : RaireSolution = serde_json::from_reader(File::open(&args.solution_json_file)?)?;
+    let problem : RaireProblem = serde_json::from_reader(File::open(&args.problem_json_file)?)?;
+    match &mut solution.solution {
+        Ok(result) => problem.rescore(result)?,
+        Err(e) => anyhow::bail!("The solution does not contain any assertions : {}",e),
+    }
+    let output_file : PathBuf = args.output_json_file.unwrap_or_else(||{
+        let mut stem = args.solution_json_file.file_stem().map(PathBuf::from).unwrap_or_else(||PathBuf::from("output"));
+        stem.as_mut_os_string().push("_rescored.json");
+        stem
+    });
+    serde_json::to_writer(File::create(&output_file)?,&solution)?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::{Parser};
+use raire::solve_batch;
+use utilities::solve_folder::{problem_files_in_folder, read_problem_file, solve_files_to_ndjson};
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// Solve all the RAIRE problems (JSON files) in a folder, in order of file name.
+struct CliOptions {
+    /// The folder containing the JSON files. Files ending in `_out.json` are ignored.
+    input_folder : PathBuf,
+    /// The folder to store the outputs, each the input file name with `_out.json` instead of `.json`. Default is the input folder.
+    #[arg(long)]
+    output_folder : Option<PathBuf>,
+    /// Instead of writing an output file for each input, write each solution to standard output as a line of JSON (NDJSON)
+    /// as soon as it is computed.
+    #[arg(long)]
+    ndjson : bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let files = problem_files_in_folder(&args.input_folder)?;
+    if args.ndjson {
+        solve_files_to_ndjson(&files,&mut std::io::stdout().lock())?;
+    } else {
+        let output_folder = args.output_folder.as_ref().unwrap_or(&args.input_folder);
+        for (file,output) in files.iter().zip(solve_batch(files.iter().map(|file|read_problem_file(file)))) {
+            let mut name = file.file_stem().map(|s|s.to_os_string()).unwrap_or_default();
+            name.push("_out.json");
+            serde_json::to_writer(File::create(output_folder.join(name))?,&output)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Produce a human readable description of the output of RAIRE. Used by the describe program.
+
+
+use std::fmt::Write;
+use raire::assertions::{Assertion, AssertionAndDifficulty};
+use raire::irv::CandidateIndex;
+use raire::RaireSolution;
+
+/// The order in which to list assertions.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,clap::ValueEnum)]
+pub enum AssertionOrder {
+    /// Most difficult first.
+    Difficulty,
+    /// NEB assertions before NEN assertions, otherwise as stored.
+    Type,
+    /// The order stored in the solution.
+    #[default]
+    Canonical,
+}
+
+/// Describe a solution in human readable form, one line per assertion.
+pub fn describe_solution(input:&RaireSolution,order:AssertionOrder) -> String {
+    let metadata = input.contest_metadata();
+    let name = |c:CandidateIndex| metadata.candidate_name(c);
+    let mut res = String::new();
+    match &input.solution {
+        Ok(solution) => {
+            writeln!(res,"Solution overall difficulty {}",solution.difficulty).unwrap();
+            let assertions : Vec<&AssertionAndDifficulty> = match order {
+                AssertionOrder::Difficulty => solution.assertions_by_difficulty(),
+                AssertionOrder::Type => solution.assertions.iter().filter(|a|a.assertion.is_neb()).chain(solution.assertions.iter().filter(|a|!a.assertion.is_neb())).collect(),
+                AssertionOrder::Canonical => solution.assertions.iter().collect(),
+            };
+            for a in assertions {
+                match &a.assertion {
+                    Assertion::NEB(neb) => write!(res,"{} NEB {}",name(neb.winner),name(neb.loser)).unwrap(),
+                    Assertion::NEN(nen) => write!(res,"{} > {} with {:?} continuing",name(nen.winner),name(nen.loser),nen.continuing.iter().cloned().map(name).collect::<Vec<_>>()).unwrap(),
+                }
+                writeln!(res,"  Difficulty {}",a.difficulty).unwrap();
+            }
+        }
+        Err(e) => {
+            writeln!(res,"Could not find a solution because {:?}",e).unwrap();
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
+    use raire::irv::{BallotPaperCount, CandidateIndex, Vote};
+    use raire::RaireProblem;
+    use crate::describe::{AssertionOrder, describe_solution};
+
+    /// The example from "A guide to RAIRE", solved.
+    fn guide_solution() -> raire::RaireSolution {
+        let (a,b,c,d) = (CandidateIndex(0),CandidateIndex(1),CandidateIndex(2),CandidateIndex(3));
+        RaireProblem {
+            metadata: json!({"candidates" : ["Alice","Bob","Chuan","Diego"]}),
+            num_candidates: 4,
+            votes: vec![
+                Vote{ n: BallotPaperCount(5000), prefs: vec![c,b,a]},
+                Vote{ n: BallotPaperCount(1000), prefs: vec![b,c,d]},
+                Vote{ n: BallotPaperCount(1500), prefs: vec![d,a]},
+                Vote{ n: BallotPaperCount(4000), prefs: vec![a,d]},
+                Vote{ n: BallotPaperCount(2000), prefs: vec![d]},
+            ],
+            winner: Some(c),
+            audit: Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(13500))),
+            ..Default::default()
+        }.solve()
+    }
+
+    /// Extract the difficulties of each assertion from the description.
+    fn difficulties(description:&str) -> Vec<f64> {
+        description.lines().filter_map(|line|line.split("  Difficulty ").nth(1)).map(|d|d.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_sort_by_difficulty() {
+        let solution = guide_solution();
+        let canonical = describe_solution(&solution,AssertionOrder::Canonical);
+        let by_difficulty = describe_solution(&solution,AssertionOrder::Difficulty);
+        println!("{}",by_difficulty);
+        let canonical_difficulties = difficulties(&canonical);
+        let sorted_difficulties = difficulties(&by_difficulty);
+        assert_eq!(canonical_difficulties.len(),sorted_difficulties.len());
+        assert!(sorted_difficulties.windows(2).all(|w|w[0]>=w[1]));
+        assert_ne!(canonical_difficulties,sorted_difficulties);
+        let by_type = describe_solution(&solution,AssertionOrder::Type);
+        assert!(by_type.lines().nth(1).unwrap().contains(" NEB "));
+    }
+}
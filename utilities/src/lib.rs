@@ -8,4 +8,9 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 pub mod parse_michelle_format;
-pub mod table_of_results;
\ No newline at end of file
+pub mod table_of_results;
+pub mod describe;
+pub mod random_contest;
+pub mod solve_folder;
+#[cfg(feature = "arrow")]
+pub mod arrow_output;
\ No newline at end of file
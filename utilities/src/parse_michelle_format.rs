@@ -12,12 +12,12 @@
 
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::Read;
 use std::path::Path;
 use anyhow::anyhow;
 use serde_json::json;
 use raire::audit_type::Audit;
-use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
+use raire::irv::{BallotPaperCount, CandidateIndex, SkippedRankRule, Vote, Votes};
 use raire::RaireProblem;
 use raire::timeout::TimeOut;
 
@@ -32,37 +32,41 @@ pub struct Contest {
 
 
 impl Contest {
-    pub fn parse<P: AsRef<Path>>(path:P) -> anyhow::Result<Vec<Contest>> {
+    /// Parse contests from a file. See [Self::parse_reader].
+    pub fn parse<P: AsRef<Path>>(path:P,skipped_rank_rule:SkippedRankRule) -> anyhow::Result<Vec<Contest>> {
         let file_name_stem = path.as_ref().file_name().and_then(|s|s.to_str()).unwrap_or_default().trim_end_matches(".raire").to_string();
-        let mut lines = BufReader::new(File::open(path)?).lines();
+        Self::parse_reader(&file_name_stem,File::open(path)?,skipped_rank_rule)
+    }
+
+    /// Parse contests from a reader. Fields may be quoted, so candidate names may contain commas, e.g. `"Smith, John"`.
+    /// An empty field in a ballot is a skipped rank, dealt with by `skipped_rank_rule`.
+    pub fn parse_reader<R:Read>(file_name_stem:&str,reader:R,skipped_rank_rule:SkippedRankRule) -> anyhow::Result<Vec<Contest>> {
+        let mut lines = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(reader).into_records();
         // first line is number of contests
-        let num_contests : usize = lines.next().ok_or_else(||anyhow!("No number of contests on first line"))??.parse()?;
+        let num_contests : usize = lines.next().ok_or_else(||anyhow!("No number of contests on first line"))??.get(0).ok_or_else(||anyhow!("No number of contests on first line"))?.parse()?;
         println!("File contains {num_contests} contests.");
         let mut res = vec![];
         for i in 0..num_contests {
-            let line = lines.next().ok_or_else(||anyhow!("Missing contest {}",i+1))??;
-            let fields = line.split(',').collect::<Vec<_>>();
+            let fields = lines.next().ok_or_else(||anyhow!("Missing contest {}",i+1))??;
             // first field is typically "Contest" then and id then number of candidates, then candidate names
             if fields.len()<3 { return Err(anyhow!("Contest {} doesn't have enough fields",i+1)); }
             let id = fields[1].to_string();
             let num_candidates : usize = fields[2].parse()?;
             let candidate_names : Vec<String> = if fields.len()>=3+num_candidates {
-                fields[3..(3+num_candidates)].iter().map(|s|s.to_string()).collect()
+                fields.iter().skip(3).take(num_candidates).map(|s|s.to_string()).collect()
             } else { return Err(anyhow!("Candidate ids missing")); };
             let candidate_name_to_index : HashMap<String,CandidateIndex> = candidate_names.iter().enumerate().map(|(n,name)|(name.clone(),CandidateIndex(n as u32))).collect();
-            res.push(Contest{ file_name_stem:file_name_stem.clone(), num_candidates,id,candidate_names,candidate_name_to_index,votes:Default::default()});
+            res.push(Contest{ file_name_stem:file_name_stem.to_string(), num_candidates,id,candidate_names,candidate_name_to_index,votes:Default::default()});
         }
         // rest of lines are contest,ballot_id,candidates (starting from 1)
         for line in lines {
             let line = line?;
-            let mut fields = line.split(',');
+            let mut fields = line.iter();
             if let Some(contest_id) = fields.next() {
                 if let Some(contest) = res.iter_mut().find(|c|c.id.as_str()==contest_id) {
                     if let Some(_ballot_id) = fields.next() {
-                        let remaining = fields.collect::<Vec<_>>();
-                        let candidates : Vec<CandidateIndex> = if remaining.len()==1 && remaining[0].is_empty() { vec![] } else {
-                            remaining.iter().map(|&s|*contest.candidate_name_to_index.get(s).expect("Expected integer candidate id")).collect()
-                        };
+                        let ranks : Vec<Option<CandidateIndex>> = fields.map(|s|if s.is_empty() { Ok(None) } else { contest.candidate_name_to_index.get(s).map(|&c|Some(c)).ok_or_else(||anyhow!("Unknown candidate {s} in contest {contest_id}")) }).collect::<anyhow::Result<_>>()?;
+                        let candidates = Votes::normalize_skipped_ranks(&ranks,skipped_rank_rule);
                         *contest.votes.entry(candidates).or_insert(0)+=1;
                     }
                 }
@@ -86,9 +90,51 @@ impl Contest {
             votes: votes.votes,
             winner: Some(winner),
             audit,
-            trim_algorithm: None,
-            difficulty_estimate: None,
-            time_limit_seconds: None,
+            ..Default::default()
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Candidate names containing commas should be quoted, and parsed correctly.
+    fn test_quoted_candidate_names() {
+        let file = "1\nContest,1,3,\"Smith, John\",Jones,\"O'Brien, \"\"Pat\"\"\"\n1,1,\"Smith, John\",Jones\n1,2,Jones\n1,3,\"O'Brien, \"\"Pat\"\"\",\"Smith, John\"\n1,4,\n";
+        let contests = Contest::parse_reader("test",file.as_bytes(),SkippedRankRule::default()).unwrap();
+        assert_eq!(1,contests.len());
+        let contest = &contests[0];
+        assert_eq!(vec!["Smith, John".to_string(),"Jones".to_string(),"O'Brien, \"Pat\"".to_string()],contest.candidate_names);
+        assert_eq!(Some(&1),contest.votes.get(&vec![CandidateIndex(0),CandidateIndex(1)]));
+        assert_eq!(Some(&1),contest.votes.get(&vec![CandidateIndex(1)]));
+        assert_eq!(Some(&1),contest.votes.get(&vec![CandidateIndex(2),CandidateIndex(0)]));
+        assert_eq!(Some(&1),contest.votes.get(&vec![]));
+    }
+
+    #[test]
+    /// A ballot with a skipped rank under both rules.
+    fn test_skipped_ranks() {
+        let file = "1\nContest,1,3,A,B,C\n1,1,A,,B\n1,2,,C\n1,3,C,B,\n";
+        let parse = |rule:SkippedRankRule| Contest::parse_reader("test",file.as_bytes(),rule).unwrap().remove(0).votes;
+        let skip_over = parse(SkippedRankRule::SkipOver);
+        assert_eq!(Some(&1),skip_over.get(&vec![CandidateIndex(0),CandidateIndex(1)]));
+        assert_eq!(Some(&1),skip_over.get(&vec![CandidateIndex(2)]));
+        assert_eq!(Some(&1),skip_over.get(&vec![CandidateIndex(2),CandidateIndex(1)]));
+        let exhaust = parse(SkippedRankRule::Exhaust);
+        assert_eq!(Some(&1),exhaust.get(&vec![CandidateIndex(0)]));
+        assert_eq!(Some(&1),exhaust.get(&vec![]));
+        assert_eq!(Some(&1),exhaust.get(&vec![CandidateIndex(2),CandidateIndex(1)])); // trailing blanks are not a skipped rank.
+    }
+
+    #[test]
+    /// A ballot with a candidate not in the contest is an error.
+    fn test_unknown_candidate() {
+        let file = "1\nContest,1,2,A,B\n1,1,A,C\n";
+        match Contest::parse_reader("test",file.as_bytes(),SkippedRankRule::default()) {
+            Err(error) => assert_eq!("Unknown candidate C in contest 1",error.to_string()),
+            Ok(_) => panic!("Expecting an error"),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Generate reproducible pseudo-random contests, useful for tests and benchmarks.
+
+
+use std::collections::BTreeMap;
+use serde_json::json;
+use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
+use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
+use raire::RaireProblem;
+use raire::timeout::TimeOut;
+
+/// A small, fast, deterministic pseudo-random number generator (SplitMix64).
+/// Used rather than an external crate so that the contests generated for a given seed never change.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+    /// A uniform number in the range [0,1).
+    fn next_f64(&mut self) -> f64 { (self.next_u64()>>11) as f64/(1u64<<53) as f64 }
+}
+
+/// Generate a pseudo-random contest with the given number of candidates and ballots, where each ballot
+/// ranks between 1 and `max_rank` candidates (inclusive, capped at the number of candidates).
+/// The same arguments always produce the same contest.
+///
+/// Each candidate is given a random popularity, and preferences are drawn in proportion to popularity,
+/// so contests are generally not close. However it is possible for the winner to be tied, in which case
+/// the winner in the returned problem is `None`, and solving it will produce a `TiedWinners` error.
+///
+/// The audit is `OneOnMargin` with all ballots auditable.
+pub fn generate_contest(seed:u64,num_candidates:usize,num_ballots:usize,max_rank:usize) -> RaireProblem {
+    let mut rng = SplitMix64(seed);
+    let popularity : Vec<f64> = (0..num_candidates).map(|_|rng.next_f64()+0.01).collect();
+    let max_rank = max_rank.clamp(1,num_candidates.max(1));
+    let mut ballots : BTreeMap<Vec<u32>,usize> = BTreeMap::new();
+    for _ in 0..num_ballots {
+        let rank = 1+(rng.next_u64()%max_rank as u64) as usize;
+        let mut remaining : Vec<u32> = (0..num_candidates as u32).collect();
+        let mut prefs : Vec<u32> = vec![];
+        while prefs.len()<rank && !remaining.is_empty() {
+            let total : f64 = remaining.iter().map(|&c|popularity[c as usize]).sum();
+            let mut choice = rng.next_f64()*total;
+            let mut index = remaining.len()-1;
+            for (i,&c) in remaining.iter().enumerate() {
+                choice-=popularity[c as usize];
+                if choice<0.0 { index=i; break; }
+            }
+            prefs.push(remaining.remove(index));
+        }
+        *ballots.entry(prefs).or_insert(0)+=1;
+    }
+    let votes : Vec<Vote> = ballots.into_iter().map(|(prefs,n)|Vote{ n: BallotPaperCount(n), prefs: prefs.into_iter().map(CandidateIndex).collect() }).collect();
+    let winner = Votes::new(votes.clone(),num_candidates).ok().and_then(|v|v.run_election(&mut TimeOut::never()).ok()).and_then(|result|if result.possible_winners.len()==1 { Some(result.possible_winners[0]) } else { None });
+    let candidates : Vec<String> = (0..num_candidates).map(|c|format!("Candidate {}",c+1)).collect();
+    RaireProblem{
+        metadata: json!({"candidates":candidates,"contest":format!("Random contest seed {seed}")}),
+        num_candidates,
+        votes,
+        winner,
+        audit: Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin::new(BallotPaperCount(num_ballots))),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_contest() {
+        let problem1 = generate_contest(42,6,1000,4);
+        let problem2 = generate_contest(42,6,1000,4);
+        assert_eq!(serde_json::to_string(&problem1).unwrap(),serde_json::to_string(&problem2).unwrap());
+        assert_eq!(1000,problem1.votes.iter().map(|v|v.n.0).sum::<usize>());
+        assert!(problem1.votes.iter().all(|v|(1..=4).contains(&v.prefs.len())));
+        let problem3 = generate_contest(43,6,1000,4);
+        assert_ne!(serde_json::to_string(&problem1).unwrap(),serde_json::to_string(&problem3).unwrap());
+        let solution = problem1.solve().solution.unwrap();
+        solution.verify_result_does_prove_winner().unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Solve all the RAIRE problems in a folder, for batch runs.
+
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use raire::{parse_batch_contest, solve_batch, RaireError, RaireProblem};
+
+/// The JSON files in a folder, sorted by name, which is the order in which they are solved.
+/// Files ending in `_out.json` are assumed to be outputs and are excluded.
+pub fn problem_files_in_folder(folder:&Path) -> io::Result<Vec<PathBuf>> {
+    let mut res = vec![];
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n|n.to_str()).unwrap_or_default();
+        if path.is_file() && name.ends_with(".json") && !name.ends_with("_out.json") { res.push(path); }
+    }
+    res.sort();
+    Ok(res)
+}
+
+/// Read the problem in `file`, for [solve_batch]. A file that cannot be read, or is not a valid problem, gives a
+/// [RaireError::InvalidContest] error, so that the other files can still be solved.
+pub fn read_problem_file(file:&Path) -> Result<RaireProblem,(RaireError,serde_json::Value)> {
+    let invalid = |e:String|(RaireError::InvalidContest(format!("{} : {e}",file.display())),serde_json::Value::Null);
+    let reader = BufReader::new(File::open(file).map_err(|e|invalid(e.to_string()))?);
+    let contest : serde_json::Value = serde_json::from_reader(reader).map_err(|e|invalid(e.to_string()))?;
+    parse_batch_contest(&serde_json::Value::Null,&contest)
+}
+
+/// Solve each of the `files`, in order, writing each solution to `writer` as a single line of JSON (NDJSON) as soon
+/// as it is computed, and flushing, so that downstream processors can consume the results while the batch is running.
+pub fn solve_files_to_ndjson<W:Write>(files:&[PathBuf],writer:&mut W) -> anyhow::Result<()> {
+    for solution in solve_batch(files.iter().map(|file|read_problem_file(file))) {
+        serde_json::to_writer(&mut *writer,&solution)?;
+        writeln!(writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_contest::generate_contest;
+
+    #[test]
+    fn test_solve_files_to_ndjson() {
+        let folder = std::env::temp_dir().join(format!("raire_solve_folder_test_{}",std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        for seed in 0..3 {
+            let mut problem = generate_contest(seed,4,1000,3);
+            problem.metadata = serde_json::json!({"contest":format!("Contest {seed}")});
+            serde_json::to_writer(File::create(folder.join(format!("contest{seed}.json"))).unwrap(),&problem).unwrap();
+        }
+        File::create(folder.join("contest0_out.json")).unwrap();
+        std::fs::write(folder.join("contest9.json"),"not a problem").unwrap();
+        let files = problem_files_in_folder(&folder).unwrap();
+        assert_eq!(4,files.len());
+        let mut buffer : Vec<u8> = vec![];
+        solve_files_to_ndjson(&files,&mut buffer).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        let lines : Vec<&str> = text.lines().collect();
+        assert_eq!(4,lines.len());
+        let invalid : raire::RaireSolution = serde_json::from_str(lines[3]).unwrap();
+        assert!(matches!(invalid.solution,Err(RaireError::InvalidContest(_))));
+        for (seed,line) in lines.iter().take(3).enumerate() {
+            let solution : raire::RaireSolution = serde_json::from_str(line).unwrap();
+            assert_eq!(serde_json::json!({"contest":format!("Contest {seed}")}),solution.metadata);
+        }
+    }
+}
@@ -9,6 +9,8 @@
 
 
 
+use std::io;
+use std::io::Write;
 use raire::{RaireSolution};
 
 pub struct SingleResultSummary {
@@ -19,7 +21,7 @@ pub struct SingleResultSummary {
 
 impl SingleResultSummary {
     pub fn new(solution:RaireSolution) -> Self {
-        let name = solution.metadata["contest"].to_string();
+        let name = solution.contest_metadata().contest_name.unwrap_or_default();
         SingleResultSummary{name,solution}
     }
 }
@@ -58,4 +60,58 @@ impl TableOfResults {
             println!();
         }
     }
-}
\ No newline at end of file
+}
+
+/// Write a CSV file with one row per contest, suitable for spreadsheets. Columns are name, num_candidates, winner, difficulty,
+/// margin, num_assertions, solve_time (total seconds), trim_timed_out, and error. For a contest that could not be
+/// solved, only the name and error are filled in.
+pub fn write_summary_csv(results:&[(String,RaireSolution)],writer:impl Write) -> io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(["name","num_candidates","winner","difficulty","margin","num_assertions","solve_time","trim_timed_out","error"])?;
+    for (name,solution) in results {
+        match &solution.solution {
+            Ok(result) => {
+                let solve_time = result.time_to_determine_winners.seconds+result.time_to_find_assertions.seconds+result.time_to_trim_assertions.seconds;
+                csv.write_record([name.clone(),result.num_candidates.to_string(),result.winner.0.to_string(),result.difficulty.to_string(),result.margin.0.to_string(),result.assertions.len().to_string(),solve_time.to_string(),result.warning_trim_timed_out.to_string(),String::new()])?;
+            }
+            Err(e) => { csv.write_record([name,"","","","","","","",&e.to_string()])?; }
+        }
+    }
+    csv.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raire::{RaireError, RaireProblem};
+    use crate::random_contest::generate_contest;
+
+    #[test]
+    fn test_write_summary_csv() {
+        let mut results : Vec<(String,RaireSolution)> = (0..2).map(|seed|(format!("Contest {seed}"),generate_contest(seed,4,1000,3).solve())).collect();
+        results.push(("Timed out".to_string(),RaireProblem{work_limit:Some(1),..generate_contest(2,4,1000,3)}.solve()));
+        let mut buffer : Vec<u8> = vec![];
+        write_summary_csv(&results,&mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        let lines : Vec<&str> = text.lines().collect();
+        assert_eq!(4,lines.len());
+        assert_eq!("name,num_candidates,winner,difficulty,margin,num_assertions,solve_time,trim_timed_out,error",lines[0]);
+        for ((name,solution),line) in results[..2].iter().zip(&lines[1..3]) {
+            let result = solution.solution.as_ref().unwrap();
+            let fields : Vec<&str> = line.split(',').collect();
+            assert_eq!(9,fields.len());
+            assert_eq!(name,fields[0]);
+            assert_eq!("4",fields[1]);
+            assert_eq!(result.winner.0.to_string(),fields[2]);
+            assert_eq!(result.difficulty,fields[3].parse::<f64>().unwrap());
+            assert_eq!(result.margin.0.to_string(),fields[4]);
+            assert_eq!(result.assertions.len().to_string(),fields[5]);
+            assert_eq!("false",fields[7]);
+            assert_eq!("",fields[8]);
+        }
+        // only the name and error are given for a contest that could not be solved.
+        let error = results[2].1.solution.as_ref().unwrap_err();
+        assert!(matches!(error,RaireError::TimeoutCheckingWinner|RaireError::TimeoutFindingAssertions(_)));
+        assert_eq!(format!("Timed out,,,,,,,,{error}"),lines[3]);
+    }
+}